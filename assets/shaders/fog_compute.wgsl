@@ -2,7 +2,7 @@
 struct VisionSourceData {
     position: vec2<f32>,
     radius: f32,
-    shape_type: u32, // 0=Circle, 1=Cone, 2=Rectangle / 0=圆形, 1=扇形, 2=矩形
+    shape_type: u32, // 0=Circle, 1=Cone, 2=Rectangle, 3=Polygon, 4=Annulus, 5=Capsule / 0=圆形, 1=扇形, 2=矩形, 3=多边形, 4=环形, 5=胶囊
     direction: f32, // Original direction, kept for potential other uses or CPU-side logic / 原始方向，保留以备他用或CPU端逻辑
     angle: f32, // Original angle, kept for potential other uses or CPU-side logic / 原始角度，保留以备他用或CPU端逻辑
     intensity: f32, // Vision intensity / 视野强度
@@ -13,7 +13,16 @@ struct VisionSourceData {
     sin_direction: f32,         // Precomputed sin of source.direction / 预计算的 source.direction 的正弦值
     cone_half_angle_cos: f32,   // Precomputed cos(source.angle * 0.5) for cone shape / 为扇形预计算的 cos(source.angle * 0.5)
 
-    _padding1: f32, // Padding to make struct size 48 bytes for alignment / 填充使结构体大小为48字节以对齐
+    eye_height: f32, // Viewer height compared against blocker heights / 与阻挡物高度比较的视野高度
+    team: u32, // FogTeam whose layers this source writes / 此视野源写入的 FogTeam 层
+    polygon_offset: u32, // First vertex in polygon_vertices / polygon_vertices 中的第一个顶点
+    polygon_len: u32, // Polygon vertex count, 0 for other shapes / 多边形顶点数，其他形状为 0
+    shape_param: f32, // Annulus inner radius or capsule length / 环形内半径或胶囊长度
+    falloff_type: u32, // 0=Linear, 1=Smoothstep, 2=Exponential, 3=Step, 4=Curve / 0=线性, 1=平滑, 2=指数, 3=阶跃, 4=采样曲线
+    falloff_param: f32, // Exponential sharpness or curve sample count / 指数锐度或曲线样本数
+    falloff_curve: array<f32, 8>, // Sampled curve, must match `MAX_FALLOFF_SAMPLES` / 采样曲线，必须与 `MAX_FALLOFF_SAMPLES` 一致
+    flags: u32, // VISION_FLAG_* bits / VISION_FLAG_* 标志位
+    _padding: u32,
 };
 
 struct ChunkComputeData {
@@ -22,6 +31,13 @@ struct ChunkComputeData {
     // padding u32
 };
 
+struct OccluderSegmentData {
+    start: vec2<f32>, // World-space segment start / 世界空间线段起点
+    end: vec2<f32>,   // World-space segment end / 世界空间线段终点
+    height: f32,      // Occluder height / 遮挡物高度
+    _padding: f32,
+};
+
 struct FogMapSettings {
     chunk_size: vec2<u32>, // World size of a chunk / 区块的世界大小
     texture_resolution_per_chunk: vec2<u32>, // Texture pixels per chunk / 每区块的纹理像素
@@ -29,11 +45,26 @@ struct FogMapSettings {
     fog_color_explored: vec4<f32>,
     vision_clear_color: vec4<f32>,
     enabled: u32,
-    _padding2: u32,
-    _padding3: u32,
-    _padding4: u32,
+    blocker_grid_enabled: u32, // 1 if a VisionBlockerGrid is present / 存在 VisionBlockerGrid 时为 1
+    blocker_cells_per_chunk: vec2<u32>, // Blocker cells per chunk axis / 每区块每轴的阻挡单元格数
+    team_count: u32, // Fog/visibility layers per chunk slot / 每个区块槽位的雾效/可见性层数
+    shared_vision: u32, // FogAlliances bits, viewer * MAX_TEAMS + source / 同盟位，viewer * MAX_TEAMS + source
+    shared_explored: u32, // Same layout, for explored history / 相同布局，用于探索历史
+    explored_decay: f32, // Explored value lost this frame out of sight / 视野外本帧损失的探索值
+    fog_color_outside: vec4<f32>, // Color outside the world bounds / 世界边界之外的颜色
+    world_bounds: vec4<f32>, // (min.x, min.y, max.x, max.y) / (min.x, min.y, max.x, max.y)
+    world_bounds_enabled: u32, // 1 if world_bounds applies / world_bounds 生效时为 1
+    ground_plane: u32, // 0 = XY, 1 = XZ / 0 = XY，1 = XZ
+    fog_translation: vec2<f32>, // Ground plane -> fog transform, translation / 地面平面 -> 雾效变换的平移
+    fog_matrix: vec4<f32>, // Same transform, linear part as columns (x_axis, y_axis) / 同一变换的线性部分，按列 (x_axis, y_axis)
 };
 
+// Returns true if a world position lies inside the map / 如果世界位置位于地图内则返回 true
+fn is_in_world(world_pos: vec2<f32>) -> bool {
+    return settings.world_bounds_enabled == 0u
+        || (all(world_pos >= settings.world_bounds.xy) && all(world_pos <= settings.world_bounds.zw));
+}
+
 const GFX_INVALID_LAYER: i32 = -1;
 const VISION_TRANSITION_RATIO: f32 = 0.20; // 20% of radius for smooth fade / 半径的 20% 用于平滑淡出
 const EXPLORATION_VISIBILITY_THRESHOLD: f32 = 0.05; // How much visibility is needed to mark as explored / 标记为已探索需要多少可见度
@@ -42,12 +73,198 @@ const EXPLORATION_VISIBILITY_THRESHOLD: f32 = 0.05; // How much visibility is ne
 const SHAPE_CIRCLE: u32 = 0u;
 const SHAPE_CONE: u32 = 1u;
 const SHAPE_RECTANGLE: u32 = 2u;
+const SHAPE_POLYGON: u32 = 3u;
+const SHAPE_ANNULUS: u32 = 4u;
+const SHAPE_CAPSULE: u32 = 5u;
+
+// 视野源标志位，必须与 CPU 端一致 / Vision source flag bits, must match the CPU side
+const VISION_FLAG_EXPLORES: u32 = 1u; // Texels seen by this source become explored / 此视野源看到的纹素会被标记为已探索
+const VISION_FLAG_REVEALS: u32 = 2u; // Source adds to this frame's visibility / 此视野源计入本帧可见度
+
+// 视野衰减曲线常量 / Vision falloff curve constants
+const FALLOFF_LINEAR: u32 = 0u;
+const FALLOFF_SMOOTHSTEP: u32 = 1u;
+const FALLOFF_EXPONENTIAL: u32 = 2u;
+const FALLOFF_STEP: u32 = 3u;
+const FALLOFF_CURVE: u32 = 4u;
 
 @group(0) @binding(0) var fog_texture: texture_storage_2d_array<r8unorm, read_write>; // Stores explored status (0.0 = unexplored, 1.0 = explored) / 存储已探索状态 (0.0 = 未探索, 1.0 = 已探索)
 @group(0) @binding(1) var visibility_texture: texture_storage_2d_array<r8unorm, write>; // Stores current frame visibility (0.0 = not visible, 1.0 = fully visible) / 存储当前帧可见性 (0.0 = 不可见, 1.0 = 完全可见)
 @group(0) @binding(2) var<storage, read> vision_sources: array<VisionSourceData>;
 @group(0) @binding(3) var<storage, read> chunks: array<ChunkComputeData>;
 @group(0) @binding(4) var<uniform> settings: FogMapSettings;
+@group(0) @binding(5) var<storage, read> occluder_segments: array<OccluderSegmentData>;
+@group(0) @binding(6) var vision_blocker_texture: texture_storage_2d_array<r8unorm, read>; // Tile-grid blockers, one layer per GPU chunk / 网格阻挡数据，每个 GPU 区块一层
+@group(0) @binding(7) var<storage, read> polygon_vertices: array<vec2<f32>>; // World-space polygon vision vertices / 世界空间多边形视野顶点
+
+const MAX_TEAMS: u32 = 4u; // Must match `MAX_TEAMS` on the CPU / 必须与 CPU 端的 `MAX_TEAMS` 一致
+const MAX_BLOCKER_STEPS: u32 = 256u; // Upper bound on grid samples per ray / 每条射线的网格采样上限
+const BLOCKER_FULL_HEIGHT: f32 = 255.0; // Grid value that blocks every source / 阻挡所有视野源的网格值
+
+// Distance from p to segment a->b.
+// 点 p 到线段 a->b 的距离
+fn distance_to_segment(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let ab = b - a;
+    let len_sq = dot(ab, ab);
+    var t = 0.0;
+    if (len_sq > 0.0) {
+        t = clamp(dot(p - a, ab) / len_sq, 0.0, 1.0);
+    }
+    return distance(p, a + ab * t);
+}
+
+// Source's falloff curve at t (0 = inner side of the transition zone, 1 = outer edge).
+// Mirrors `VisionFalloff::apply` on the CPU.
+// 视野源在 t 处的衰减曲线值（0 = 过渡区内侧，1 = 外缘），与 CPU 端 `VisionFalloff::apply` 一致
+fn apply_falloff(source_idx: u32, t_in: f32) -> f32 {
+    let t = clamp(t_in, 0.0, 1.0);
+    let falloff_type = vision_sources[source_idx].falloff_type;
+    if (falloff_type == FALLOFF_SMOOTHSTEP) {
+        return 1.0 - smoothstep(0.0, 1.0, t);
+    } else if (falloff_type == FALLOFF_EXPONENTIAL) {
+        let k = max(vision_sources[source_idx].falloff_param, 0.001);
+        return (exp(-k * t) - exp(-k)) / (1.0 - exp(-k));
+    } else if (falloff_type == FALLOFF_STEP) {
+        return select(0.0, 1.0, t < 1.0);
+    } else if (falloff_type == FALLOFF_CURVE) {
+        let count = u32(vision_sources[source_idx].falloff_param);
+        if (count == 0u) {
+            return 1.0 - t;
+        } else if (count == 1u) {
+            return vision_sources[source_idx].falloff_curve[0];
+        }
+        let x = t * f32(count - 1u);
+        let j = min(u32(floor(x)), count - 2u);
+        let a = vision_sources[source_idx].falloff_curve[j];
+        let b = vision_sources[source_idx].falloff_curve[j + 1u];
+        return a + (b - a) * (x - f32(j));
+    }
+    return 1.0 - t;
+}
+
+// Fade from inner_radius (fully visible) out to radius (not visible) along the source's falloff curve.
+// 沿视野源的衰减曲线从 inner_radius（完全可见）衰减到 radius（不可见）
+fn radial_falloff(source_idx: u32, dist: f32, inner_radius: f32, radius: f32) -> f32 {
+    if (dist <= inner_radius) {
+        return 1.0;
+    } else if (dist >= radius) {
+        return 0.0;
+    }
+    return apply_falloff(source_idx, (dist - inner_radius) / (radius - inner_radius));
+}
+
+// Returns true if segment p0->p1 crosses segment a->b.
+// 如果线段 p0->p1 与线段 a->b 相交则返回 true。
+fn segments_intersect(p0: vec2<f32>, p1: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> bool {
+    let r = p1 - p0;
+    let s = b - a;
+    let denom = r.x * s.y - r.y * s.x;
+    // Parallel or degenerate segments never block / 平行或退化线段不阻挡
+    if (abs(denom) < 1e-6) {
+        return false;
+    }
+    let qp = a - p0;
+    let t = (qp.x * s.y - qp.y * s.x) / denom;
+    let u = (qp.x * r.y - qp.y * r.x) / denom;
+    // t is strictly inside so the source and the target texel themselves are not blocked
+    // t 严格位于内部，因此视野源和目标纹素本身不会被阻挡
+    return t > 0.0 && t < 1.0 && u >= 0.0 && u <= 1.0;
+}
+
+// Returns true if `viewer` sees through the vision sources of `source` (FogAlliances).
+// 如果 `viewer` 能通过 `source` 的视野源观察（FogAlliances）则返回 true。
+fn shares_vision(viewer: u32, source: u32) -> bool {
+    if (viewer == source) {
+        return true;
+    }
+    if (viewer >= MAX_TEAMS || source >= MAX_TEAMS) {
+        return false;
+    }
+    return (settings.shared_vision & (1u << (viewer * MAX_TEAMS + source))) != 0u;
+}
+
+// Finds the fog layer of a GPU-resident chunk, or GFX_INVALID_LAYER if it is not resident.
+// 查找 GPU 常驻区块的雾效层，如果不常驻则返回 GFX_INVALID_LAYER。
+fn find_chunk_layer(coords: vec2<i32>) -> i32 {
+    for (var i = 0u; i < arrayLength(&chunks); i = i + 1u) {
+        if (all(chunks[i].coords == coords)) {
+            return chunks[i].fog_layer_index;
+        }
+    }
+    return GFX_INVALID_LAYER;
+}
+
+// Returns true if a grid cell of the given height blocks a viewer at `eye_height`.
+// Must match `VisionBlockerGrid::value_blocks` on the CPU.
+// 如果给定高度的网格单元格阻挡了位于 `eye_height` 的观察者则返回 true。
+// 必须与 CPU 端的 `VisionBlockerGrid::value_blocks` 保持一致。
+fn cell_blocks(cell_height: f32, eye_height: f32) -> bool {
+    return cell_height >= BLOCKER_FULL_HEIGHT || (cell_height > 0.0 && cell_height > eye_height);
+}
+
+// Ray-marches the blocker grid between `from` and `to`.
+// The cells containing the two endpoints are skipped so a unit standing in a blocked
+// cell can still see out, and the face of a wall is itself visible.
+// 在 `from` 与 `to` 之间对阻挡网格进行光线步进。
+// 跳过两个端点所在的单元格，使站在阻挡单元格中的单位仍能向外看到，且墙面本身可见。
+fn is_grid_blocked(from: vec2<f32>, to: vec2<f32>, eye_height: f32) -> bool {
+    if (settings.blocker_grid_enabled == 0u) {
+        return false;
+    }
+
+    let chunk_size_f = vec2<f32>(f32(settings.chunk_size.x), f32(settings.chunk_size.y));
+    let cells = vec2<i32>(i32(settings.blocker_cells_per_chunk.x), i32(settings.blocker_cells_per_chunk.y));
+    let cell_size = chunk_size_f / vec2<f32>(cells);
+
+    let from_cell = vec2<i32>(floor(from / cell_size));
+    let to_cell = vec2<i32>(floor(to / cell_size));
+    let delta = to - from;
+    // Sample twice per cell crossed so thin diagonal walls are not skipped
+    // 每穿过一个单元格采样两次，避免跳过细的对角墙
+    let cells_crossed = max(abs(delta.x) / cell_size.x, abs(delta.y) / cell_size.y);
+    let steps = min(u32(ceil(cells_crossed * 2.0)), MAX_BLOCKER_STEPS);
+
+    var cached_chunk = vec2<i32>(2147483647, 2147483647);
+    var cached_layer = GFX_INVALID_LAYER;
+    for (var i = 1u; i < steps; i = i + 1u) {
+        let p = from + delta * (f32(i) / f32(steps));
+        let cell = vec2<i32>(floor(p / cell_size));
+        if (all(cell == from_cell) || all(cell == to_cell)) {
+            continue;
+        }
+
+        let chunk = vec2<i32>(floor(vec2<f32>(cell) / vec2<f32>(cells)));
+        if (any(chunk != cached_chunk)) {
+            cached_chunk = chunk;
+            cached_layer = find_chunk_layer(chunk);
+        }
+        // Chunks without blocker data on the GPU are treated as open
+        // GPU 上没有阻挡数据的区块视为开放
+        if (cached_layer == GFX_INVALID_LAYER) {
+            continue;
+        }
+
+        let local_cell = cell - chunk * cells;
+        // r8unorm stores height / 255 / r8unorm 存储 高度 / 255
+        let cell_height = round(textureLoad(vision_blocker_texture, local_cell, cached_layer).r * 255.0);
+        if (cell_blocks(cell_height, eye_height)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Returns true if any occluder segment taller than `eye_height` blocks the line of sight from `from` to `to`.
+// 如果任何高于 `eye_height` 的遮挡线段阻挡了从 `from` 到 `to` 的视线则返回 true。
+fn is_line_of_sight_blocked(from: vec2<f32>, to: vec2<f32>, eye_height: f32) -> bool {
+    for (var i = 0u; i < arrayLength(&occluder_segments); i = i + 1u) {
+        let segment = occluder_segments[i];
+        if (segment.height > eye_height && segments_intersect(from, to, segment.start, segment.end)) {
+            return true;
+        }
+    }
+    return false;
+}
 
 @compute @workgroup_size(8, 8, 1)
 fn main(
@@ -60,17 +277,18 @@ fn main(
         return;
     }
 
-    let chunk_index = global_id.z;
-    // Assuming global_id.z directly maps to an index in the chunks array
-    // 假设 global_id.z 直接映射到 chunks 数组中的索引
+    // global_id.z enumerates (chunk, team) pairs; a chunk's team layers are contiguous
+    // global_id.z 枚举 (区块, 队伍) 组合；同一区块的各队伍层是连续的
+    let team_count = max(settings.team_count, 1u);
+    let chunk_index = global_id.z / team_count;
+    let team = global_id.z % team_count;
     if (chunk_index >= arrayLength(&chunks)) { return; } // Bounds check for chunk_index / chunk_index 边界检查
 
     let chunk_data = chunks[chunk_index];
-    let target_layer_idx = chunk_data.fog_layer_index;
-
-    if (target_layer_idx == GFX_INVALID_LAYER) {
+    if (chunk_data.fog_layer_index == GFX_INVALID_LAYER) {
         return;
     }
+    let target_layer_idx = chunk_data.fog_layer_index * i32(team_count) + i32(team);
 
     // pixel_coord_in_chunk is global_id.xy, assuming workgroup processes one chunk
     // 假设工作组处理一个区块，pixel_coord_in_chunk 是 global_id.xy
@@ -90,19 +308,28 @@ fn main(
     // 当前区块纹理部分内的 UV (0.0 到 1.0 范围)
     let uv_in_chunk = (vec2<f32>(global_id.xy) + 0.5) / tex_res_f;
 
-    // World position of the current texel
-    // 当前纹素的世界位置
+    // Fog-space position of the current texel; vision sources and occluders are already
+    // in fog coordinates (FogMapSettings::world_to_fog on the CPU), so no transform is needed here
+    // 当前纹素的雾效空间位置；视野源和遮挡物已在 CPU 端转换为雾效坐标，因此此处无需变换
     let world_pos_xy = chunk_world_origin + uv_in_chunk * chunk_size_f;
 
     // --- Calculate Current Visibility ---
     // --- 计算当前可见性 ---
     var current_visibility: f32 = 0.0;
-    for (var i = 0u; i < arrayLength(&vision_sources); i = i + 1u) {
+    // Visibility from sources that explore; current_visibility only counts sources that reveal
+    // 来自会探索的视野源的可见度；current_visibility 只计入会揭示的视野源
+    var explore_visibility: f32 = 0.0;
+    // Vision stops at the world bounds, so texels outside are never seen or explored
+    // 视野止于世界边界，边界外的纹素永远不会被看到或探索
+    let source_count = select(0u, arrayLength(&vision_sources), is_in_world(world_pos_xy));
+    for (var i = 0u; i < source_count; i = i + 1u) {
        let source = vision_sources[i];
 
-       // Skip if source is ineffective (e.g. zero radius or intensity)
-       // 如果视野源无效（例如零半径或强度），则跳过
-       if (source.radius <= 0.001 || source.intensity <= 0.001) {
+       // Skip if source is ineffective (e.g. zero radius or intensity, neither reveals nor explores) or not shared with this team
+       // 如果视野源无效（例如零半径或强度、既不揭示也不探索）或未与此队伍共享，则跳过
+       if (source.radius <= 0.001 || source.intensity <= 0.001
+           || (source.flags & (VISION_FLAG_REVEALS | VISION_FLAG_EXPLORES)) == 0u
+           || !shares_vision(team, source.team)) {
            continue;
        }
 
@@ -126,14 +353,7 @@ fn main(
            // 圆形视野 / Circular vision
            // 当距离小于内部半径时，可见性为1.0；当距离大于外部半径时，可见性为0.0
            // Visibility is 1.0 when distance is less than inner radius; 0.0 when distance is greater than outer radius
-           if (dist <= inner_radius) {
-               single_source_visibility = 1.0;
-           } else if (dist >= source.radius) {
-               single_source_visibility = 0.0;
-           } else {
-               // 在过渡区域内平滑过渡 / Smooth transition in the transition area
-               single_source_visibility = 1.0 - ((dist - inner_radius) / (source.radius - inner_radius));
-           }
+           single_source_visibility = radial_falloff(i, dist, inner_radius, source.radius);
        } else if (source.shape_type == SHAPE_CONE) {
            // 扇形视野 / Cone vision
            if (dist <= source.radius) {
@@ -150,11 +370,14 @@ fn main(
                if (dot_product >= half_angle_cos) {
                    // 点在扇形内 / Point is within cone
                    // 计算距离衰减 / Calculate distance attenuation
-                   let dist_visibility = 1.0 - smoothstep(inner_radius, source.radius, dist);
+                   let dist_visibility = radial_falloff(i, dist, inner_radius, source.radius);
 
                    // 计算角度衰减（边缘平滑过渡） / Calculate angle attenuation (smooth transition at edges)
                    let angle_t = (dot_product - half_angle_cos) / (1.0 - half_angle_cos);
-                   let angle_visibility = smoothstep(0.0, 0.2, angle_t);
+                   var angle_visibility = 1.0;
+                   if (angle_t < 0.2) {
+                       angle_visibility = apply_falloff(i, 1.0 - angle_t / 0.2);
+                   }
 
                    // 组合距离和角度衰减 / Combine distance and angle attenuation
                    single_source_visibility = dist_visibility * angle_visibility;
@@ -188,10 +411,56 @@ fn main(
                single_source_visibility = 1.0;
            } else if (edge_dist <= inner_edge_dist) {
                // 点在过渡区域 / Point is in transition area
-               single_source_visibility = 1.0 - (edge_dist / inner_edge_dist);
+               single_source_visibility = apply_falloff(i, edge_dist / inner_edge_dist);
+           }
+       } else if (source.shape_type == SHAPE_ANNULUS) {
+           // 环形视野：外缘同圆形衰减，内侧盲区外以相同带宽淡入
+           // Annulus vision: outer edge fades like a circle, fades in past the blind inner disc over the same band
+           let band = source.radius * transition_ratio;
+           var inner_fade = 0.0;
+           if (dist >= source.shape_param + band) {
+               inner_fade = 1.0;
+           } else if (dist > source.shape_param) {
+               inner_fade = apply_falloff(i, 1.0 - (dist - source.shape_param) / band);
+           }
+           single_source_visibility = radial_falloff(i, dist, inner_radius, source.radius) * inner_fade;
+       } else if (source.shape_type == SHAPE_CAPSULE) {
+           // 胶囊视野：按到光束中心线的距离做圆形衰减
+           // Capsule vision: circle falloff measured from the beam's center line
+           let beam_end = source.position + vec2<f32>(source.cos_direction, source.sin_direction) * source.shape_param;
+           let beam_dist = distance_to_segment(world_pos_xy, source.position, beam_end);
+           single_source_visibility = radial_falloff(i, beam_dist, inner_radius, source.radius);
+       } else if (source.shape_type == SHAPE_POLYGON) {
+           // 多边形视野（顶点已在 CPU 端变换到世界空间） / Polygon vision (vertices already in world space)
+           // 奇偶规则判断内部，同时求到边的最近距离 / Even-odd inside test plus nearest edge distance
+           var inside = false;
+           var edge_dist = 1e30;
+           for (var v = 0u; v < source.polygon_len; v = v + 1u) {
+               let a = polygon_vertices[source.polygon_offset + v];
+               let b = polygon_vertices[source.polygon_offset + (v + 1u) % source.polygon_len];
+               if ((a.y > world_pos_xy.y) != (b.y > world_pos_xy.y)
+                   && world_pos_xy.x < a.x + (world_pos_xy.y - a.y) / (b.y - a.y) * (b.x - a.x)) {
+                   inside = !inside;
+               }
+               edge_dist = min(edge_dist, distance_to_segment(world_pos_xy, a, b));
+           }
+
+           // 过渡带宽度与正方形相同：半径 × 过渡比例 / Transition band as for squares: radius × ratio
+           let inner_edge_dist = source.radius * transition_ratio;
+           if (inside) {
+               single_source_visibility = 1.0;
+           } else if (edge_dist <= inner_edge_dist) {
+               single_source_visibility = apply_falloff(i, edge_dist / inner_edge_dist);
            }
        }
 
+       // 视线遮挡（高处的视野源可越过较低的阻挡物） / Line-of-sight occlusion (high sources see over lower blockers)
+       if (single_source_visibility > 0.0
+           && (is_line_of_sight_blocked(source.position, world_pos_xy, source.eye_height)
+               || is_grid_blocked(source.position, world_pos_xy, source.eye_height))) {
+           single_source_visibility = 0.0;
+       }
+
        // 应用强度 / Apply intensity
        var intensity: f32 = source.intensity;
        if (intensity < 0.01) {
@@ -201,11 +470,18 @@ fn main(
 
        // Accumulative blending for multiple vision sources
        // 多个视野源的累积混合
-       current_visibility = current_visibility + single_source_visibility * (1.0 - current_visibility);
-       // Optimization: if current_visibility is already 1.0, no need to check more sources
-       // 优化: 如果 current_visibility 已经是 1.0，则无需检查更多源
+       if ((source.flags & VISION_FLAG_REVEALS) != 0u) {
+           current_visibility = current_visibility + single_source_visibility * (1.0 - current_visibility);
+       }
+       if ((source.flags & VISION_FLAG_EXPLORES) != 0u) {
+           explore_visibility = explore_visibility + single_source_visibility * (1.0 - explore_visibility);
+       }
        if (current_visibility >= 0.999) {
            current_visibility = 1.0;
+       }
+       // Optimization: once both values are saturated, no need to check more sources
+       // 优化: 两个值都已饱和时，无需检查更多源
+       if (current_visibility >= 1.0 && explore_visibility >= 0.999) {
            break;
        }
     }
@@ -219,7 +495,7 @@ fn main(
 
     // If current visibility is high enough and the area is not already fully explored, mark as explored.
     // 如果当前可见度足够高且该区域尚未完全探索，则标记为已探索。
-    if (current_visibility > EXPLORATION_VISIBILITY_THRESHOLD && current_explored_value < 0.999) {
+    if (explore_visibility > EXPLORATION_VISIBILITY_THRESHOLD && current_explored_value < 0.999) {
         // Mark as explored by writing 1.0 to the red channel.
         // 通过向红色通道写入 1.0 来标记为已探索。
         textureStore(fog_texture, pixel_coord_in_chunk, target_layer_idx, vec4<f32>(1.0, 0.0, 0.0, 1.0));
@@ -235,15 +511,16 @@ fn main(
     let previous_explored_value = textureLoad(fog_texture, pixel_coord_in_chunk, target_layer_idx).r;
     var new_explored_value = previous_explored_value;
 
-    if (current_visibility > EXPLORATION_VISIBILITY_THRESHOLD) {
+    if (explore_visibility > EXPLORATION_VISIBILITY_THRESHOLD) {
         // If currently visible enough, mark as fully explored (1.0)
         // This ensures explored areas are definitively marked.
         // 如果当前足够可见，则标记为完全探索 (1.0)
         // 这确保了已探索区域被明确标记。
         new_explored_value = 1.0;
-        // Alternative: allow explored areas to "fade" if not re-seen for a while (more complex)
-        // 备选: 如果一段时间未重新看到，则允许已探索区域“褪色”(更复杂)
-        // new_explored_value = max(previous_explored_value, current_visibility); // If you want explored to reflect max visibility ever seen
+    } else if (current_visibility <= EXPLORATION_VISIBILITY_THRESHOLD && settings.explored_decay > 0.0) {
+        // Out of sight: explored memory fades by this frame's decay step
+        // 不在视野内：探索记忆按本帧的衰减步长褪色
+        new_explored_value = max(previous_explored_value - settings.explored_decay, 0.0);
     }
     // Store updated explored status
     // 存储更新的已探索状态
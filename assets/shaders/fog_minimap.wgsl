@@ -0,0 +1,143 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct OverlayChunkData { // Used for mapping world coord to layer index in textures / 用于将世界坐标映射到纹理中的层索引
+    coords: vec2<i32>,
+    fog_layer_index: i32,        // Layer index for fog_texture (explored) and visibility_texture / fog_texture (已探索) 和 visibility_texture 的层索引
+    snapshot_layer_index: i32, // Layer index for snapshot_texture / snapshot_texture 的层索引
+};
+
+struct FogMapSettings {
+    chunk_size: vec2<u32>,
+    texture_resolution_per_chunk: vec2<u32>,
+    fog_color_unexplored: vec4<f32>,
+    fog_color_explored: vec4<f32>,
+    vision_clear_color: vec4<f32>, // Usually (0,0,0,0) for full transparency / 通常是 (0,0,0,0) 以实现完全透明
+    enabled: u32,
+    blocker_grid_enabled: u32,
+    blocker_cells_per_chunk: vec2<u32>,
+    team_count: u32, // Fog/visibility layers per chunk slot / 每个区块槽位的雾效/可见性层数
+    shared_vision: u32, // FogAlliances bits, viewer * MAX_TEAMS + source / 同盟位，viewer * MAX_TEAMS + source
+    shared_explored: u32, // Same layout, for explored history / 相同布局，用于探索历史
+    explored_decay: f32, // Explored value lost this frame out of sight / 视野外本帧损失的探索值
+    fog_color_outside: vec4<f32>, // Color outside the world bounds / 世界边界之外的颜色
+    world_bounds: vec4<f32>, // (min.x, min.y, max.x, max.y) / (min.x, min.y, max.x, max.y)
+    world_bounds_enabled: u32, // 1 if world_bounds applies / world_bounds 生效时为 1
+    ground_plane: u32, // 0 = XY, 1 = XZ / 0 = XY，1 = XZ
+    fog_translation: vec2<f32>, // Ground plane -> fog transform, translation / 地面平面 -> 雾效变换的平移
+    fog_matrix: vec4<f32>, // Same transform, linear part as columns (x_axis, y_axis) / 同一变换的线性部分，按列 (x_axis, y_axis)
+};
+
+struct Minimap { // FogMinimap being drawn / 正在绘制的 FogMinimap
+    region: vec4<f32>, // (min.x, min.y, max.x, max.y) in fog coordinates / 雾效坐标
+    background: vec4<f32>, // Color under the fog without a snapshot / 没有快照时迷雾下方的颜色
+    team: u32,
+    show_snapshots: u32,
+    _padding1: u32,
+    _padding2: u32,
+};
+
+const GFX_INVALID_LAYER: i32 = -1;
+const MAX_TEAMS: u32 = 4u; // Must match `MAX_TEAMS` on the CPU / 必须与 CPU 端的 `MAX_TEAMS` 一致
+
+// --- Bindings for fog_minimap ---
+// --- fog_minimap 的绑定 ---
+@group(0) @binding(0) var fog_sampler: sampler;
+@group(0) @binding(1) var visibility_tex: texture_2d_array<f32>;
+@group(0) @binding(2) var explored_tex: texture_2d_array<f32>;
+@group(0) @binding(3) var snapshot_tex: texture_2d_array<f32>;
+@group(0) @binding(4) var<uniform> settings: FogMapSettings;
+@group(0) @binding(5) var<storage, read> chunk_mapping: array<OverlayChunkData>;
+@group(1) @binding(0) var<uniform> minimap: Minimap;
+
+// Same transitions as fog_overlay.wgsl / 与 fog_overlay.wgsl 相同的过渡
+const VISIBILITY_THRESHOLD_FULLY_CLEAR: f32 = 0.95;
+const VISIBILITY_THRESHOLD_START_CLEARING: f32 = 0.1;
+const EXPLORED_TRANSITION_WIDTH: f32 = 0.12;
+const EXPLORED_TRANSITION_CENTER: f32 = 0.5;
+
+// Returns true if a fog position lies inside the map / 如果雾效位置位于地图内则返回 true
+fn is_in_world(world_pos: vec2<f32>) -> bool {
+    return settings.world_bounds_enabled == 0u
+        || (all(world_pos >= settings.world_bounds.xy) && all(world_pos <= settings.world_bounds.zw));
+}
+
+// Straight-alpha "over" compositing / 非预乘 alpha 的 "over" 合成
+fn over(top: vec4<f32>, bottom: vec4<f32>) -> vec4<f32> {
+    let alpha = top.a + bottom.a * (1.0 - top.a);
+    if (alpha < 1e-5) {
+        return vec4<f32>(0.0);
+    }
+    let rgb = top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a);
+    return vec4<f32>(rgb / alpha, alpha);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // UV (0,0) is the top-left of the image, the region's min corner is its bottom-left
+    // UV (0,0) 为图像左上角，区域的 min 角对应图像左下角
+    let region_size = minimap.region.zw - minimap.region.xy;
+    let world_pos_xy = minimap.region.xy + vec2<f32>(in.uv.x, 1.0 - in.uv.y) * region_size;
+    if (!is_in_world(world_pos_xy)) {
+        return settings.fog_color_outside;
+    }
+
+    let chunk_size_f = vec2<f32>(f32(settings.chunk_size.x), f32(settings.chunk_size.y));
+    let chunk_coords_f = floor(world_pos_xy / chunk_size_f);
+    let chunk_coords_i = vec2<i32>(i32(chunk_coords_f.x), i32(chunk_coords_f.y));
+
+    var active_fog_layer_idx = GFX_INVALID_LAYER;
+    var active_snapshot_layer_idx = GFX_INVALID_LAYER;
+    for (var i = 0u; i < arrayLength(&chunk_mapping); i = i + 1u) {
+        let map_entry = chunk_mapping[i];
+        if (map_entry.coords.x == chunk_coords_i.x && map_entry.coords.y == chunk_coords_i.y) {
+            active_fog_layer_idx = map_entry.fog_layer_index;
+            active_snapshot_layer_idx = map_entry.snapshot_layer_index;
+            break;
+        }
+    }
+    let uv_in_chunk = fract(world_pos_xy / chunk_size_f);
+
+    // What the overlay would draw over: the snapshot if shown, else the background
+    // 覆盖层下方的内容：显示快照时为快照，否则为背景
+    var scene_color = minimap.background;
+    if (minimap.show_snapshots != 0u && active_snapshot_layer_idx != GFX_INVALID_LAYER) {
+        let snapshot_color = textureSampleLevel(snapshot_tex, fog_sampler, vec2(uv_in_chunk.x, 1.0 - uv_in_chunk.y), active_snapshot_layer_idx, 0.0);
+        if (snapshot_color.a > 0.99) {
+            scene_color = snapshot_color;
+        }
+    }
+
+    if (settings.enabled == 0u) {
+        return scene_color;
+    }
+    if (active_fog_layer_idx == GFX_INVALID_LAYER) {
+        return over(settings.fog_color_unexplored, scene_color);
+    }
+
+    let team_count = max(settings.team_count, 1u);
+    let team_base_layer = active_fog_layer_idx * i32(team_count);
+    let team_layer_idx = team_base_layer + i32(minimap.team);
+    let current_visibility = textureSampleLevel(visibility_tex, fog_sampler, uv_in_chunk, team_layer_idx, 0.0).r;
+    var explored_value_raw = textureSampleLevel(explored_tex, fog_sampler, uv_in_chunk, team_layer_idx, 0.0).r;
+
+    // Merge explored history shared by allied teams (FogAlliances)
+    // 合并盟友共享的探索历史（FogAlliances）
+    for (var team = 0u; team < min(team_count, MAX_TEAMS); team = team + 1u) {
+        let bit = 1u << (minimap.team * MAX_TEAMS + team);
+        if (team != minimap.team && minimap.team < MAX_TEAMS && (settings.shared_explored & bit) != 0u) {
+            let allied_explored = textureSampleLevel(explored_tex, fog_sampler, uv_in_chunk, team_base_layer + i32(team), 0.0).r;
+            explored_value_raw = max(explored_value_raw, allied_explored);
+        }
+    }
+
+    let edge0 = EXPLORED_TRANSITION_CENTER - EXPLORED_TRANSITION_WIDTH / 2.0;
+    let edge1 = EXPLORED_TRANSITION_CENTER + EXPLORED_TRANSITION_WIDTH / 2.0;
+    let alpha_explored = smoothstep(edge0, edge1, explored_value_raw);
+    let clear_factor = smoothstep(VISIBILITY_THRESHOLD_START_CLEARING, VISIBILITY_THRESHOLD_FULLY_CLEAR, current_visibility);
+
+    // Same fog colors as the overlay, blended over the snapshots instead of replaced by them
+    // 与覆盖层相同的迷雾颜色，叠加在快照之上而非被快照替换
+    let visible_or_explored_color = mix(settings.fog_color_explored, settings.vision_clear_color, clear_factor);
+    let fog_color = mix(settings.fog_color_unexplored, visible_or_explored_color, alpha_explored);
+    return over(fog_color, scene_color);
+}
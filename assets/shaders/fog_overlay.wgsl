@@ -19,12 +19,36 @@ struct FogMapSettings {
     fog_color_explored: vec4<f32>,
     vision_clear_color: vec4<f32>, // Usually (0,0,0,0) for full transparency / 通常是 (0,0,0,0) 以实现完全透明
     enabled: u32,
+    blocker_grid_enabled: u32,
+    blocker_cells_per_chunk: vec2<u32>,
+    team_count: u32, // Fog/visibility layers per chunk slot / 每个区块槽位的雾效/可见性层数
+    shared_vision: u32, // FogAlliances bits, viewer * MAX_TEAMS + source / 同盟位，viewer * MAX_TEAMS + source
+    shared_explored: u32, // Same layout, for explored history / 相同布局，用于探索历史
+    explored_decay: f32, // Explored value lost this frame out of sight / 视野外本帧损失的探索值
+    fog_color_outside: vec4<f32>, // Color outside the world bounds / 世界边界之外的颜色
+    world_bounds: vec4<f32>, // (min.x, min.y, max.x, max.y) / (min.x, min.y, max.x, max.y)
+    world_bounds_enabled: u32, // 1 if world_bounds applies / world_bounds 生效时为 1
+    ground_plane: u32, // 0 = XY, 1 = XZ / 0 = XY，1 = XZ
+    fog_translation: vec2<f32>, // Ground plane -> fog transform, translation / 地面平面 -> 雾效变换的平移
+    fog_matrix: vec4<f32>, // Same transform, linear part as columns (x_axis, y_axis) / 同一变换的线性部分，按列 (x_axis, y_axis)
+};
+
+// Returns true if a world position lies inside the map / 如果世界位置位于地图内则返回 true
+fn is_in_world(world_pos: vec2<f32>) -> bool {
+    return settings.world_bounds_enabled == 0u
+        || (all(world_pos >= settings.world_bounds.xy) && all(world_pos <= settings.world_bounds.zw));
+}
+
+struct ViewFogTeam { // FogTeam rendered by the current view / 当前视图渲染的 FogTeam
+    team: u32,
+    _padding1: u32,
     _padding2: u32,
     _padding3: u32,
-    _padding4: u32,
 };
 
 const GFX_INVALID_LAYER: i32 = -1;
+const GROUND_PLANE_XY: u32 = 0u; // Must match `FogGroundPlane` on the CPU / 必须与 CPU 端的 `FogGroundPlane` 一致
+const MAX_TEAMS: u32 = 4u; // Must match `MAX_TEAMS` on the CPU / 必须与 CPU 端的 `MAX_TEAMS` 一致
 
 // --- Bindings for fog_overlay ---
 // --- fog_overlay 的绑定 ---
@@ -35,6 +59,7 @@ const GFX_INVALID_LAYER: i32 = -1;
 @group(0) @binding(4) var snapshot_tex: texture_2d_array<f32>;       // Snapshot of explored areas / 已探索区域的快照
 @group(0) @binding(5) var<uniform> settings: FogMapSettings;
 @group(0) @binding(6) var<storage, read> chunk_mapping: array<OverlayChunkData>; // Chunk coord -> layer indices / 区块坐标 -> 层索引
+@group(1) @binding(0) var<uniform> view_fog: ViewFogTeam;
 
 
 // --- Constants for Blending ---
@@ -55,6 +80,24 @@ const EXPLORED_TRANSITION_WIDTH: f32 = 0.12; // Adjust this value (e.g., 0.05 to
 // 过渡的中心点。由于 explored_tex 是 0 或 1，线性采样将使边界平均值在 0.5 左右。
 const EXPLORED_TRANSITION_CENTER: f32 = 0.5;
  
+// Height of a world position above the ground plane / 世界位置在地面平面上方的高度
+fn ground_height(world_pos: vec3<f32>) -> f32 {
+    if (settings.ground_plane == GROUND_PLANE_XY) {
+        return world_pos.z;
+    }
+    return world_pos.y;
+}
+
+// Fog coordinates of a world position, see `FogMapSettings::world_to_fog` / 世界位置的雾效坐标
+fn ground_to_fog(world_pos: vec3<f32>) -> vec2<f32> {
+    var plane_pos = vec2<f32>(world_pos.x, -world_pos.z);
+    if (settings.ground_plane == GROUND_PLANE_XY) {
+        plane_pos = world_pos.xy;
+    }
+    let fog_matrix = mat2x2<f32>(settings.fog_matrix.xy, settings.fog_matrix.zw);
+    return fog_matrix * plane_pos + settings.fog_translation;
+}
+
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     if (settings.enabled == 0u) {
@@ -64,9 +107,19 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     let screen_uv = in.uv;
     
     let ndc = uv_to_ndc(screen_uv);
-    let ndc_pos = vec3<f32>(ndc, 0.0);
-    let world_pos = position_ndc_to_world(ndc_pos);
-    let world_pos_xy = world_pos.xy; // Use only xy for 2D comparison
+    // Intersect this pixel's view ray with the ground plane; depth 1 is the near plane (reverse-Z)
+    // 将该像素的视线与地面平面求交；深度 1 为近平面（反向 Z）
+    let ray_origin = position_ndc_to_world(vec3<f32>(ndc, 1.0));
+    let ray_direction = position_ndc_to_world(vec3<f32>(ndc, 0.5)) - ray_origin;
+    let rise = ground_height(ray_direction);
+    let hit = -ground_height(ray_origin) / rise;
+    if (abs(rise) < 1e-6 || hit < 0.0) {
+        return vec4<f32>(0.0, 0.0, 0.0, 0.0); // Ray misses the ground (sky) / 射线未命中地面（天空）
+    }
+    let world_pos_xy = ground_to_fog(ray_origin + ray_direction * hit); // Fog coordinates on the plane / 平面上的雾效坐标
+    if (!is_in_world(world_pos_xy)) {
+        return settings.fog_color_outside;
+    }
 
     let chunk_size_f = vec2<f32>(f32(settings.chunk_size.x), f32(settings.chunk_size.y));
     let chunk_coords_f = floor(world_pos_xy / chunk_size_f);
@@ -92,12 +145,26 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
           return settings.fog_color_unexplored;
     }
     let uv_in_chunk = fract(world_pos_xy / chunk_size_f);
+    // Each chunk slot holds one fog/visibility layer per team / 每个区块槽位为每个队伍保留一层雾效/可见性
+    let team_count = max(settings.team_count, 1u);
+    let team_base_layer = active_fog_layer_idx * i32(team_count);
+    let team_layer_idx = team_base_layer + i32(view_fog.team);
 
 
     // Sample visibility and explored status using LINEAR filtering for smooth transitions
     // 使用线性过滤采样可见性和已探索状态，以实现平滑过渡
-    let current_visibility = textureSample(visibility_tex, fog_sampler, uv_in_chunk, active_fog_layer_idx).r;
-    let explored_value_raw = textureSample(explored_tex, fog_sampler, uv_in_chunk, active_fog_layer_idx).r; // Value is 0.0 to 1.0 due to linear sampling / 由于线性采样，值在 0.0 到 1.0 之间
+    let current_visibility = textureSample(visibility_tex, fog_sampler, uv_in_chunk, team_layer_idx).r;
+    var explored_value_raw = textureSample(explored_tex, fog_sampler, uv_in_chunk, team_layer_idx).r; // Value is 0.0 to 1.0 due to linear sampling / 由于线性采样，值在 0.0 到 1.0 之间
+
+    // Merge explored history shared by allied teams (FogAlliances)
+    // 合并盟友共享的探索历史（FogAlliances）
+    for (var team = 0u; team < min(team_count, MAX_TEAMS); team = team + 1u) {
+        let bit = 1u << (view_fog.team * MAX_TEAMS + team);
+        if (team != view_fog.team && view_fog.team < MAX_TEAMS && (settings.shared_explored & bit) != 0u) {
+            let allied_explored = textureSample(explored_tex, fog_sampler, uv_in_chunk, team_base_layer + i32(team)).r;
+            explored_value_raw = max(explored_value_raw, allied_explored);
+        }
+    }
 
     // --- Smooth transition from Unexplored to Explored ---
     // --- 从未探索平滑过渡到已探索 ---
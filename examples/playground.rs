@@ -186,6 +186,10 @@ fn setup(
             angle: std::f32::consts::FRAC_PI_2,
             intensity: 1.0,
             transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Linear,
+            reveals: true,
+            explores: true,
         },
     ));
 
@@ -217,11 +221,41 @@ fn setup(
             angle: std::f32::consts::FRAC_PI_2,
             intensity: 1.0,
             transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Linear,
+            reveals: true,
+            explores: true,
         },
         MovableVision,
         Player,
     ));
 
+    // 生成阻挡视线的墙体
+    // Spawn a wall that blocks line of sight
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.35, 0.3, 0.25),
+            custom_size: Some(Vec2::new(20.0, 160.0)),
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(-80.0, -220.0, 0.0)),
+        VisionOccluder::rect(Vec2::new(10.0, 80.0)),
+        Capturable,
+    ));
+
+    // 生成低矮的篱笆，眼睛高度不低于 1.0 的视野源可以越过它
+    // Spawn a low hedge that sources with an eye height of at least 1.0 can see over
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.25, 0.45, 0.2),
+            custom_size: Some(Vec2::new(120.0, 10.0)),
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(-200.0, -300.0, 0.0)),
+        VisionOccluder::rect(Vec2::new(60.0, 5.0)).with_height(1.0),
+        Capturable,
+    ));
+
     // 生成水平来回移动的 Sprite
     // Spawn horizontally moving sprite
     commands.spawn((
@@ -281,6 +315,10 @@ fn setup(
                     angle: std::f32::consts::FRAC_PI_2,
                     intensity: 1.0,
                     transition_ratio: 0.2,
+                    eye_height: 0.0,
+                    falloff: VisionFalloff::Smoothstep,
+                    reveals: true,
+                    explores: true,
                 },
                 RotationAble,
             ));
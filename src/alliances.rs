@@ -0,0 +1,137 @@
+//! Runtime alliance table controlling which teams share vision and explored history.
+//! 控制队伍之间共享视野与探索历史的运行时同盟表
+//!
+//! Every [`FogTeam`] keeps its own fog layers. [`FogAlliances`] decides, per pair of
+//! teams, whether one team's vision sources also clear fog for the other, and whether
+//! one team's explored history is shown to the other. The table is read every frame
+//! by both the CPU chunk pass and the GPU compute/overlay passes, so changes take
+//! effect on the next frame. Explored state stays stored per team, so saves are never
+//! merged.
+
+use crate::prelude::*;
+
+/// Alliance matrix between fog teams.
+/// 雾效队伍之间的同盟矩阵
+///
+/// Relations are directional: `viewer` receives something from `source`. A team
+/// always shares with itself. Teams at or above [`MAX_TEAMS`] are ignored.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn form_alliance(mut alliances: ResMut<FogAlliances>) {
+///     // Teams 0 and 1 see each other's units' vision
+///     alliances.set_allied(FogTeam(0), FogTeam(1), true);
+///     // Team 1 also gets team 0's map knowledge
+///     alliances.set_shared_explored(FogTeam(1), FogTeam(0), true);
+/// }
+/// ```
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource, Default)]
+pub struct FogAlliances {
+    /// Bit `viewer * MAX_TEAMS + source` is set when `viewer` sees through `source`'s vision.
+    shared_vision: u32,
+    /// Bit `viewer * MAX_TEAMS + source` is set when `viewer` sees `source`'s explored history.
+    shared_explored: u32,
+}
+
+impl FogAlliances {
+    /// Returns the matrix bit for a `(viewer, source)` pair, or `None` if a team is out of range.
+    fn bit(viewer: FogTeam, source: FogTeam) -> Option<u32> {
+        let (viewer, source) = (u32::from(viewer.0), u32::from(source.0));
+        (viewer < MAX_TEAMS && source < MAX_TEAMS).then(|| 1 << (viewer * MAX_TEAMS + source))
+    }
+
+    fn set_bit(bits: &mut u32, viewer: FogTeam, source: FogTeam, value: bool) {
+        if let Some(bit) = Self::bit(viewer, source) {
+            if value {
+                *bits |= bit;
+            } else {
+                *bits &= !bit;
+            }
+        }
+    }
+
+    /// Makes two teams share vision in both directions, or breaks that alliance.
+    /// 使两个队伍双向共享视野，或解除该同盟
+    pub fn set_allied(&mut self, a: FogTeam, b: FogTeam, allied: bool) {
+        self.set_shared_vision(a, b, allied);
+        self.set_shared_vision(b, a, allied);
+    }
+
+    /// Lets `viewer` see through the vision sources of `source`.
+    /// 允许 `viewer` 通过 `source` 的视野源观察
+    pub fn set_shared_vision(&mut self, viewer: FogTeam, source: FogTeam, shared: bool) {
+        Self::set_bit(&mut self.shared_vision, viewer, source, shared);
+    }
+
+    /// Shows the explored history of `source` to `viewer`.
+    /// 向 `viewer` 显示 `source` 的探索历史
+    pub fn set_shared_explored(&mut self, viewer: FogTeam, source: FogTeam, shared: bool) {
+        Self::set_bit(&mut self.shared_explored, viewer, source, shared);
+    }
+
+    /// Returns true if `viewer` sees through the vision sources of `source`.
+    /// 如果 `viewer` 能通过 `source` 的视野源观察则返回 true
+    pub fn shares_vision(&self, viewer: FogTeam, source: FogTeam) -> bool {
+        viewer == source
+            || Self::bit(viewer, source).is_some_and(|bit| self.shared_vision & bit != 0)
+    }
+
+    /// Returns true if `viewer` sees the explored history of `source`.
+    /// 如果 `viewer` 能看到 `source` 的探索历史则返回 true
+    pub fn shares_explored(&self, viewer: FogTeam, source: FogTeam) -> bool {
+        viewer == source
+            || Self::bit(viewer, source).is_some_and(|bit| self.shared_explored & bit != 0)
+    }
+
+    /// Clears every alliance.
+    /// 清除所有同盟关系
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Packed shared-vision matrix as uploaded to the shaders.
+    pub(crate) fn shared_vision_bits(&self) -> u32 {
+        self.shared_vision
+    }
+
+    /// Packed shared-explored matrix as uploaded to the shaders.
+    pub(crate) fn shared_explored_bits(&self) -> u32 {
+        self.shared_explored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alliance_is_symmetric_and_revocable() {
+        let mut alliances = FogAlliances::default();
+        assert!(alliances.shares_vision(FogTeam(2), FogTeam(2)));
+        assert!(!alliances.shares_vision(FogTeam(0), FogTeam(1)));
+
+        alliances.set_allied(FogTeam(0), FogTeam(1), true);
+        assert!(alliances.shares_vision(FogTeam(0), FogTeam(1)));
+        assert!(alliances.shares_vision(FogTeam(1), FogTeam(0)));
+        assert!(!alliances.shares_explored(FogTeam(1), FogTeam(0)));
+
+        alliances.set_allied(FogTeam(1), FogTeam(0), false);
+        assert!(!alliances.shares_vision(FogTeam(0), FogTeam(1)));
+    }
+
+    #[test]
+    fn test_shared_explored_is_directional() {
+        let mut alliances = FogAlliances::default();
+        alliances.set_shared_explored(FogTeam(3), FogTeam(0), true);
+        assert!(alliances.shares_explored(FogTeam(3), FogTeam(0)));
+        assert!(!alliances.shares_explored(FogTeam(0), FogTeam(3)));
+        assert_eq!(alliances.shared_explored_bits(), 1 << (3 * MAX_TEAMS));
+
+        // Out-of-range teams are ignored
+        alliances.set_shared_vision(FogTeam(200), FogTeam(0), true);
+        assert_eq!(alliances.shared_vision_bits(), 0);
+    }
+}
@@ -2,23 +2,61 @@
 
 use crate::prelude::*;
 use bevy_asset::{Assets, Handle, RenderAssetUsages};
+use bevy_camera::visibility::RenderLayers;
 use bevy_image::{Image, ImageSampler, ImageSamplerDescriptor, TextureFormatPixelInfo};
 use bevy_math::{IVec2, Rect, UVec2, Vec2};
 use bevy_reflect::prelude::ReflectDefault;
 use bevy_render::extract_component::ExtractComponent;
 use bevy_render::render_resource::{Extent3d, TextureDimension, TextureUsages};
+use bevy_transform::components::GlobalTransform;
 use std::fmt::Display;
 
 /// Marks cameras that should render fog of war.
+///
+/// Every active tagged camera keeps the chunks in its view resident, so several can
+/// be used for split-screen or picture-in-picture; a [`FogTeam`] on each picks whose
+/// fog it renders.
+///
+/// Any render target works: windows, offscreen `Image`s and manual texture views,
+/// with or without HDR. Cameras without this marker only count when none is tagged,
+/// and then only the first one rendering to a window.
 #[derive(Component)]
 pub struct FogOfWarCamera;
 
+/// Team or faction a `VisionSource` reveals fog for, or whose fog a camera renders.
+/// 视野源所属的队伍/阵营，或相机所渲染的队伍雾效
+///
+/// Each team has its own explored and visibility layers, so sources only clear fog
+/// for their own team. Entities without a `FogTeam` belong to team `0`. Team ids
+/// must be below [`FogMapSettings::team_count`]; sources of other teams are ignored.
+#[derive(
+    Component,
+    Reflect,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[reflect(Component, Default)]
+pub struct FogTeam(pub u8);
+
 /// Component that reveals fog of war in a specified area.
-/// Supports circle, cone, and square vision shapes.
-#[derive(Component, Reflect, ExtractComponent, Clone)]
+/// Supports circle, cone, square, polygon, annulus, and capsule vision shapes.
+///
+/// The shape is evaluated in fog coordinates around the source's fog position, so
+/// `FogMapSettings::fog_transform` moves the source but does not scale, skew or
+/// rotate its range, polygon vertices or direction.
+#[derive(Component, Reflect, ExtractComponent, Clone, Debug)]
 #[reflect(Component)]
 pub struct VisionSource {
-    /// Vision range in world units (radius for circle/cone, half-width for square).
+    /// Vision range in fog units (radius for circle/cone, half-width for square, outer
+    /// radius for annulus, beam half-width for capsule). Polygons take their extent from
+    /// their vertices instead.
     pub range: f32,
 
     /// Whether this vision source is currently active.
@@ -27,7 +65,7 @@ pub struct VisionSource {
     /// The geometric shape of the vision area.
     pub shape: VisionShape,
 
-    /// Direction for cone vision in radians (0 = right, π/2 = up). Ignored for other shapes.
+    /// Direction in radians (0 = right, π/2 = up). Aims cones and capsules, rotates squares and polygons.
     pub direction: f32,
 
     /// Cone vision angle in radians (total angle). Ignored for non-cone shapes.
@@ -38,6 +76,21 @@ pub struct VisionSource {
 
     /// Transition zone ratio for smooth fog edges (0.0 = hard, 0.2 = soft).
     pub transition_ratio: f32,
+
+    /// Height of the viewer's eyes above the ground (default: 0.0).
+    /// Blockers lower than or equal to this height are seen over.
+    pub eye_height: f32,
+
+    /// Curve used across the transition zone of every shape edge (default: linear, smoothstep for cones).
+    pub falloff: VisionFalloff,
+
+    /// Whether the source clears fog live this frame (default: true).
+    /// Turn off for sources that should only mark areas explored, e.g. a map-reveal item.
+    pub reveals: bool,
+
+    /// Whether what the source sees becomes permanently explored (default: true).
+    /// Turn off for sources that should only show areas live, e.g. a spectator peek.
+    pub explores: bool,
 }
 
 impl VisionSource {
@@ -51,6 +104,10 @@ impl VisionSource {
             angle: std::f32::consts::FRAC_PI_2,
             intensity: 1.0,
             transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Linear,
+            reveals: true,
+            explores: true,
         }
     }
 
@@ -66,6 +123,10 @@ impl VisionSource {
             angle,
             intensity: 1.0,
             transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Smoothstep,
+            reveals: true,
+            explores: true,
         }
     }
 
@@ -80,8 +141,106 @@ impl VisionSource {
             angle: std::f32::consts::FRAC_PI_2,
             intensity: 1.0,
             transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Linear,
+            reveals: true,
+            explores: true,
+        }
+    }
+
+    /// Creates a polygon vision source from local-space vertices.
+    /// The polygon is rotated by `direction`; `range` is set to its farthest vertex.
+    pub fn polygon(vertices: Vec<Vec2>) -> Self {
+        let shape = VisionShape::Polygon { vertices };
+        Self {
+            range: shape.polygon_radius().unwrap_or(0.0),
+            enabled: true,
+            shape,
+            direction: 0.0,
+            angle: std::f32::consts::FRAC_PI_2,
+            intensity: 1.0,
+            transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Linear,
+            reveals: true,
+            explores: true,
         }
     }
+
+    /// Creates a ring-shaped vision source that reveals from `min_range` to `max_range`.
+    pub fn annulus(min_range: f32, max_range: f32) -> Self {
+        Self {
+            range: max_range,
+            enabled: true,
+            shape: VisionShape::Annulus {
+                inner_radius: min_range,
+            },
+            direction: 0.0,
+            angle: std::f32::consts::FRAC_PI_2,
+            intensity: 1.0,
+            transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Linear,
+            reveals: true,
+            explores: true,
+        }
+    }
+
+    /// Creates a beam-shaped vision source.
+    /// - `range`: Half-width of the beam
+    /// - `length`: Distance the beam's center line extends from the source
+    /// - `direction`: Beam direction in radians (0 = right, π/2 = up)
+    pub fn capsule(range: f32, length: f32, direction: f32) -> Self {
+        Self {
+            range,
+            enabled: true,
+            shape: VisionShape::Capsule { length },
+            direction,
+            angle: std::f32::consts::FRAC_PI_2,
+            intensity: 1.0,
+            transition_ratio: 0.2,
+            eye_height: 0.0,
+            falloff: VisionFalloff::Linear,
+            reveals: true,
+            explores: true,
+        }
+    }
+
+    /// Sets the eye height, letting the source see over lower blockers.
+    pub fn with_eye_height(mut self, eye_height: f32) -> Self {
+        self.eye_height = eye_height;
+        self
+    }
+
+    /// Sets the falloff curve used across the transition zone.
+    pub fn with_falloff(mut self, falloff: VisionFalloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Sets whether the source clears fog live.
+    pub fn with_reveals(mut self, reveals: bool) -> Self {
+        self.reveals = reveals;
+        self
+    }
+
+    /// Sets whether the source marks what it sees as explored.
+    pub fn with_explores(mut self, explores: bool) -> Self {
+        self.explores = explores;
+        self
+    }
+
+    /// Sets the direction in radians.
+    pub fn with_direction(mut self, direction: f32) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Radius of the shape before its transition band: `range`, or the farthest
+    /// vertex for polygons.
+    pub fn outer_radius(&self) -> f32 {
+        self.shape.polygon_radius().unwrap_or(self.range)
+    }
 }
 
 /// Geometric shape types for vision areas.
@@ -94,14 +253,19 @@ impl VisionSource {
 /// # Performance Comparison
 /// - **Circle**: Moderate cost, most versatile
 /// - **Square**: Lowest cost, simple calculations
-/// - **Cone**: Highest cost, complex angle math
+/// - **Cone**: Highest fixed cost, complex angle math
+/// - **Polygon**: Cost grows linearly with the vertex count
+/// - **Annulus / Capsule**: Close to circle, one extra distance term
 ///
 /// # Shape Characteristics
 /// Each shape interprets the `range` parameter differently:
 /// - **Circle**: `range` = radius
 /// - **Cone**: `range` = radius at maximum distance
 /// - **Square**: `range` = half-width (center to edge)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+/// - **Polygon**: `range` is ignored; the vertices define the extent
+/// - **Annulus**: `range` = outer radius
+/// - **Capsule**: `range` = half-width around the beam's center line
+#[derive(Debug, Clone, PartialEq, Default, Reflect)]
 #[reflect(Default)]
 pub enum VisionShape {
     /// Circular 360-degree omnidirectional vision.
@@ -111,8 +275,99 @@ pub enum VisionShape {
     /// Directional cone-shaped vision with angular limits.
     Cone,
 
-    /// Axis-aligned square vision area.
+    /// Square vision area, rotated by `direction`.
     Square,
+
+    /// Arbitrary simple polygon in local space, rotated by `direction`.
+    /// The transition band extends outward by `transition_ratio` × the farthest vertex distance.
+    Polygon { vertices: Vec<Vec2> },
+
+    /// Ring between `inner_radius` and `range`, e.g. artillery minimum to maximum range.
+    /// Both edges fade over `transition_ratio` × `range`, towards the inside of the ring.
+    Annulus { inner_radius: f32 },
+
+    /// Beam from the source along `direction`, `length` long and `range` wide on each side.
+    /// The edge fades like a circle's, measured from the center line.
+    Capsule { length: f32 },
+}
+
+impl VisionShape {
+    /// Distance of the farthest polygon vertex, or `None` for other shapes.
+    pub fn polygon_radius(&self) -> Option<f32> {
+        match self {
+            VisionShape::Polygon { vertices } => {
+                Some(vertices.iter().map(|v| v.length()).fold(0.0, f32::max))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Maximum number of samples a `VisionFalloff::Curve` can hold on the GPU.
+pub const MAX_FALLOFF_SAMPLES: usize = 8;
+
+/// How visibility fades across the transition zone at a vision shape's edges.
+/// 视野形状边缘过渡区域内的可见度衰减曲线
+///
+/// Every shape maps a point in its transition zone to `t`, from `0.0` at the inner
+/// (fully visible) side to `1.0` at the outer edge, and uses the curve to turn `t`
+/// into visibility. Cone edges fade angularly with the same curve. Points outside
+/// the shape are never visible, whatever the curve returns at `t = 1.0`.
+#[derive(Debug, Clone, PartialEq, Default, Reflect)]
+#[reflect(Default)]
+pub enum VisionFalloff {
+    /// `1 - t`.
+    #[default]
+    Linear,
+
+    /// `1 - smoothstep(0, 1, t)`, soft at both ends of the zone.
+    Smoothstep,
+
+    /// Exponential decay normalised to reach zero at the edge.
+    /// Higher `sharpness` drops off faster near the inner side.
+    Exponential { sharpness: f32 },
+
+    /// Hard edge: fully visible up to the outer edge of the shape.
+    Step,
+
+    /// Visibility sampled at evenly spaced `t`, linearly interpolated.
+    /// Only the first [`MAX_FALLOFF_SAMPLES`] samples are used.
+    Curve { samples: Vec<f32> },
+}
+
+impl VisionFalloff {
+    /// Visibility at `t` within the transition zone, `t` clamped to `0.0..=1.0`.
+    /// Matches `apply_falloff` in the fog compute shader.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            VisionFalloff::Linear => 1.0 - t,
+            VisionFalloff::Smoothstep => 1.0 - t * t * (3.0 - 2.0 * t),
+            VisionFalloff::Exponential { sharpness } => {
+                let k = sharpness.max(0.001);
+                ((-k * t).exp() - (-k).exp()) / (1.0 - (-k).exp())
+            }
+            VisionFalloff::Step => {
+                if t < 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            VisionFalloff::Curve { samples } => {
+                let samples = &samples[..samples.len().min(MAX_FALLOFF_SAMPLES)];
+                match samples.len() {
+                    0 => 1.0 - t,
+                    1 => samples[0],
+                    n => {
+                        let x = t * (n - 1) as f32;
+                        let i = (x.floor() as usize).min(n - 2);
+                        samples[i] + (samples[i + 1] - samples[i]) * (x - i as f32)
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl Default for VisionSource {
@@ -125,10 +380,247 @@ impl Default for VisionSource {
             angle: std::f32::consts::FRAC_PI_2, // 默认90度扇形 / Default 90 degree cone
             intensity: 1.0,
             transition_ratio: 0.2, // 默认20%的过渡区域 / Default 20% transition area
+            eye_height: 0.0,       // 默认位于地面 / Default at ground level
+            falloff: VisionFalloff::default(),
+            reveals: true,
+            explores: true,
         }
     }
 }
 
+/// Component that blocks line of sight for every `VisionSource`.
+/// 阻挡所有视野源视线的遮挡物组件
+///
+/// Geometry is described in the entity's local space and transformed by its
+/// `GlobalTransform` every frame, so occluders can move, rotate and scale freely.
+/// Texels behind an occluder (as seen from a vision source) receive no visibility
+/// from that source in the fog compute pass.
+///
+/// Occluders are infinitely tall by default. Give them a finite `height` to let
+/// sources whose `eye_height` is at least that tall see over them.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct VisionOccluder {
+    /// The blocking geometry in local space.
+    pub shape: OccluderShape,
+
+    /// Whether this occluder currently blocks vision.
+    pub enabled: bool,
+
+    /// Height of the occluder. Only sources with a lower `eye_height` are blocked.
+    pub height: f32,
+}
+
+impl VisionOccluder {
+    /// Creates an occluder from a single wall segment.
+    pub fn segment(start: Vec2, end: Vec2) -> Self {
+        Self {
+            shape: OccluderShape::Segment { start, end },
+            enabled: true,
+            height: f32::MAX,
+        }
+    }
+
+    /// Creates an occluder from a closed polygon outline.
+    pub fn polygon(vertices: Vec<Vec2>) -> Self {
+        Self {
+            shape: OccluderShape::Polygon { vertices },
+            enabled: true,
+            height: f32::MAX,
+        }
+    }
+
+    /// Creates an axis-aligned box occluder centered on the entity.
+    /// `half_size` is the distance from the center to each edge.
+    pub fn rect(half_size: Vec2) -> Self {
+        Self {
+            shape: OccluderShape::Rect { half_size },
+            enabled: true,
+            height: f32::MAX,
+        }
+    }
+
+    /// Sets a finite height so that sources with an `eye_height` at or above it see over.
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Returns true if this occluder blocks a viewer with the given eye height.
+    pub fn blocks_eye_height(&self, eye_height: f32) -> bool {
+        self.height > eye_height
+    }
+
+    /// Returns the occluder outline as world-space segments.
+    /// 以世界空间线段形式返回遮挡物轮廓
+    pub fn world_segments(&self, transform: &GlobalTransform) -> Vec<(Vec2, Vec2)> {
+        self.fog_segments(transform, &FogMapSettings::default())
+    }
+
+    /// Returns the occluder outline as segments in fog coordinates.
+    /// The shape's local points lie on the entity's own copy of the ground plane.
+    /// 以雾效坐标线段形式返回遮挡物轮廓
+    pub fn fog_segments(
+        &self,
+        transform: &GlobalTransform,
+        settings: &FogMapSettings,
+    ) -> Vec<(Vec2, Vec2)> {
+        let plane = settings.ground_plane;
+        let to_world =
+            |p: Vec2| settings.world_to_fog(transform.transform_point(plane.to_world(p, 0.0)));
+        let outline: Vec<Vec2> = match &self.shape {
+            OccluderShape::Segment { start, end } => {
+                return vec![(to_world(*start), to_world(*end))];
+            }
+            OccluderShape::Polygon { vertices } => vertices.iter().map(|v| to_world(*v)).collect(),
+            OccluderShape::Rect { half_size } => [
+                Vec2::new(-half_size.x, -half_size.y),
+                Vec2::new(half_size.x, -half_size.y),
+                Vec2::new(half_size.x, half_size.y),
+                Vec2::new(-half_size.x, half_size.y),
+            ]
+            .into_iter()
+            .map(to_world)
+            .collect(),
+        };
+
+        if outline.len() < 2 {
+            return Vec::new();
+        }
+        (0..outline.len())
+            .map(|i| (outline[i], outline[(i + 1) % outline.len()]))
+            .collect()
+    }
+}
+
+/// Geometric shapes a `VisionOccluder` can use.
+/// 遮挡物形状
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub enum OccluderShape {
+    /// A single line segment (thin wall).
+    Segment { start: Vec2, end: Vec2 },
+
+    /// A closed polygon; the last vertex connects back to the first.
+    Polygon { vertices: Vec<Vec2> },
+
+    /// An axis-aligned box (in local space) with the given half extents.
+    Rect { half_size: Vec2 },
+}
+
+/// Hides an entity depending on the fog state at its position.
+/// 根据实体所在位置的雾效状态隐藏实体
+///
+/// Every frame the plugin evaluates the entity's `GlobalTransform` against the
+/// vision of the team rendered by the `FogOfWarCamera` (team `0` if the camera has
+/// no [`FogTeam`]) and shows or hides it according to the policy for the resulting
+/// [`ChunkVisibility`]:
+/// - **Visible**: the position is lit by a shared vision source, using the same
+///   shapes, falloff and occlusion as the fog shader
+/// - **Explored**: not lit, but its chunk was explored by the team or an ally
+///   sharing explored history (chunk granularity)
+/// - **Unexplored**: anything else
+///
+/// Entities whose own `FogTeam` shares vision with the viewing team always count as
+/// visible. A [`Stealth`] entity not detected by the viewing team is never visible. The component is only written back when the state or policy changes.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn spawn(mut commands: Commands) {
+///     // Enemy unit: only drawn while in vision
+///     commands.spawn((Transform::default(), FogHideable::default(), FogTeam(1)));
+///     // Building: stays drawn once its area has been explored
+///     commands.spawn((Transform::default(), FogHideable::default().show_when_explored(true)));
+/// }
+/// ```
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Component, Default)]
+pub struct FogHideable {
+    /// Show the entity while its position is visible.
+    pub visible: bool,
+
+    /// Show the entity while its position is explored but not visible.
+    pub explored: bool,
+
+    /// Show the entity while its position is unexplored.
+    pub unexplored: bool,
+
+    /// How the entity is shown or hidden.
+    pub method: FogHideMethod,
+
+    /// Fog state applied on the last update, `None` before the first update.
+    state: Option<ChunkVisibility>,
+}
+
+impl Default for FogHideable {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            explored: false,
+            unexplored: false,
+            method: FogHideMethod::default(),
+            state: None,
+        }
+    }
+}
+
+impl FogHideable {
+    /// Sets whether the entity is shown while its position is explored but not visible.
+    pub fn show_when_explored(mut self, show: bool) -> Self {
+        self.explored = show;
+        self
+    }
+
+    /// Sets whether the entity is shown while its position is unexplored.
+    pub fn show_when_unexplored(mut self, show: bool) -> Self {
+        self.unexplored = show;
+        self
+    }
+
+    /// Sets how the entity is shown or hidden.
+    pub fn with_method(mut self, method: FogHideMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Returns true if the policy shows the entity in the given fog state.
+    pub fn shows(&self, state: ChunkVisibility) -> bool {
+        match state {
+            ChunkVisibility::Visible => self.visible,
+            ChunkVisibility::Explored => self.explored,
+            ChunkVisibility::Unexplored => self.unexplored,
+        }
+    }
+
+    /// Fog state at the entity's position as of the last update.
+    /// 最近一次更新时实体位置的雾效状态
+    pub fn state(&self) -> Option<ChunkVisibility> {
+        self.state
+    }
+
+    pub(crate) fn set_state(&mut self, state: ChunkVisibility) {
+        self.state = Some(state);
+    }
+}
+
+/// How a `FogHideable` entity is shown or hidden.
+/// `FogHideable` 实体的显示/隐藏方式
+#[derive(Reflect, Clone, Debug, Default, PartialEq)]
+pub enum FogHideMethod {
+    /// Toggle `Visibility` between `Inherited` and `Hidden`.
+    #[default]
+    Visibility,
+
+    /// Swap the entity's `RenderLayers`, e.g. to keep it visible to a minimap camera.
+    RenderLayers {
+        /// Layers while shown.
+        shown: RenderLayers,
+        /// Layers while hidden.
+        hidden: RenderLayers,
+    },
+}
+
 /// Visibility state enumeration for fog of war chunks.
 /// 区块的可见性状态
 ///
@@ -496,6 +988,8 @@ pub struct FogChunkImage {
     /// Handle to the fog texture showing current visibility.
     /// 雾效纹理句柄，显示当前可见性
     ///
+    /// Holds one array layer per team (`FogMapSettings::team_count`).
+    ///
     /// This texture is continuously updated by GPU compute shaders to reflect
     /// real-time visibility changes as vision sources move and are enabled/disabled.
     ///
@@ -587,13 +1081,13 @@ impl FogChunkImage {
     /// let chunk_image = FogChunkImage::from_setting_raw(&mut images, &settings);
     /// ```
     pub fn from_setting_raw(images: &mut Assets<Image>, setting: &FogMapSettings) -> Self {
-        // Create fog texture with zero-filled initial data
+        // Create fog texture with zero-filled initial data, one layer per team
         let data = vec![0u8; setting.fog_texture_format.pixel_size().unwrap_or(0)];
         let mut fog_image = Image::new_fill(
             Extent3d {
                 width: setting.texture_resolution_per_chunk.x,
                 height: setting.texture_resolution_per_chunk.y,
-                depth_or_array_layers: 1,
+                depth_or_array_layers: setting.effective_team_count(),
             },
             TextureDimension::D2,
             &data,
@@ -668,6 +668,12 @@ pub struct ResetCheckpoint {
     /// 相机视图区块集合的备份
     /// Backup of camera view chunks set
     pub camera_view_chunks: std::collections::HashSet<IVec2>,
+    /// 各队伍探索区块集合的备份
+    /// Backup of per-team explored chunks
+    pub team_explored_chunks: std::collections::HashMap<FogTeam, std::collections::HashSet<IVec2>>,
+    /// 各队伍可见区块集合的备份
+    /// Backup of per-team visible chunks
+    pub team_visible_chunks: std::collections::HashMap<FogTeam, std::collections::HashSet<IVec2>>,
     /// 检查点创建时间
     /// Checkpoint creation time
     pub created_at: u64,
@@ -0,0 +1,291 @@
+//! Explored fog decaying back to unexplored ("memory fades").
+//! 已探索迷雾逐渐衰减回未探索状态（“记忆消退”）
+//!
+//! When [`FogMapSettings::explored_decay_rate`] is above zero, the explored value
+//! of every texel out of sight drops by that much per second. Decay is counted in
+//! whole steps of the 8-bit fog texture (`1/255`), so slow rates still make progress
+//! instead of being rounded away each frame.
+//!
+//! The same step count drives three places:
+//! - the compute shader, which subtracts the frame's step from texels out of sight;
+//! - CPU-resident chunks, which are decayed by the steps they missed when uploaded;
+//! - [`ChunkStateCache`], where a chunk returns to unexplored for a team once no
+//!   source of that team has touched it for a full 255 steps.
+
+use crate::prelude::*;
+use bevy_asset::Assets;
+use bevy_image::Image;
+use bevy_math::IVec2;
+use bevy_time::Time;
+use std::collections::HashMap;
+
+/// Number of decay steps that take a texel from fully explored to unexplored.
+const FULL_DECAY_STEPS: u64 = u8::MAX as u64;
+
+/// Decay clock shared by the main world, the render world and CPU-resident chunks.
+/// 主世界、渲染世界与 CPU 驻留区块共享的衰减时钟
+#[derive(Resource, Debug, Default)]
+pub(crate) struct ExploredDecay {
+    /// Fraction of a step accumulated but not applied yet.
+    carry: f32,
+    /// Steps applied this frame.
+    step: u32,
+    /// Steps applied since startup.
+    clock: u64,
+    /// Clock reading at which each team's explored chunk was last touched.
+    last_touched: HashMap<FogTeam, HashMap<IVec2, u64>>,
+    /// Clock reading at which each chunk was copied back to the CPU.
+    cpu_marks: HashMap<IVec2, u64>,
+}
+
+impl ExploredDecay {
+    /// Explored value the compute shader subtracts this frame.
+    pub(crate) fn frame_amount(&self) -> f32 {
+        self.step as f32 / FULL_DECAY_STEPS as f32
+    }
+
+    /// Remembers when a chunk's fog data left the GPU.
+    pub(crate) fn mark_downloaded(&mut self, coords: IVec2) {
+        self.cpu_marks.insert(coords, self.clock);
+    }
+
+    /// Applies the decay a CPU-resident chunk missed to its fog data before upload.
+    pub(crate) fn decay_on_upload(&mut self, coords: IVec2, fog_data: &mut [u8]) {
+        let Some(mark) = self.cpu_marks.remove(&coords) else {
+            return;
+        };
+        let steps = self.clock.saturating_sub(mark).min(FULL_DECAY_STEPS) as u8;
+        if steps > 0 {
+            fog_data
+                .iter_mut()
+                .for_each(|value| *value = value.saturating_sub(steps));
+        }
+    }
+
+    fn tick(&mut self, rate: f32, delta: f32) {
+        self.carry += rate.max(0.0) * delta * FULL_DECAY_STEPS as f32;
+        let steps = self.carry.floor();
+        self.carry -= steps;
+        self.step = steps as u32;
+        self.clock += u64::from(self.step);
+    }
+}
+
+/// Ages explored chunks and returns fully decayed ones to unexplored.
+/// 推进已探索区块的衰减，并将完全衰减的区块恢复为未探索
+///
+/// Runs after `update_chunk_visibility`, so chunks touched this frame restart
+/// their decay. A chunk that no team has explored any more has its CPU-side
+/// fog and snapshot data cleared; if it is on the GPU its snapshot layer is
+/// cleared through [`RequestCleanChunkSnapshot`], while the fog layers have already
+/// decayed to zero in the compute shader.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_explored_decay(
+    time: Res<Time>,
+    settings: Res<FogMapSettings>,
+    mut decay: ResMut<ExploredDecay>,
+    mut cache: ResMut<ChunkStateCache>,
+    chunk_manager: Res<ChunkEntityManager>,
+    chunks: Query<(&FogChunk, &FogChunkImage)>,
+    mut images: ResMut<Assets<Image>>,
+    mut clean_snapshots: MessageWriter<RequestCleanChunkSnapshot>,
+) {
+    if !settings.explored_decay_enabled() {
+        decay.step = 0;
+        return;
+    }
+    decay.tick(settings.explored_decay_rate, time.delta_secs());
+
+    let ExploredDecay {
+        clock,
+        last_touched,
+        cpu_marks,
+        ..
+    } = &mut *decay;
+    let clock = *clock;
+
+    for (team, touched) in &cache.team_touched_chunks {
+        let marks = last_touched.entry(*team).or_default();
+        for &coords in touched {
+            marks.insert(coords, clock);
+        }
+    }
+
+    let mut expired = Vec::new();
+    for (team, explored) in &cache.team_explored_chunks {
+        let marks = last_touched.entry(*team).or_default();
+        marks.retain(|coords, _| explored.contains(coords));
+        for &coords in explored {
+            // Chunks explored without being touched (e.g. loaded from a save) start decaying now
+            // 未被触及却已探索的区块（例如从存档加载）从现在开始衰减
+            let mark = *marks.entry(coords).or_insert(clock);
+            if clock - mark >= FULL_DECAY_STEPS {
+                expired.push((*team, coords));
+            }
+        }
+    }
+
+    for (team, coords) in expired {
+        if let Some(marks) = last_touched.get_mut(&team) {
+            marks.remove(&coords);
+        }
+        if !cache.forget_explored(team, coords) {
+            continue;
+        }
+
+        // Discard the chunk's CPU copy so it comes back blank
+        // 丢弃区块的 CPU 副本，使其恢复为空白
+        cpu_marks.remove(&coords);
+        let Some((chunk, chunk_image)) = chunk_manager
+            .map
+            .get(&coords)
+            .and_then(|entity| chunks.get(*entity).ok())
+        else {
+            continue;
+        };
+        if chunk.state.memory_location != ChunkMemoryLocation::Cpu {
+            // The GPU snapshot layer would keep showing the stale picture
+            // GPU 快照层会继续显示过时的画面
+            clean_snapshots.write(RequestCleanChunkSnapshot(coords));
+        }
+        for handle in [
+            &chunk_image.fog_image_handle,
+            &chunk_image.snapshot_image_handle,
+        ] {
+            if let Some(mut image) = images.get_mut(handle) {
+                if let Some(data) = image.data.as_mut() {
+                    data.fill(0);
+                }
+            }
+        }
+        trace!("Chunk {:?} fully decayed back to unexplored.", coords);
+    }
+
+    cpu_marks.retain(|coords, _| cache.explored_chunks.contains(coords));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::{SnapshotClearQueue, handle_clean_chunk_snapshot_events};
+    use bevy_math::Rect;
+    use std::time::Duration;
+
+    #[test]
+    fn test_slow_decay_accumulates_whole_steps() {
+        let mut decay = ExploredDecay::default();
+        // 0.1 per second at 60 FPS is well under one 8-bit step per frame
+        for _ in 0..60 {
+            decay.tick(0.1, 1.0 / 60.0);
+        }
+        assert!((25..=26).contains(&decay.clock));
+
+        decay.mark_downloaded(IVec2::ZERO);
+        for _ in 0..300 {
+            decay.tick(0.1, 1.0 / 60.0);
+        }
+        let mut fog = vec![255u8, 200, 10];
+        decay.decay_on_upload(IVec2::ZERO, &mut fog);
+        assert!((127..=128).contains(&fog[0]));
+        assert_eq!(fog[0] - fog[1], 55);
+        assert_eq!(fog[2], 0);
+
+        // The mark is consumed by the upload
+        let mut fog = vec![255u8];
+        decay.decay_on_upload(IVec2::ZERO, &mut fog);
+        assert_eq!(fog[0], 255);
+    }
+
+    #[test]
+    fn test_expired_gpu_chunk_clears_its_snapshot() {
+        let settings = FogMapSettings {
+            explored_decay_rate: 1.0,
+            ..Default::default()
+        };
+        let mut app = App::new();
+        app.insert_resource(settings.clone())
+            .init_resource::<Time>()
+            .init_resource::<ExploredDecay>()
+            .init_resource::<ChunkStateCache>()
+            .init_resource::<ChunkEntityManager>()
+            .init_resource::<Assets<Image>>()
+            .init_resource::<MainWorldSnapshotRequestQueue>()
+            .init_resource::<SnapshotClearQueue>()
+            .add_message::<RequestCleanChunkSnapshot>()
+            .add_systems(
+                Update,
+                (update_explored_decay, handle_clean_chunk_snapshot_events).chain(),
+            );
+
+        let chunk_image = FogChunkImage::from_setting_raw(
+            &mut app.world_mut().resource_mut::<Assets<Image>>(),
+            &settings,
+        );
+        let snapshot_handle = chunk_image.snapshot_image_handle.clone();
+        {
+            let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+            let mut snapshot = images.get_mut(&snapshot_handle).unwrap();
+            snapshot.data.as_mut().unwrap().fill(255);
+        }
+        let mut chunk = FogChunk::new(IVec2::ZERO, settings.chunk_size, 1.0);
+        chunk.fog_layer_index = Some(2);
+        chunk.snapshot_layer_index = Some(3);
+        chunk.state.memory_location = ChunkMemoryLocation::Gpu;
+        let entity = app.world_mut().spawn((chunk, chunk_image)).id();
+        app.world_mut()
+            .resource_mut::<ChunkEntityManager>()
+            .map
+            .insert(IVec2::ZERO, entity);
+        app.world_mut()
+            .resource_mut::<ChunkStateCache>()
+            .mark_explored(FogTeam(0), IVec2::ZERO);
+        app.world_mut()
+            .resource_mut::<MainWorldSnapshotRequestQueue>()
+            .requests
+            .push(MainWorldSnapshotRequest {
+                chunk_coords: IVec2::ZERO,
+                snapshot_layer_index: 3,
+                world_bounds: Rect::new(0.0, 0.0, 1.0, 1.0),
+            });
+
+        // Touched on the first frame, then out of sight for a full decay
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+        assert!(
+            app.world()
+                .resource::<SnapshotClearQueue>()
+                .layers
+                .is_empty()
+        );
+        app.world_mut()
+            .resource_mut::<ChunkStateCache>()
+            .team_touched_chunks
+            .clear();
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+
+        let world = app.world();
+        assert!(
+            !world
+                .resource::<ChunkStateCache>()
+                .explored_chunks
+                .contains(&IVec2::ZERO)
+        );
+        assert_eq!(world.resource::<SnapshotClearQueue>().layers, vec![3]);
+        assert!(
+            world
+                .resource::<MainWorldSnapshotRequestQueue>()
+                .requests
+                .is_empty()
+        );
+        let snapshot = world
+            .resource::<Assets<Image>>()
+            .get(&snapshot_handle)
+            .unwrap();
+        assert!(snapshot.data.as_ref().unwrap().iter().all(|&v| v == 0));
+    }
+}
@@ -0,0 +1,290 @@
+//! Last-seen "ghost" markers for entities that left vision.
+//! 实体离开视野后留下的“最后目击”幽灵标记
+//!
+//! Snapshots keep what an explored chunk looked like, but not where a given entity
+//! was when it was last seen. Tagging an entity with [`FogGhost`] makes the plugin
+//! spawn a separate ghost entity carrying [`FogLastSeen`] and the entity's last
+//! visible transform whenever it leaves vision. The ghost is removed once the tracked
+//! entity is seen again, once the ghost's own location comes back into sight, or once
+//! that location is no longer explored (fog reset, explored decay).
+
+use crate::prelude::*;
+use bevy_time::Time;
+use bevy_transform::components::{GlobalTransform, Transform};
+
+/// Tags an entity whose last-seen position is remembered while it is out of vision.
+/// 标记需要在离开视野后记住其最后目击位置的实体
+///
+/// Visibility is evaluated at the entity's `GlobalTransform` for `viewer` (the team
+/// rendered by the `FogOfWarCamera` when `None`), with the same rules as
+/// [`FogHideable`], so undetected [`Stealth`] entities count as out of vision.
+/// Entities whose own [`FogTeam`] shares vision with the viewer are
+/// always visible and never leave a ghost.
+///
+/// The ghost is a plain entity with [`FogLastSeen`] and a `Transform`; add visuals to
+/// it by reacting to `Added<FogLastSeen>`. It outlives the tracked entity, so a base
+/// destroyed out of sight is still marked until its location is scouted again.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn spawn(mut commands: Commands) {
+///     commands.spawn((Transform::default(), FogGhost::default(), FogTeam(1)));
+/// }
+///
+/// fn draw_ghosts(mut commands: Commands, ghosts: Query<Entity, Added<FogLastSeen>>) {
+///     for ghost in &ghosts {
+///         commands.entity(ghost).insert(Sprite::from_color(
+///             Color::srgba(1.0, 0.2, 0.2, 0.4),
+///             Vec2::splat(24.0),
+///         ));
+///     }
+/// }
+/// ```
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Component, Default)]
+pub struct FogGhost {
+    /// Team whose memory the ghost belongs to, `None` for the viewing team.
+    /// 幽灵所属记忆的队伍，`None` 表示观察队伍
+    pub viewer: Option<FogTeam>,
+
+    /// Whether the entity was visible on the last update.
+    visible: bool,
+
+    /// World transform of the entity the last time it was visible.
+    last_seen: Option<Transform>,
+
+    /// `Time::elapsed_secs` the last time the entity was visible.
+    last_seen_at: f32,
+
+    /// Ghost entity standing in for the entity while it is out of vision.
+    ghost: Option<Entity>,
+}
+
+impl FogGhost {
+    /// Sets the team whose memory the ghost belongs to.
+    pub fn with_viewer(mut self, viewer: FogTeam) -> Self {
+        self.viewer = Some(viewer);
+        self
+    }
+
+    /// Returns true if the entity was visible on the last update.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// World transform of the entity the last time it was visible, `None` if never seen.
+    /// 实体最后一次可见时的世界变换，从未被看到时为 `None`
+    pub fn last_seen(&self) -> Option<&Transform> {
+        self.last_seen.as_ref()
+    }
+
+    /// `Time::elapsed_secs` the last time the entity was visible.
+    pub fn last_seen_at(&self) -> f32 {
+        self.last_seen_at
+    }
+
+    /// Ghost entity currently standing in for this entity, if any.
+    /// 当前代替该实体的幽灵实体（如果有）
+    pub fn ghost(&self) -> Option<Entity> {
+        self.ghost
+    }
+}
+
+/// Marker left at the last-seen transform of a [`FogGhost`] entity.
+/// 留在 [`FogGhost`] 实体最后目击位置的标记
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct FogLastSeen {
+    /// The tracked entity. It may have been despawned since.
+    /// 被追踪的实体，可能已经被销毁
+    pub entity: Entity,
+
+    /// Team whose memory this ghost belongs to.
+    /// 该幽灵所属记忆的队伍
+    pub team: FogTeam,
+
+    /// `Time::elapsed_secs` when the entity was last seen here.
+    /// 实体最后一次在此处被看到时的 `Time::elapsed_secs`
+    pub seen_at: f32,
+
+    /// Whether the location has been out of sight since the ghost appeared.
+    out_of_sight: bool,
+}
+
+/// Entities that leave ghosts, with their team and stealth level.
+type TrackedGhostsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static mut FogGhost,
+        Option<&'static FogTeam>,
+        Option<&'static Stealth>,
+    ),
+>;
+
+/// Spawns ghosts for `FogGhost` entities that left vision and removes stale ones.
+/// 为离开视野的 `FogGhost` 实体生成幽灵，并移除过期的幽灵
+///
+/// An entity stepping just past the edge of vision leaves its ghost on a spot that
+/// is still visible, so a ghost is only removed by its location being seen after
+/// that location has been out of sight at least once.
+pub(crate) fn update_fog_ghosts(
+    mut commands: Commands,
+    time: Res<Time>,
+    fog: FogQuery,
+    mut tracked: TrackedGhostsQuery,
+    mut ghosts: Query<(Entity, &Transform, &mut FogLastSeen)>,
+) {
+    if tracked.is_empty() && ghosts.is_empty() {
+        return;
+    }
+
+    let default_viewer = fog.viewer_team();
+    let sampler = fog.sampler();
+    let settings = fog.settings();
+
+    for (ghost_entity, transform, mut last_seen) in ghosts.iter_mut() {
+        let position = settings.world_to_fog(transform.translation);
+        let stale = if !fog.is_explored_for(last_seen.team, position) {
+            true
+        } else if sampler.is_visible(last_seen.team, position) {
+            last_seen.out_of_sight
+        } else {
+            if !last_seen.out_of_sight {
+                last_seen.out_of_sight = true;
+            }
+            false
+        };
+        if !stale {
+            continue;
+        }
+
+        commands.entity(ghost_entity).despawn();
+        if let Ok((_, _, mut tracker, _, _)) = tracked.get_mut(last_seen.entity) {
+            if tracker.ghost == Some(ghost_entity) {
+                tracker.ghost = None;
+            }
+        }
+    }
+
+    let now = time.elapsed_secs();
+    for (entity, transform, mut tracker, team, stealth) in tracked.iter_mut() {
+        let viewer = tracker.viewer.unwrap_or(default_viewer);
+        let position = settings.world_to_fog(transform.translation());
+
+        if sampler.sees_entity(viewer, position, team.copied(), stealth) {
+            tracker.visible = true;
+            tracker.last_seen = Some(transform.compute_transform());
+            tracker.last_seen_at = now;
+            if let Some(ghost) = tracker.ghost.take() {
+                if ghosts.contains(ghost) {
+                    commands.entity(ghost).despawn();
+                }
+            }
+        } else if tracker.visible {
+            tracker.visible = false;
+            let Some(last_seen) = tracker.last_seen else {
+                continue;
+            };
+            let ghost = commands
+                .spawn((
+                    FogLastSeen {
+                        entity,
+                        team: viewer,
+                        seen_at: tracker.last_seen_at,
+                        out_of_sight: false,
+                    },
+                    last_seen,
+                ))
+                .id();
+            trace!(
+                "Entity {:?} left vision, ghost {:?} spawned.",
+                entity, ghost
+            );
+            tracker.ghost = Some(ghost);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_math::{IVec2, Vec3};
+
+    fn ghost_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<FogMapSettings>()
+            .init_resource::<ChunkStateCache>()
+            .init_resource::<FogAlliances>()
+            .init_resource::<ActiveReveals>()
+            .init_resource::<Time>()
+            .add_systems(Update, update_fog_ghosts);
+        app.world_mut()
+            .resource_mut::<ChunkStateCache>()
+            .mark_explored(FogTeam(0), IVec2::ZERO);
+        app
+    }
+
+    fn move_to(app: &mut App, entity: Entity, x: f32, y: f32) {
+        *app.world_mut().get_mut::<GlobalTransform>(entity).unwrap() =
+            GlobalTransform::from_xyz(x, y, 0.0);
+    }
+
+    fn last_seen(app: &mut App) -> Vec<(Entity, Vec3)> {
+        app.world_mut()
+            .query::<(Entity, &Transform, &FogLastSeen)>()
+            .iter(app.world())
+            .map(|(entity, transform, _)| (entity, transform.translation))
+            .collect()
+    }
+
+    #[test]
+    fn test_ghost_follows_vision_and_outlives_its_entity() {
+        let mut app = ghost_app();
+        let eye = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, VisionSource::circle(100.0)))
+            .id();
+        let enemy = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_xyz(10.0, 0.0, 0.0),
+                FogGhost::default(),
+                FogTeam(1),
+            ))
+            .id();
+
+        app.update();
+        assert!(app.world().get::<FogGhost>(enemy).unwrap().is_visible());
+        assert!(last_seen(&mut app).is_empty());
+
+        // Leaving vision drops a ghost where the enemy was last seen
+        move_to(&mut app, enemy, 200.0, 0.0);
+        app.update();
+        let ghosts = last_seen(&mut app);
+        assert_eq!(ghosts.len(), 1);
+        assert_eq!(ghosts[0].1, Vec3::new(10.0, 0.0, 0.0));
+        let ghost = ghosts[0].0;
+        assert_eq!(
+            app.world().get::<FogGhost>(enemy).unwrap().ghost(),
+            Some(ghost)
+        );
+
+        // The ghost stays while its spot is out of sight, even once the enemy is gone
+        move_to(&mut app, eye, 1000.0, 1000.0);
+        app.update();
+        app.world_mut().despawn(enemy);
+        app.update();
+        assert_eq!(last_seen(&mut app).len(), 1);
+        assert_eq!(app.world().get::<FogLastSeen>(ghost).unwrap().entity, enemy);
+
+        // Scouting the spot again clears it
+        move_to(&mut app, eye, 0.0, 0.0);
+        app.update();
+        assert!(last_seen(&mut app).is_empty());
+    }
+}
@@ -56,12 +56,15 @@
 //! ```
 
 use self::prelude::*;
+use crate::line_of_sight::{collect_occluder_segments, is_rect_occluded};
 use crate::persistence::FogOfWarPersistencePlugin;
 use crate::render::FogOfWarRenderPlugin;
+use crate::visibility::{shape_intersects_rect, shape_reach};
 use bevy_asset::{Assets, RenderAssetUsages};
+use bevy_camera::visibility::Visibility;
 use bevy_camera::{Camera, Projection, RenderTarget};
 use bevy_image::{Image, ImageSampler, ImageSamplerDescriptor};
-use bevy_math::{IVec2, Rect, Vec2};
+use bevy_math::{Affine2, IVec2, Rect, UVec2, Vec2};
 use bevy_platform::collections::HashSet;
 use bevy_render::extract_component::ExtractComponentPlugin;
 use bevy_render::extract_resource::ExtractResourcePlugin;
@@ -69,16 +72,27 @@ use bevy_render::render_resource::{Extent3d, TextureDimension, TextureUsages};
 use bevy_time::Time;
 use bevy_transform::components::GlobalTransform;
 
+mod alliances;
 mod components;
 mod data_transfer;
+mod decay;
+mod ghost;
+mod line_of_sight;
 mod managers;
+mod minimap;
+mod observed;
 pub mod persistence;
 pub mod persistence_utils;
 pub mod prelude;
 mod render;
+mod reveal;
 mod settings;
 mod snapshot;
+mod stats;
+mod stealth;
 mod texture_handles;
+mod visibility;
+mod vision_blocker;
 
 /// Event to request a snapshot for a specific chunk.
 /// 请求为特定区块生成快照的事件。
@@ -124,9 +138,15 @@ pub enum FogSystems {
     ///
     /// This system set includes:
     /// - `clear_per_frame_caches` - Clears frame-specific data
-    /// - `update_chunk_visibility` - Processes vision sources
+    /// - `update_reveal_areas` - Ages `RevealArea`s and starts new ones
+    /// - `update_chunk_visibility` - Processes vision sources and reveal areas
+    /// - `update_explored_decay` - Returns long-unseen chunks to unexplored
     /// - `update_camera_view_chunks` - Updates camera view area
-    /// - `update_chunk_component_state` - Syncs cache to components
+    /// - `update_chunk_component_state` - Syncs cache to components and reports chunk transitions
+    /// - `update_fog_stats` - Refreshes `FogStats`
+    /// - `update_stealth_detection` - Tracks which teams detect `Stealth` entities
+    /// - `update_fog_hideables` - Shows or hides `FogHideable` entities
+    /// - `update_fog_ghosts` - Leaves `FogLastSeen` ghosts where `FogGhost` entities were last seen
     ///
     /// **Complexity**: O(V×C) where V=vision sources, C=chunks in vision range
     UpdateChunkState,
@@ -219,6 +239,16 @@ impl Plugin for FogOfWarPlugin {
     /// **Space**: O(T) where T=maximum texture array layers
     fn build(&self, app: &mut App) {
         app.register_type::<VisionSource>()
+            .register_type::<VisionOccluder>()
+            .register_type::<FogTeam>()
+            .register_type::<FogAlliances>()
+            .register_type::<FogHideable>()
+            .register_type::<FogGhost>()
+            .register_type::<Stealth>()
+            .register_type::<FogObserved>()
+            .register_type::<Detection>()
+            .register_type::<FogLastSeen>()
+            .register_type::<FogMinimap>()
             .register_type::<FogChunk>()
             .register_type::<Capturable>()
             .register_type::<ForceSnapshotCapturables>()
@@ -228,6 +258,8 @@ impl Plugin for FogOfWarPlugin {
             // .register_type::<FogMapSettings>()
             .register_type::<FogTextureArray>()
             .register_type::<SnapshotTextureArray>()
+            .register_type::<VisionBlockerTextureArray>()
+            .register_type::<VisionBlockerGrid>()
             .register_type::<ChunkEntityManager>()
             .register_type::<ChunkStateCache>()
             .register_type::<TextureArrayManager>()
@@ -239,6 +271,14 @@ impl Plugin for FogOfWarPlugin {
         app.init_resource::<FogMapSettings>()
             .init_resource::<ChunkEntityManager>()
             .init_resource::<ChunkStateCache>()
+            .init_resource::<FogAlliances>()
+            .init_resource::<ActiveReveals>()
+            .init_resource::<decay::ExploredDecay>()
+            .init_resource::<FogStats>()
+            .init_resource::<stats::FogReadbackRequests>()
+            .init_resource::<stats::FogReadbacksInFlight>()
+            .init_resource::<stats::PendingExploredFractions>()
+            .init_resource::<minimap::FogMinimapViews>()
             .init_resource::<GpuToCpuCopyRequests>()
             .init_resource::<CpuToGpuCopyRequests>()
             .init_resource::<MainWorldSnapshotRequestQueue>()
@@ -249,11 +289,25 @@ impl Plugin for FogOfWarPlugin {
             .add_message::<RequestChunkSnapshot>() // Added event for remaking snapshots / 添加用于重制快照的事件
             .add_message::<ResetFogOfWar>() // Added event for resetting fog of war / 添加用于重置雾效的事件
             .add_message::<FogResetSuccess>() // Added event for successful reset / 添加用于成功重置的事件
-            .add_message::<FogResetFailed>(); // Added event for failed reset / 添加用于失败重置的事件
+            .add_message::<FogResetFailed>() // Added event for failed reset / 添加用于失败重置的事件
+            .add_message::<RevealArea>()
+            .add_message::<EntityDetected>()
+            .add_message::<EntityUndetected>()
+            .add_message::<EntityEnteredVision>()
+            .add_message::<EntityLeftVision>()
+            .add_message::<EntityExplored>()
+            .add_message::<ChunkExplored>()
+            .add_message::<ChunkBecameVisible>()
+            .add_message::<ChunkLeftVision>()
+            .add_message::<RequestExploredFraction>()
+            .add_message::<ExploredFractionReady>()
+            .add_message::<stats::FogLayerReadback>();
 
         app.add_plugins(ExtractResourcePlugin::<GpuToCpuCopyRequests>::default())
             .add_plugins(ExtractResourcePlugin::<CpuToGpuCopyRequests>::default())
             .add_plugins(ExtractResourcePlugin::<FogResetSync>::default())
+            .add_plugins(ExtractResourcePlugin::<stats::FogReadbackRequests>::default())
+            .add_plugins(ExtractResourcePlugin::<minimap::FogMinimapViews>::default())
             .add_plugins(ExtractComponentPlugin::<SnapshotCamera>::default());
 
         app.configure_sets(
@@ -273,14 +327,35 @@ impl Plugin for FogOfWarPlugin {
             Update,
             (
                 clear_per_frame_caches,
+                reveal::update_reveal_areas,
                 update_chunk_visibility,
+                decay::update_explored_decay,
                 update_camera_view_chunks,
                 update_chunk_component_state,
+                stats::update_fog_readbacks,
+                stats::update_fog_stats,
+                stealth::update_stealth_detection,
+                update_fog_hideables,
+                ghost::update_fog_ghosts,
             )
                 .chain()
                 .in_set(FogSystems::UpdateChunkState),
         );
 
+        // Report vision changes once this frame's chunk state is final
+        // 在本帧区块状态确定后报告视野变化
+        app.add_systems(
+            Update,
+            observed::update_fog_observed.after(FogSystems::UpdateChunkState),
+        );
+
+        // Collect minimaps to redraw from this frame's fog state
+        // 根据本帧的迷雾状态收集需要重绘的小地图
+        app.add_systems(
+            Update,
+            minimap::update_fog_minimaps.after(FogSystems::UpdateChunkState),
+        );
+
         app.add_systems(
             Update,
             (manage_chunk_entities).in_set(FogSystems::ManageEntities),
@@ -291,6 +366,20 @@ impl Plugin for FogOfWarPlugin {
             manage_chunk_texture_transfer.in_set(FogSystems::PrepareTransfers),
         );
 
+        // Measure explored fractions against this frame's chunk residency
+        // 根据本帧的区块驻留状态测量已探索比例
+        app.add_systems(
+            Update,
+            stats::process_explored_fraction_requests.after(FogSystems::PrepareTransfers),
+        );
+
+        // Upload blocker cells once chunk layers are settled for this frame
+        // 在本帧区块层分配确定后上传阻挡单元格
+        app.add_systems(
+            Update,
+            vision_blocker::sync_vision_blocker_texture.after(FogSystems::PrepareTransfers),
+        );
+
         app.add_systems(Update, (reset_fog_of_war_system, monitor_reset_sync_system));
 
         app.add_plugins(FogOfWarRenderPlugin);
@@ -336,12 +425,19 @@ fn setup_fog_resources(
     // --- Create Texture Arrays ---
     // --- 创建 Texture Arrays ---
 
+    // Fog and visibility hold one layer per team for every slot; team `t` of slot `L`
+    // lives at layer `L * team_count + t`. Snapshots are shared by all teams.
+    // 雾效和可见性纹理为每个槽位的每个队伍保留一层；槽位 `L` 的队伍 `t` 位于第
+    // `L * team_count + t` 层。快照由所有队伍共享。
     let fog_texture_size = Extent3d {
         width: settings.texture_resolution_per_chunk.x,
         height: settings.texture_resolution_per_chunk.y,
+        depth_or_array_layers: MAX_LAYERS * settings.effective_team_count(),
+    };
+    let snapshot_texture_size = Extent3d {
         depth_or_array_layers: MAX_LAYERS,
+        ..fog_texture_size
     };
-    let snapshot_texture_size = fog_texture_size;
     let visibility_texture_size = fog_texture_size;
 
     // Fog Texture: R8Unorm (0=visible, 1=unexplored)
@@ -427,6 +523,11 @@ fn setup_fog_resources(
     commands.insert_resource(SnapshotTextureArray {
         handle: snapshot_handle.clone(),
     });
+    // Blocker texture starts with one cell per chunk until a grid is inserted
+    // 在插入网格之前，阻挡纹理以每区块一个单元格开始
+    commands.insert_resource(VisionBlockerTextureArray {
+        handle: images.add(vision_blocker::create_vision_blocker_image(UVec2::ONE)),
+    });
     commands.insert_resource(TextureArrayManager::new(MAX_LAYERS));
 
     info!("Fog of War resources initialized");
@@ -441,6 +542,7 @@ fn setup_fog_resources(
 ///
 /// # Cleared Caches
 /// - `visible_chunks`: Chunks currently within vision source range
+/// - `team_visible_chunks`: Per-team visible chunks
 /// - `camera_view_chunks`: Chunks within the camera's viewport
 ///
 /// # Preserved Caches
@@ -458,8 +560,7 @@ fn setup_fog_resources(
 /// - `update_camera_view_chunks`
 /// - `update_chunk_component_state`
 fn clear_per_frame_caches(mut cache: ResMut<ChunkStateCache>) {
-    cache.visible_chunks.clear();
-    cache.camera_view_chunks.clear();
+    cache.clear();
 }
 
 /// Updates visible and explored chunk sets based on VisionSource positions.
@@ -472,14 +573,21 @@ fn clear_per_frame_caches(mut cache: ResMut<ChunkStateCache>) {
 ///
 /// # Algorithm
 /// For each enabled vision source:
-/// 1. Calculate bounding box around the vision range
+/// 1. Calculate bounding box around the shape's reach
 /// 2. Convert world coordinates to chunk coordinates
-/// 3. Test intersection between vision circle and chunk rectangles
-/// 4. Mark intersecting chunks as both visible and explored
+/// 3. Test intersection between the vision shape and chunk rectangles
+/// 4. Skip chunks fully hidden behind a blocker taller than the source's eye height
+/// 5. Mark remaining intersecting chunks as both visible and explored, for the
+///    source's `FogTeam`, every team sharing its vision via `FogAlliances`, and in
+///    the shared union sets
+///
+/// Running `RevealArea`s are processed like vision sources. Sources with
+/// `explores` off mark chunks visible only, and sources with `reveals` off mark
+/// them explored only.
 ///
 /// # Performance Optimizations
 /// - **Spatial Culling**: Only tests chunks within vision source bounding box
-/// - **Circle-Rectangle Intersection**: Efficient geometric test
+/// - **Shape-Rectangle Intersection**: Circle, cone sector and rotated square tests
 /// - **Early Termination**: Skips disabled vision sources
 ///
 /// # Complexity Analysis
@@ -488,8 +596,12 @@ fn clear_per_frame_caches(mut cache: ResMut<ChunkStateCache>) {
 /// - **Per Vision Source**: O((2R/S)²) where R=range, S=chunk size
 ///
 /// # Visibility Rules
-/// - Chunks intersecting any vision source become visible and explored
+/// - Chunks touched by a source's actual shape (circle, cone sector or rotated
+///   square with its transition band) become visible and explored
+/// - A chunk is only culled when a single `VisionOccluder` or `VisionBlockerGrid`
+///   cell hides all of it; partial occlusion is resolved per texel on the GPU
 /// - Once explored, chunks remain in the explored set permanently
+/// - Sources whose `FogTeam` is outside `FogMapSettings::team_count` are ignored
 /// - Visibility is recalculated each frame based on current vision source positions
 ///
 /// # Dependencies
@@ -498,27 +610,54 @@ fn clear_per_frame_caches(mut cache: ResMut<ChunkStateCache>) {
 fn update_chunk_visibility(
     settings: Res<FogMapSettings>,
     mut cache: ResMut<ChunkStateCache>,
-    vision_sources: Query<(&GlobalTransform, &VisionSource)>,
+    alliances: Res<FogAlliances>,
+    vision_sources: Query<(&GlobalTransform, &VisionSource, Option<&FogTeam>)>,
+    reveals: Res<ActiveReveals>,
+    occluders: Query<(&GlobalTransform, &VisionOccluder)>,
+    blocker_grid: Option<Res<VisionBlockerGrid>>,
     // We update the cache first, then sync to components if needed
     // 我们先更新缓存，如果需要再同步到组件
 ) {
     let chunk_size = settings.chunk_size.as_vec2();
-
-    for (transform, source) in vision_sources.iter() {
-        if !source.enabled {
+    let blocker_grid = blocker_grid.as_deref();
+
+    // Gather occluder edges once for all vision sources
+    // 为所有视野源一次性收集遮挡边
+    let occluder_segments = collect_occluder_segments(&settings, occluders.iter());
+
+    let team_count = settings.effective_team_count();
+
+    let entity_sources = vision_sources
+        .iter()
+        .filter_map(|(transform, source, team)| {
+            let team = team.copied().unwrap_or_default();
+            (source.enabled && settings.contains_team(team)).then(|| {
+                // Get 2D position / 获取 2D 位置
+                (settings.world_to_fog(transform.translation()), source, team)
+            })
+        });
+    let reveal_sources = reveals
+        .active(&settings)
+        .map(|reveal| (reveal.area().position, reveal.source(), reveal.area().team));
+
+    for (source_pos, source, team) in entity_sources.chain(reveal_sources) {
+        if !source.reveals && !source.explores {
             continue;
         }
 
-        let source_pos = transform.translation().truncate(); // Get 2D position / 获取 2D 位置
-        let range_sq = source.range * source.range;
+        let reach = shape_reach(source);
 
-        // Calculate the bounding box of the vision circle in chunk coordinates
-        // 计算视野圆形在区块坐标系下的包围盒
-        let min_world = source_pos - Vec2::splat(source.range);
-        let max_world = source_pos + Vec2::splat(source.range);
+        // Calculate the bounding box of the vision shape in chunk coordinates
+        // 计算视野形状在区块坐标系下的包围盒
+        let min_world = source_pos - Vec2::splat(reach);
+        let max_world = source_pos + Vec2::splat(reach);
 
-        let min_chunk = (min_world / chunk_size).floor().as_ivec2();
-        let max_chunk = (max_world / chunk_size).ceil().as_ivec2();
+        // Chunks outside the world bounds are never marked, so never allocated
+        // 世界边界之外的区块永远不会被标记，因此永远不会被分配
+        let (min_chunk, max_chunk) = settings.clamp_chunk_range(
+            (min_world / chunk_size).floor().as_ivec2(),
+            (max_world / chunk_size).ceil().as_ivec2(),
+        );
 
         // Iterate over potentially affected chunks
         // 遍历可能受影响的区块
@@ -528,69 +667,226 @@ fn update_chunk_visibility(
                 let chunk_min = chunk_coords.as_vec2() * chunk_size;
                 let chunk_max = chunk_min + chunk_size;
 
-                // Check if circle intersects chunk rectangle
-                // 检查圆是否与区块矩形相交
-                if circle_intersects_rect(source_pos, range_sq, chunk_min, chunk_max) {
-                    // Mark as visible and explored in the cache
-                    // 在缓存中标记为可见和已探索
-                    cache.visible_chunks.insert(chunk_coords);
-                    cache.explored_chunks.insert(chunk_coords);
+                // Check if the vision shape touches the chunk and is not hidden behind a blocker
+                // 检查视野形状是否与区块相交且未被阻挡物遮挡
+                if shape_intersects_rect(source, source_pos, chunk_min, chunk_max)
+                    && !is_rect_occluded(
+                        source_pos,
+                        source.eye_height,
+                        chunk_min,
+                        chunk_max,
+                        &occluder_segments,
+                        blocker_grid,
+                    )
+                {
+                    // Mark as visible and explored for the source's team and its allies
+                    // 在缓存中为视野源所属队伍及其盟友标记为可见和已探索
+                    for viewer in (0..team_count).map(|t| FogTeam(t as u8)) {
+                        if !alliances.shares_vision(viewer, team) {
+                            continue;
+                        }
+                        match (source.reveals, source.explores) {
+                            (true, true) => cache.mark_visible(viewer, chunk_coords),
+                            (true, false) => cache.mark_revealed(viewer, chunk_coords),
+                            _ => cache.mark_explored(viewer, chunk_coords),
+                        }
+                    }
                 }
             }
         }
     }
 }
 
-/// Updates the set of chunks currently within the camera's view.
+/// Cameras with their view, render target and whether they are tagged `FogOfWarCamera`.
+type ViewCamerasQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Camera,
+        &'static GlobalTransform,
+        &'static Projection,
+        Option<&'static RenderTarget>,
+        Has<FogOfWarCamera>,
+    ),
+>;
+
+/// Updates the set of chunks currently within the camera views.
 /// 更新当前在相机视野内的区块集合。
+///
+/// Every active `FogOfWarCamera` contributes its view whatever its render target, so
+/// split-screen, picture-in-picture and offscreen cameras all keep their chunks
+/// resident. Without any, the first active camera targeting the window is used.
 fn update_camera_view_chunks(
     settings: Res<FogMapSettings>,
     mut cache: ResMut<ChunkStateCache>,
-    // Assuming a single primary 2D camera with OrthographicProjection
-    // 假设有一个带 OrthographicProjection 的主 2D 相机
-    camera_q: Query<(
-        &Camera,
-        &GlobalTransform,
-        &Projection,
-        Option<&RenderTarget>,
-    )>,
+    camera_q: ViewCamerasQuery,
 ) {
     let chunk_size = settings.chunk_size.as_vec2();
 
-    for (camera, cam_transform, projection, render_target) in camera_q.iter() {
-        if let Projection::Orthographic(projection) = projection {
-            // Consider only the active camera targeting the primary window
-            // 只考虑渲染到主窗口的活动相机
+    let has_fog_cameras = camera_q
+        .iter()
+        .any(|(camera, .., is_fog_camera)| camera.is_active && is_fog_camera);
+    let cameras = camera_q
+        .iter()
+        .filter(|(camera, _, _, render_target, is_fog_camera)| {
+            // Fall back to the active camera targeting the primary window
+            // 回退到渲染到主窗口的活动相机
             let targets_window =
                 render_target.is_none_or(|rt| matches!(rt, RenderTarget::Window(_)));
-            if !camera.is_active || !targets_window {
-                continue;
+            camera.is_active
+                && if has_fog_cameras {
+                    *is_fog_camera
+                } else {
+                    targets_window
+                }
+        })
+        .take(if has_fog_cameras { usize::MAX } else { 1 });
+
+    for (camera, cam_transform, projection, _, _) in cameras {
+        let view_rect = match projection {
+            // Top-down 2D camera: the view AABB is based on scale and position
+            // 俯视 2D 相机：视图 AABB 基于缩放和位置
+            Projection::Orthographic(projection)
+                if settings.ground_plane == FogGroundPlane::XY
+                    && settings.fog_transform == Affine2::IDENTITY =>
+            {
+                let camera_pos = cam_transform.translation().truncate();
+                let half_size = projection.area.size() * 0.5 * projection.scale;
+                Some(Rect::from_center_half_size(camera_pos, half_size))
             }
+            // Perspective, tilted or transformed views: intersect the view rays with the ground plane
+            // 透视、倾斜或带变换的视图：将视线与地面平面求交
+            _ => camera_ground_rect(camera, cam_transform, projection, &settings),
+        };
+        let Some(view_rect) = view_rect else {
+            continue;
+        };
 
-            // Calculate camera's view AABB in world space
-            // 计算相机在世界空间中的视图 AABB
-            // Note: This is simplified. Real calculation depends on projection type and camera orientation.
-            // 注意: 这是简化的。实际计算取决于投影类型和相机方向。
-            // For Orthographic, it's roughly based on scale and position.
-            // 对于正交投影，大致基于缩放和位置。
-            let camera_pos = cam_transform.translation().truncate();
+        let (min_chunk, max_chunk) = settings.clamp_chunk_range(
+            (view_rect.min / chunk_size).floor().as_ivec2(),
+            (view_rect.max / chunk_size).ceil().as_ivec2(),
+        );
 
-            let half_width = projection.area.width() * 0.5 * projection.scale;
-            let half_height = projection.area.height() * 0.5 * projection.scale;
+        for y in min_chunk.y..=max_chunk.y {
+            for x in min_chunk.x..=max_chunk.x {
+                cache.camera_view_chunks.insert(IVec2::new(x, y));
+            }
+        }
+    }
+}
 
-            let cam_min_world = camera_pos - Vec2::new(half_width, half_height);
-            let cam_max_world = camera_pos + Vec2::new(half_width, half_height);
+/// Footprint of a camera's view on the ground plane, as an AABB in fog coordinates.
+/// 相机视野在地面平面上的覆盖范围（雾效坐标下的 AABB）
+///
+/// A ray is cast through each viewport corner and intersected with the plane. Rays
+/// that miss it (above the horizon) or hit it past the far plane are cut at the far
+/// plane, so a tilted perspective camera still gets a finite footprint.
+/// Returns `None` for custom projections or before the camera has been computed.
+pub(crate) fn camera_ground_rect(
+    camera: &Camera,
+    transform: &GlobalTransform,
+    projection: &Projection,
+    settings: &FogMapSettings,
+) -> Option<Rect> {
+    let plane = settings.ground_plane;
+    // Ray length from the near plane / 从近平面起算的射线长度
+    let max_distance = match projection {
+        Projection::Perspective(projection) => projection.far,
+        Projection::Orthographic(projection) => projection.far - projection.near,
+        Projection::Custom(_) => return None,
+    };
 
-            let min_chunk = (cam_min_world / chunk_size).floor().as_ivec2();
-            let max_chunk = (cam_max_world / chunk_size).ceil().as_ivec2();
+    let mut footprint: Option<Rect> = None;
+    for corner in [
+        Vec2::new(-1.0, -1.0),
+        Vec2::new(1.0, -1.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(-1.0, 1.0),
+    ] {
+        // Depth 1 is the near plane (reverse-Z) / 深度 1 为近平面（反向 Z）
+        let near = camera.ndc_to_world(transform, corner.extend(1.0))?;
+        let direction =
+            (camera.ndc_to_world(transform, corner.extend(0.5))? - near).normalize_or_zero();
+
+        let rise = plane.height(direction);
+        let hit = -plane.height(near) / rise;
+        let distance = if rise.abs() > f32::EPSILON && hit >= 0.0 {
+            hit.min(max_distance)
+        } else {
+            max_distance
+        };
+        let point = settings.world_to_fog(near + direction * distance);
+        footprint = Some(footprint.map_or(Rect::from_corners(point, point), |rect| {
+            rect.union_point(point)
+        }));
+    }
+    footprint
+}
 
-            for y in min_chunk.y..=max_chunk.y {
-                for x in min_chunk.x..=max_chunk.x {
-                    cache.camera_view_chunks.insert(IVec2::new(x, y));
-                }
+/// Hideable entities, with their team and stealth level.
+type HideablesQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static mut FogHideable,
+        Option<&'static FogTeam>,
+        Option<&'static Stealth>,
+    ),
+>;
+
+/// Shows or hides `FogHideable` entities based on the fog state at their position.
+/// 根据所在位置的雾效状态显示或隐藏 `FogHideable` 实体。
+///
+/// Visibility is evaluated per entity on the CPU with the same shapes, falloff and
+/// occlusion as the compute shader, for the team of the `FogOfWarCamera`. Explored
+/// state comes from the chunk cache. `Visibility` or `RenderLayers` is only written
+/// when an entity's state or policy changes.
+///
+/// # Performance
+/// - **Complexity**: O(H×V) where H=hideable entities, V=vision sources
+///
+/// # Dependencies
+/// - Must run after `update_chunk_visibility`
+fn update_fog_hideables(mut commands: Commands, fog: FogQuery, mut hideables: HideablesQuery) {
+    if hideables.is_empty() {
+        return;
+    }
+
+    let viewer = fog.viewer_team();
+    let sampler = fog.sampler();
+    let settings = fog.settings();
+
+    for (entity, transform, mut hideable, team, stealth) in hideables.iter_mut() {
+        let position = settings.world_to_fog(transform.translation());
+        let state = if sampler.sees_entity(viewer, position, team.copied(), stealth) {
+            ChunkVisibility::Visible
+        } else if fog.is_explored_for(viewer, position) {
+            ChunkVisibility::Explored
+        } else {
+            ChunkVisibility::Unexplored
+        };
+
+        // Policy edits also re-apply / 策略修改时也重新应用
+        if hideable.state() == Some(state) && !hideable.is_changed() {
+            continue;
+        }
+        hideable.bypass_change_detection().set_state(state);
+
+        let show = hideable.shows(state);
+        match &hideable.method {
+            FogHideMethod::Visibility => {
+                commands.entity(entity).insert(if show {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                });
+            }
+            FogHideMethod::RenderLayers { shown, hidden } => {
+                let layers = if show { shown } else { hidden };
+                commands.entity(entity).insert(layers.clone());
             }
-            // Only process one main camera / 只处理一个主相机
-            break;
         }
     }
 }
@@ -602,11 +898,14 @@ fn update_chunk_component_state(
     chunk_manager: Res<ChunkEntityManager>,
     mut chunk_q: Query<&mut FogChunk>,
     mut snapshot_event_writer: MessageWriter<RequestChunkSnapshot>, // Changed to EventWriter / 更改为 EventWriter
+    mut explored_writer: MessageWriter<ChunkExplored>,
+    mut visible_writer: MessageWriter<ChunkBecameVisible>,
+    mut left_vision_writer: MessageWriter<ChunkLeftVision>,
 ) {
     for (coords, entity) in chunk_manager.map.iter() {
         if let Ok(mut chunk) = chunk_q.get_mut(*entity) {
             let is_visible = cache.visible_chunks.contains(coords);
-            let is_explored = cache.explored_chunks.contains(coords); // Should always contain visible
+            let is_explored = cache.explored_chunks.contains(coords); // Contains visible unless lit by a non-exploring source
 
             let new_visibility = if is_visible {
                 ChunkVisibility::Visible
@@ -621,6 +920,16 @@ fn update_chunk_component_state(
                 // info!("Chunk {:?} visibility changed from {:?} to {:?}", coords, old_visibility, new_visibility);
                 chunk.state.visibility = new_visibility;
 
+                let coords = *coords;
+                if old_visibility == ChunkVisibility::Unexplored {
+                    explored_writer.write(ChunkExplored { coords });
+                }
+                if new_visibility == ChunkVisibility::Visible {
+                    visible_writer.write(ChunkBecameVisible { coords });
+                } else if old_visibility == ChunkVisibility::Visible {
+                    left_vision_writer.write(ChunkLeftVision { coords });
+                }
+
                 // If the chunk was unexplored and is now explored/visible, OR if it was explored and is now visible, send a snapshot request event.
                 // 如果区块之前是未探索状态，现在变为已探索/可见状态，或者之前是已探索状态，现在变为可见状态，则发送快照请求事件。
                 let should_request_snapshot = (old_visibility == ChunkVisibility::Unexplored
@@ -639,17 +948,17 @@ fn update_chunk_component_state(
                         };
                         trace!(
                             "Chunk {:?} ({}) {} ({} -> {}). Sending RequestChunkSnapshot.",
-                            *coords,
+                            coords,
                             entity.index(),
                             reason,
                             old_visibility,
                             new_visibility
                         );
-                        snapshot_event_writer.write(RequestChunkSnapshot(*coords));
+                        snapshot_event_writer.write(RequestChunkSnapshot(coords));
                     } else {
                         warn!(
                             "Chunk {:?} ({}) changed visibility ({} -> {}), but has no snapshot_layer_index. Cannot request snapshot via event.",
-                            *coords,
+                            coords,
                             entity.index(),
                             old_visibility,
                             new_visibility
@@ -770,56 +1079,6 @@ fn manage_chunk_entities(
     }
 }
 
-/// Efficiently tests if a circle intersects with an axis-aligned rectangle.
-/// 高效测试圆形是否与轴对齐矩形相交。
-///
-/// This function implements the standard circle-rectangle intersection algorithm
-/// by finding the closest point on the rectangle to the circle center and
-/// comparing the distance to the circle radius.
-///
-/// # Algorithm
-/// 1. Clamp circle center to rectangle bounds (finds closest point)
-/// 2. Calculate squared distance from circle center to closest point
-/// 3. Compare with squared radius (avoids expensive sqrt operation)
-///
-/// # Parameters
-/// - `circle_center`: Center position of the vision source
-/// - `range_sq`: Squared radius of the vision source (for performance)
-/// - `rect_min`: Bottom-left corner of the chunk rectangle
-/// - `rect_max`: Top-right corner of the chunk rectangle
-///
-/// # Performance
-/// - **Complexity**: O(1) - Constant time geometric calculation
-/// - **Optimizations**: Uses squared distance to avoid sqrt
-/// - **Precision**: Works with f32 precision, suitable for game coordinates
-///
-/// # Returns
-/// `true` if the circle intersects or overlaps the rectangle, `false` otherwise
-///
-/// # Mathematical Foundation
-/// Based on the principle that the closest point on a rectangle to any external
-/// point can be found by clamping coordinates to the rectangle's bounds.
-fn circle_intersects_rect(
-    circle_center: Vec2,
-    range_sq: f32,
-    rect_min: Vec2,
-    rect_max: Vec2,
-) -> bool {
-    // Clamp the circle center to the rectangle's bounds
-    // 将圆心限制在矩形边界内
-    let closest_x = circle_center.x.clamp(rect_min.x, rect_max.x);
-    let closest_y = circle_center.y.clamp(rect_min.y, rect_max.y);
-
-    // Calculate the distance from the circle center to the closest point
-    // 计算圆心到最近点的距离
-    let dx = circle_center.x - closest_x;
-    let dy = circle_center.y - closest_y;
-
-    // If the distance is less than or equal to the radius, they intersect
-    // 如果距离小于等于半径，则相交
-    (dx * dx + dy * dy) <= range_sq
-}
-
 /// Orchestrates intelligent memory management between CPU and GPU for chunk textures.
 /// 协调区块纹理在CPU和GPU之间的智能内存管理。
 ///
@@ -860,7 +1119,7 @@ fn circle_intersects_rect(
 /// - Queues new transfer requests via `GpuToCpuCopyRequests` and `CpuToGpuCopyRequests`
 /// - Triggers snapshot requests for chunks being moved from GPU to CPU
 #[allow(clippy::too_many_arguments)]
-pub fn manage_chunk_texture_transfer(
+pub(crate) fn manage_chunk_texture_transfer(
     mut commands: Commands,
     mut chunk_query: Query<(Entity, &mut FogChunk, &mut FogChunkImage)>,
     chunk_cache: Res<ChunkStateCache>,
@@ -871,6 +1130,7 @@ pub fn manage_chunk_texture_transfer(
     mut gpu_data_ready_reader: MessageReader<ChunkGpuDataReady>,
     mut cpu_data_uploaded_reader: MessageReader<ChunkCpuDataUploaded>,
     mut snapshot_requests: ResMut<MainWorldSnapshotRequestQueue>,
+    mut explored_decay: ResMut<decay::ExploredDecay>,
 ) {
     for event in gpu_data_ready_reader.read() {
         if let Some((_entity, mut chunk, chunk_image)) = chunk_query
@@ -893,6 +1153,7 @@ pub fn manage_chunk_texture_transfer(
                     .get_mut(&chunk_image.snapshot_image_handle)
                     .expect("Failed to get snapshot image");
                 snapshot_image.data = Some(event.snapshot_data.clone());
+                explored_decay.mark_downloaded(chunk.coords);
 
                 // if let Some((fog_data, snapshot_data)) = cpu_storage.storage.get(&chunk.coords) {} else {
                 //     let fog_image = Image::new_fill(Extent3d {
@@ -1094,6 +1355,14 @@ pub fn manage_chunk_texture_transfer(
                         continue; // Skip this chunk and continue with the next one
                     };
 
+                    // Catch up on the explored decay missed while the chunk was on the CPU
+                    // 补上区块驻留 CPU 期间错过的探索衰减
+                    if let Some(mut fog_image) = images.get_mut(&chunk_image.fog_image_handle) {
+                        if let Some(fog_data) = fog_image.data.as_mut() {
+                            explored_decay.decay_on_upload(chunk.coords, fog_data);
+                        }
+                    }
+
                     chunk.state.memory_location = ChunkMemoryLocation::PendingCopyToGpu;
                     cpu_to_gpu_requests.requests.push(CpuToGpuCopyRequest {
                         chunk_coords: chunk.coords,
@@ -1157,6 +1426,8 @@ fn reset_fog_of_war_system(
             visible_chunks: cache.visible_chunks.clone(),
             gpu_resident_chunks: cache.gpu_resident_chunks.clone(),
             camera_view_chunks: cache.camera_view_chunks.clone(),
+            team_explored_chunks: cache.team_explored_chunks.clone(),
+            team_visible_chunks: cache.team_visible_chunks.clone(),
             created_at: current_time,
         };
 
@@ -1301,11 +1572,12 @@ fn reset_chunk_images(
     for (_entity, chunk_image) in chunk_query.iter_mut() {
         {
             if let Some(mut fog_image) = images.get_mut(&chunk_image.fog_image_handle) {
-                // 使用统一的纹理大小计算器
-                // Use unified texture size calculator
-                let size_info = TextureSizeCalculator::calculate_2d_single_channel(
+                // 使用统一的纹理大小计算器（每个队伍一层）
+                // Use unified texture size calculator (one layer per team)
+                let size_info = TextureSizeCalculator::calculate_3d_single_channel(
                     fog_image.texture_descriptor.size.width,
                     fog_image.texture_descriptor.size.height,
+                    fog_image.texture_descriptor.size.depth_or_array_layers,
                 )
                 .map_err(|e| format!("Failed to calculate fog texture size: {e}"))?;
 
@@ -1453,6 +1725,8 @@ fn rollback_reset_to_checkpoint(
     cache.visible_chunks = checkpoint.visible_chunks.clone();
     cache.gpu_resident_chunks = checkpoint.gpu_resident_chunks.clone();
     cache.camera_view_chunks = checkpoint.camera_view_chunks.clone();
+    cache.team_explored_chunks = checkpoint.team_explored_chunks.clone();
+    cache.team_visible_chunks = checkpoint.team_visible_chunks.clone();
 
     info!(
         "Rollback completed: restored {} explored, {} visible, {} GPU, {} camera view chunks",
@@ -1574,3 +1848,114 @@ fn monitor_reset_sync_system(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_asset::Handle;
+    use bevy_camera::{OrthographicProjection, PerspectiveProjection};
+    use bevy_math::Vec3;
+    use bevy_transform::components::Transform;
+
+    /// Camera with its clip matrix computed for an 800x600 target, as `camera_system` does.
+    fn computed_camera(projection: &mut Projection) -> Camera {
+        projection.update(800.0, 600.0);
+        let mut camera = Camera::default();
+        camera.computed.clip_from_view = projection.get_clip_from_view();
+        camera
+    }
+
+    #[test]
+    fn test_top_down_orthographic_footprint() {
+        let settings = FogMapSettings::default();
+        let mut projection = Projection::Orthographic(OrthographicProjection::default_2d());
+        let camera = computed_camera(&mut projection);
+        let transform = GlobalTransform::from_xyz(100.0, 50.0, 10.0);
+
+        let rect = camera_ground_rect(&camera, &transform, &projection, &settings).unwrap();
+        assert!(rect.min.abs_diff_eq(Vec2::new(-300.0, -250.0), 0.01));
+        assert!(rect.max.abs_diff_eq(Vec2::new(500.0, 350.0), 0.01));
+    }
+
+    #[test]
+    fn test_tilted_perspective_footprint_is_capped_at_far() {
+        let settings = FogMapSettings {
+            ground_plane: FogGroundPlane::XZ,
+            ..Default::default()
+        };
+        let mut projection = Projection::Perspective(PerspectiveProjection {
+            far: 100.0,
+            ..Default::default()
+        });
+        let camera = computed_camera(&mut projection);
+        // Looking slightly down, so the top of the view is above the horizon
+        let transform = GlobalTransform::from(
+            Transform::from_xyz(0.0, 10.0, 0.0).looking_to(Vec3::new(0.0, -0.2, -1.0), Vec3::Y),
+        );
+
+        let rect = camera_ground_rect(&camera, &transform, &projection, &settings).unwrap();
+        assert!(rect.min.is_finite() && rect.max.is_finite());
+        // Forward is -Z in the world and +Y in fog coordinates
+        assert!(rect.min.y > 0.0);
+        assert!(rect.max.y > 80.0 && rect.max.y <= 100.2);
+        assert!(rect.max.x <= 100.2 && rect.min.x >= -100.2);
+    }
+
+    #[test]
+    fn test_custom_projection_has_no_footprint() {
+        let settings = FogMapSettings::default();
+        let mut projection = Projection::custom(PerspectiveProjection::default());
+        let camera = computed_camera(&mut projection);
+        let transform = GlobalTransform::IDENTITY;
+        assert!(camera_ground_rect(&camera, &transform, &projection, &settings).is_none());
+    }
+
+    #[test]
+    fn test_every_fog_camera_adds_its_view_chunks() {
+        let mut app = App::new();
+        app.init_resource::<FogMapSettings>()
+            .init_resource::<ChunkStateCache>()
+            .add_systems(Update, update_camera_view_chunks);
+
+        let camera_at = |x: f32| {
+            (
+                Camera::default(),
+                GlobalTransform::from_xyz(x, 0.0, 0.0),
+                Projection::Orthographic(OrthographicProjection {
+                    area: Rect::new(-100.0, -100.0, 100.0, 100.0),
+                    ..OrthographicProjection::default_2d()
+                }),
+            )
+        };
+        let image_target = || RenderTarget::Image(Handle::<Image>::default().into());
+        let window_camera = app.world_mut().spawn((camera_at(0.0), FogOfWarCamera)).id();
+        let image_camera = app
+            .world_mut()
+            .spawn((camera_at(2000.0), image_target(), FogOfWarCamera))
+            .id();
+        // Untagged offscreen camera, e.g. a render-to-texture preview
+        app.world_mut().spawn((camera_at(-5000.0), image_target()));
+
+        app.update();
+        let view_chunks = &app.world().resource::<ChunkStateCache>().camera_view_chunks;
+        assert!(view_chunks.contains(&IVec2::ZERO));
+        assert!(view_chunks.contains(&IVec2::new(8, 0)));
+        assert!(!view_chunks.contains(&IVec2::new(-20, 0)));
+
+        // Without tagged cameras only the window camera counts
+        for camera in [window_camera, image_camera] {
+            app.world_mut()
+                .entity_mut(camera)
+                .remove::<FogOfWarCamera>();
+        }
+        app.world_mut()
+            .resource_mut::<ChunkStateCache>()
+            .camera_view_chunks
+            .clear();
+        app.update();
+        let view_chunks = &app.world().resource::<ChunkStateCache>().camera_view_chunks;
+        assert!(view_chunks.contains(&IVec2::ZERO));
+        assert!(!view_chunks.contains(&IVec2::new(8, 0)));
+        assert!(!view_chunks.contains(&IVec2::new(-20, 0)));
+    }
+}
@@ -0,0 +1,281 @@
+//! CPU-side line-of-sight tests mirroring the fog compute shader.
+//! 与雾效计算着色器一致的 CPU 端视线测试
+//!
+//! The compute shader decides visibility per texel. The CPU only needs to know
+//! whether a whole chunk can receive any visibility from a source, so the tests
+//! here are deliberately conservative: a chunk is only reported as occluded when
+//! a single blocker provably hides all of it.
+
+use crate::prelude::*;
+use bevy_math::{IVec2, Vec2};
+use bevy_transform::components::GlobalTransform;
+
+/// Upper bound on grid samples per ray, matching `MAX_BLOCKER_STEPS` in the shader.
+const MAX_BLOCKER_STEPS: u32 = 256;
+
+/// World-space occluder edge with the height of its owning `VisionOccluder`.
+/// 带有所属遮挡物高度的世界空间遮挡边
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OccluderSegment {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub height: f32,
+}
+
+/// Flattens enabled occluders into fog-space segments, as the render extraction does.
+/// 与渲染提取相同，将启用的遮挡物展开为雾效空间线段
+pub(crate) fn collect_occluder_segments<'a>(
+    settings: &FogMapSettings,
+    occluders: impl Iterator<Item = (&'a GlobalTransform, &'a VisionOccluder)>,
+) -> Vec<OccluderSegment> {
+    occluders
+        .filter(|(_, occluder)| occluder.enabled)
+        .flat_map(|(transform, occluder)| {
+            occluder
+                .fog_segments(transform, settings)
+                .into_iter()
+                .map(|(start, end)| OccluderSegment {
+                    start,
+                    end,
+                    height: occluder.height,
+                })
+        })
+        .collect()
+}
+
+/// Returns true if segment `p0 -> p1` crosses segment `a -> b`.
+/// Same rule as `segments_intersect` in `fog_compute.wgsl`.
+pub(crate) fn segments_intersect(p0: Vec2, p1: Vec2, a: Vec2, b: Vec2) -> bool {
+    let r = p1 - p0;
+    let s = b - a;
+    let denom = r.perp_dot(s);
+    // Parallel or degenerate segments never block / 平行或退化线段不阻挡
+    if denom.abs() < 1e-6 {
+        return false;
+    }
+    let qp = a - p0;
+    let t = qp.perp_dot(s) / denom;
+    let u = qp.perp_dot(r) / denom;
+    t > 0.0 && t < 1.0 && (0.0..=1.0).contains(&u)
+}
+
+/// Returns true if segment `p0 -> p1` passes through the axis-aligned box `min..max`.
+/// 如果线段 `p0 -> p1` 穿过轴对齐矩形 `min..max` 则返回 true
+fn segment_intersects_rect(p0: Vec2, p1: Vec2, min: Vec2, max: Vec2) -> bool {
+    let delta = p1 - p0;
+    let mut t_enter = 0.0_f32;
+    let mut t_exit = 1.0_f32;
+    for axis in 0..2 {
+        if delta[axis].abs() < 1e-6 {
+            if p0[axis] < min[axis] || p0[axis] > max[axis] {
+                return false;
+            }
+            continue;
+        }
+        let t0 = (min[axis] - p0[axis]) / delta[axis];
+        let t1 = (max[axis] - p0[axis]) / delta[axis];
+        t_enter = t_enter.max(t0.min(t1));
+        t_exit = t_exit.min(t0.max(t1));
+        if t_enter > t_exit {
+            return false;
+        }
+    }
+    true
+}
+
+/// Collects the grid cells that block `eye_height` along `from -> to`, walking the
+/// ray the same way as `is_grid_blocked` in the shader (endpoint cells skipped).
+/// 以与着色器 `is_grid_blocked` 相同的方式沿射线收集阻挡 `eye_height` 的网格单元格
+fn blocking_cells_along(
+    grid: &VisionBlockerGrid,
+    from: Vec2,
+    to: Vec2,
+    eye_height: f32,
+) -> Vec<IVec2> {
+    let cell_size = grid.cell_size();
+    let from_cell = (from / cell_size).floor().as_ivec2();
+    let to_cell = (to / cell_size).floor().as_ivec2();
+    let delta = to - from;
+    let cells_crossed = (delta.abs() / cell_size).max_element();
+    let steps = ((cells_crossed * 2.0).ceil() as u32).min(MAX_BLOCKER_STEPS);
+
+    let mut cells: Vec<IVec2> = Vec::new();
+    for i in 1..steps {
+        let p = from + delta * (i as f32 / steps as f32);
+        let cell = (p / cell_size).floor().as_ivec2();
+        if cell == from_cell || cell == to_cell || cells.last() == Some(&cell) {
+            continue;
+        }
+        if VisionBlockerGrid::value_blocks(grid.value_at(p), eye_height) {
+            cells.push(cell);
+        }
+    }
+    cells
+}
+
+/// Returns true if the ray from `from` to `to` is blocked for a viewer at `eye_height`.
+/// Same test as `is_line_of_sight_blocked` and `is_grid_blocked` in the shader.
+/// 如果从 `from` 到 `to` 的射线对位于 `eye_height` 的观察者被阻挡则返回 true
+pub(crate) fn is_ray_blocked(
+    from: Vec2,
+    to: Vec2,
+    eye_height: f32,
+    segments: &[OccluderSegment],
+    grid: Option<&VisionBlockerGrid>,
+) -> bool {
+    segments.iter().any(|segment| {
+        segment.height > eye_height && segments_intersect(from, to, segment.start, segment.end)
+    }) || grid.is_some_and(|grid| !blocking_cells_along(grid, from, to, eye_height).is_empty())
+}
+
+/// Returns true if a single blocker hides the whole rectangle `rect_min..rect_max`
+/// from a source at `source_pos`.
+/// 如果单个阻挡物将整个矩形从视野源处完全遮挡则返回 true
+///
+/// The shadow cast by a convex blocker from a point is convex, so a rectangle is
+/// fully hidden when all four of its corners are behind the same blocker. Rectangles
+/// that are only hidden by a combination of blockers are reported as visible, which
+/// keeps the chunk resident and lets the shader resolve it per texel.
+///
+/// Grid cells are tested as whole boxes, while the shader samples twice per cell,
+/// so rays that barely graze a cell corner may still be lit on the GPU.
+pub(crate) fn is_rect_occluded(
+    source_pos: Vec2,
+    eye_height: f32,
+    rect_min: Vec2,
+    rect_max: Vec2,
+    segments: &[OccluderSegment],
+    grid: Option<&VisionBlockerGrid>,
+) -> bool {
+    let corners = [
+        rect_min,
+        Vec2::new(rect_max.x, rect_min.y),
+        rect_max,
+        Vec2::new(rect_min.x, rect_max.y),
+    ];
+
+    let segment_occludes = segments.iter().any(|segment| {
+        segment.height > eye_height
+            && corners
+                .iter()
+                .all(|corner| segments_intersect(source_pos, *corner, segment.start, segment.end))
+    });
+    if segment_occludes {
+        return true;
+    }
+
+    let Some(grid) = grid else {
+        return false;
+    };
+    let cell_size = grid.cell_size();
+    // Any blocker hiding the whole rectangle must lie on the ray to its first corner
+    // 任何遮挡整个矩形的阻挡物都必然位于到第一个角点的射线上
+    blocking_cells_along(grid, source_pos, corners[0], eye_height)
+        .into_iter()
+        .any(|cell| {
+            let cell_min = cell.as_vec2() * cell_size;
+            let cell_max = cell_min + cell_size;
+            // Texels inside the blocking cell are lit by the shader, so the cell must be outside the rectangle
+            // 着色器会照亮阻挡单元格内的纹素，因此该单元格必须位于矩形之外
+            let overlaps_rect = cell_min.x < rect_max.x
+                && cell_max.x > rect_min.x
+                && cell_min.y < rect_max.y
+                && cell_max.y > rect_min.y;
+            !overlaps_rect
+                && corners
+                    .iter()
+                    .all(|corner| segment_intersects_rect(source_pos, *corner, cell_min, cell_max))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_transform::components::Transform;
+
+    fn wall(start: Vec2, end: Vec2, height: f32) -> OccluderSegment {
+        OccluderSegment { start, end, height }
+    }
+
+    #[test]
+    fn test_segments_intersect_edge_cases() {
+        let crosses = |a: Vec2, b: Vec2| segments_intersect(Vec2::ZERO, Vec2::new(10.0, 0.0), a, b);
+        assert!(crosses(Vec2::new(5.0, -1.0), Vec2::new(5.0, 1.0)));
+        assert!(!crosses(Vec2::new(5.0, 1.0), Vec2::new(5.0, 3.0)));
+
+        // Collinear and parallel walls never block
+        assert!(!crosses(Vec2::new(2.0, 0.0), Vec2::new(8.0, 0.0)));
+        assert!(!crosses(Vec2::new(2.0, 1.0), Vec2::new(8.0, 1.0)));
+
+        // The ray's own endpoints are excluded, the wall's endpoints are not
+        assert!(!crosses(Vec2::new(10.0, -1.0), Vec2::new(10.0, 1.0)));
+        assert!(!crosses(Vec2::new(0.0, -1.0), Vec2::new(0.0, 1.0)));
+        assert!(crosses(Vec2::new(5.0, 0.0), Vec2::new(5.0, 4.0)));
+    }
+
+    #[test]
+    fn test_ray_is_blocked_by_taller_wall_between_points() {
+        let segments = [wall(Vec2::new(5.0, -1.0), Vec2::new(5.0, 1.0), 2.0)];
+        let blocked =
+            |to: Vec2, eye_height: f32| is_ray_blocked(Vec2::ZERO, to, eye_height, &segments, None);
+        assert!(blocked(Vec2::new(10.0, 0.0), 0.0));
+        // Ray passing beside the wall
+        assert!(!blocked(Vec2::new(10.0, 5.0), 0.0));
+        // Target in front of the wall
+        assert!(!blocked(Vec2::new(4.0, 0.0), 0.0));
+        // Eye above the wall sees over it
+        assert!(!blocked(Vec2::new(10.0, 0.0), 3.0));
+    }
+
+    #[test]
+    fn test_rect_occluded_only_when_fully_in_shadow() {
+        let segments = [wall(Vec2::new(10.0, -20.0), Vec2::new(10.0, 20.0), 2.0)];
+        let occluded = |min: Vec2, max: Vec2, eye_height: f32| {
+            is_rect_occluded(Vec2::ZERO, eye_height, min, max, &segments, None)
+        };
+
+        assert!(occluded(Vec2::new(20.0, -5.0), Vec2::new(30.0, 5.0), 0.0));
+        // The top corners stick out past the end of the wall
+        assert!(!occluded(Vec2::new(20.0, 10.0), Vec2::new(30.0, 80.0), 0.0));
+        // A source above the wall is never occluded by it
+        assert!(!occluded(Vec2::new(20.0, -5.0), Vec2::new(30.0, 5.0), 5.0));
+    }
+
+    #[test]
+    fn test_occluder_shapes_break_into_closed_outlines() {
+        let settings = FogMapSettings::default();
+        let transform = GlobalTransform::from(Transform::from_xyz(10.0, 0.0, 0.0));
+        let rect = VisionOccluder::rect(Vec2::new(1.0, 2.0)).with_height(3.0);
+        let triangle =
+            VisionOccluder::polygon(vec![Vec2::ZERO, Vec2::new(4.0, 0.0), Vec2::new(0.0, 4.0)]);
+        let point = VisionOccluder::polygon(vec![Vec2::ONE]);
+        let mut disabled = VisionOccluder::segment(Vec2::ZERO, Vec2::ONE);
+        disabled.enabled = false;
+
+        let segments = rect.fog_segments(&transform, &settings);
+        assert_eq!(
+            segments,
+            vec![
+                (Vec2::new(9.0, -2.0), Vec2::new(11.0, -2.0)),
+                (Vec2::new(11.0, -2.0), Vec2::new(11.0, 2.0)),
+                (Vec2::new(11.0, 2.0), Vec2::new(9.0, 2.0)),
+                (Vec2::new(9.0, 2.0), Vec2::new(9.0, -2.0)),
+            ]
+        );
+
+        let segments = triangle.fog_segments(&transform, &settings);
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[2], (Vec2::new(10.0, 4.0), Vec2::new(10.0, 0.0)));
+        assert!(point.fog_segments(&transform, &settings).is_empty());
+
+        let occluders = [
+            (&transform, &rect),
+            (&transform, &triangle),
+            (&transform, &disabled),
+        ];
+        let collected = collect_occluder_segments(&settings, occluders.into_iter());
+        assert_eq!(collected.len(), 7);
+        assert_eq!(collected[0].height, 3.0);
+        assert_eq!(collected[6].height, f32::MAX);
+    }
+}
@@ -147,8 +147,9 @@ pub struct ChunkStateCache {
     /// **Usage**: Rendering decisions, fog texture updates, exploration tracking
     pub visible_chunks: HashSet<IVec2>,
 
-    /// Set of chunk coordinates that have ever been revealed (includes visible_chunks).
-    /// 曾经被照亮过的区块坐标集合 (包含 visible_chunks)
+    /// Set of chunk coordinates that have ever been explored. Includes `visible_chunks`,
+    /// except chunks lit only by sources with `explores` turned off.
+    /// 曾经被探索过的区块坐标集合 (包含 visible_chunks，仅被 `explores` 关闭的视野源照亮的区块除外)
     ///
     /// This set accumulates all chunks that have been visible at any point, creating
     /// the persistent "explored area" for traditional fog of war mechanics.
@@ -182,6 +183,25 @@ pub struct ChunkStateCache {
     /// **Persistence**: Updated during memory transfers, maintained across frames
     /// **Usage**: Memory management, transfer scheduling, rendering availability
     pub gpu_resident_chunks: HashSet<IVec2>,
+
+    /// Per-team chunk coordinates currently visible to one of the team's vision sources.
+    /// 每个队伍当前被其视野源照亮的区块坐标集合
+    ///
+    /// `visible_chunks` is the union of these sets. Rebuilt every frame.
+    pub team_visible_chunks: HashMap<FogTeam, HashSet<IVec2>>,
+
+    /// Per-team chunk coordinates the team has ever revealed.
+    /// 每个队伍曾经照亮过的区块坐标集合
+    ///
+    /// `explored_chunks` is the union of these sets. Only cleared during fog reset.
+    pub team_explored_chunks: HashMap<FogTeam, HashSet<IVec2>>,
+
+    /// Per-team chunk coordinates a vision source touched this frame, whether it
+    /// revealed or only explored them.
+    /// 每个队伍本帧被视野源触及的区块坐标集合（无论是揭示还是仅探索）
+    ///
+    /// Restarts explored decay for these chunks. Rebuilt every frame.
+    pub team_touched_chunks: HashMap<FogTeam, HashSet<IVec2>>,
 }
 
 impl ChunkStateCache {
@@ -220,6 +240,12 @@ impl ChunkStateCache {
     /// - `gpu_resident_chunks`: Managed by separate memory management systems
     pub fn clear(&mut self) {
         self.visible_chunks.clear();
+        self.team_visible_chunks
+            .values_mut()
+            .for_each(HashSet::clear);
+        self.team_touched_chunks
+            .values_mut()
+            .for_each(HashSet::clear);
         // explored_chunks 通常不清空，除非需要重置迷雾 / explored_chunks is usually not cleared unless resetting fog
         self.camera_view_chunks.clear();
         // gpu_resident_chunks 的管理更复杂，不一定每帧清空 / gpu_resident_chunks management is more complex, not necessarily cleared every frame
@@ -271,6 +297,104 @@ impl ChunkStateCache {
         self.explored_chunks.clear();
         self.camera_view_chunks.clear();
         self.gpu_resident_chunks.clear();
+        self.team_visible_chunks.clear();
+        self.team_explored_chunks.clear();
+        self.team_touched_chunks.clear();
+    }
+
+    /// Marks a chunk as visible and explored for `team` and in the shared union sets.
+    /// 将区块标记为对 `team` 可见且已探索，并同步更新合并集合
+    pub fn mark_visible(&mut self, team: FogTeam, coords: IVec2) {
+        self.mark_revealed(team, coords);
+        self.mark_explored(team, coords);
+    }
+
+    /// Marks a chunk as visible for `team` without exploring it.
+    /// 将区块标记为对 `team` 可见，但不标记为已探索
+    pub fn mark_revealed(&mut self, team: FogTeam, coords: IVec2) {
+        self.visible_chunks.insert(coords);
+        self.team_visible_chunks
+            .entry(team)
+            .or_default()
+            .insert(coords);
+        self.team_touched_chunks
+            .entry(team)
+            .or_default()
+            .insert(coords);
+    }
+
+    /// Marks a chunk as explored for `team` without making it visible.
+    /// 将区块标记为 `team` 已探索，但不使其可见
+    pub fn mark_explored(&mut self, team: FogTeam, coords: IVec2) {
+        self.explored_chunks.insert(coords);
+        self.team_explored_chunks
+            .entry(team)
+            .or_default()
+            .insert(coords);
+        self.team_touched_chunks
+            .entry(team)
+            .or_default()
+            .insert(coords);
+    }
+
+    /// Forgets that `team` explored the chunk, dropping it from the union set once
+    /// no team has explored it. Returns true if the chunk is now unexplored by every team.
+    /// 移除 `team` 对该区块的探索记录；当没有队伍探索过它时从合并集合中移除。
+    /// 如果该区块现在对所有队伍都未探索则返回 true
+    pub fn forget_explored(&mut self, team: FogTeam, coords: IVec2) -> bool {
+        if let Some(chunks) = self.team_explored_chunks.get_mut(&team) {
+            chunks.remove(&coords);
+        }
+        let still_explored = self
+            .team_explored_chunks
+            .values()
+            .any(|chunks| chunks.contains(&coords));
+        if !still_explored {
+            self.explored_chunks.remove(&coords);
+        }
+        !still_explored
+    }
+
+    /// Returns true if the chunk is currently visible to `team`.
+    /// 如果区块当前对 `team` 可见则返回 true
+    pub fn is_visible_to(&self, team: FogTeam, coords: IVec2) -> bool {
+        self.team_visible_chunks
+            .get(&team)
+            .is_some_and(|chunks| chunks.contains(&coords))
+    }
+
+    /// Returns true if `team` has ever explored the chunk.
+    /// 如果 `team` 曾探索过该区块则返回 true
+    pub fn is_explored_by(&self, team: FogTeam, coords: IVec2) -> bool {
+        self.team_explored_chunks
+            .get(&team)
+            .is_some_and(|chunks| chunks.contains(&coords))
+    }
+
+    /// Returns true if `team` or a team sharing its explored history with `team` has explored the chunk.
+    /// 如果 `team` 或与其共享探索历史的队伍探索过该区块则返回 true
+    pub fn is_explored_with_allies(
+        &self,
+        team: FogTeam,
+        coords: IVec2,
+        alliances: &FogAlliances,
+    ) -> bool {
+        self.team_explored_chunks.iter().any(|(source, chunks)| {
+            alliances.shares_explored(team, *source) && chunks.contains(&coords)
+        })
+    }
+
+    /// Returns the teams that have explored the chunk, in ascending order.
+    /// 按升序返回探索过该区块的队伍
+    pub fn teams_exploring(&self, coords: IVec2) -> Vec<FogTeam> {
+        let mut teams: Vec<FogTeam> = self
+            .team_explored_chunks
+            .iter()
+            .filter(|(_, chunks)| chunks.contains(&coords))
+            .map(|(team, _)| *team)
+            .collect();
+        teams.sort_by_key(|team| team.0);
+        teams
     }
 }
 
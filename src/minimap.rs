@@ -0,0 +1,233 @@
+//! Minimap images of the fog state.
+//! 迷雾状态的小地图图像
+//!
+//! A [`FogMinimap`] renders the explored and visible state of a fixed region into an
+//! `Image`, straight from the fog, visibility and snapshot texture arrays. Nothing in
+//! the scene is rendered again: the fog colors of [`FogMapSettings`] are blended over
+//! the chunk snapshots (when enabled) or the minimap's background color, so explored
+//! areas out of sight stay tinted with `fog_color_explored` and visible areas are clear.
+//!
+//! Every active minimap region counts as a view for GPU chunk culling, so exploration
+//! inside it stays current even when no camera looks there.
+
+use crate::prelude::*;
+use bevy_asset::{Handle, RenderAssetUsages};
+use bevy_color::{Color, ColorToComponents};
+use bevy_image::Image;
+use bevy_math::{Rect, UVec2, Vec4};
+use bevy_render::extract_resource::ExtractResource;
+use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy_time::Time;
+
+/// Renders the fog state of a region into an image, e.g. for a minimap UI node.
+/// 将某一区域的迷雾状态渲染到图像中，例如用于小地图界面节点
+///
+/// `region` is in fog coordinates (see `FogMapSettings::world_to_fog`); its `min`
+/// corner maps to the bottom-left of the image. The image must use
+/// `TextureFormat::Rgba8UnormSrgb` and `TextureUsages::RENDER_ATTACHMENT`, which
+/// [`FogMinimap::image`] sets up.
+///
+/// Only chunks resident in the GPU texture arrays have fog data to show; chunks
+/// offloaded to the CPU are drawn as unexplored.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+///     let image = images.add(FogMinimap::image(UVec2::splat(256)));
+///     commands.spawn(
+///         FogMinimap::new(image.clone(), Rect::new(-2048.0, -2048.0, 2048.0, 2048.0))
+///             .with_update_interval(0.25),
+///     );
+///     commands.spawn((
+///         ImageNode::new(image),
+///         Node {
+///             width: Val::Px(256.0),
+///             height: Val::Px(256.0),
+///             ..default()
+///         },
+///     ));
+/// }
+/// ```
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct FogMinimap {
+    /// Image the minimap is rendered into.
+    /// 小地图渲染的目标图像
+    pub image: Handle<Image>,
+
+    /// Region shown by the minimap, in fog coordinates.
+    /// 小地图显示的区域（雾效坐标）
+    pub region: Rect,
+
+    /// Team whose fog is shown, `None` for the team of the first `FogOfWarCamera`.
+    /// 显示其迷雾的队伍，`None` 表示第一个 `FogOfWarCamera` 的队伍
+    pub team: Option<FogTeam>,
+
+    /// Whether explored areas show their snapshots (default: true).
+    /// 已探索区域是否显示其快照（默认 true）
+    pub show_snapshots: bool,
+
+    /// Color under the fog where there is no snapshot (default: transparent).
+    /// 没有快照之处迷雾下方的颜色（默认透明）
+    pub background: Color,
+
+    /// Seconds between redraws, `0.0` redraws every frame.
+    /// 两次重绘之间的秒数，`0.0` 表示每帧重绘
+    pub update_interval: f32,
+
+    /// `Time::elapsed_secs` of the last redraw, `None` to redraw on the next frame.
+    last_update: Option<f32>,
+}
+
+impl FogMinimap {
+    /// Creates a minimap of `region` rendered into `image` every frame.
+    /// 创建每帧将 `region` 渲染到 `image` 的小地图
+    pub fn new(image: Handle<Image>, region: Rect) -> Self {
+        Self {
+            image,
+            region,
+            team: None,
+            show_snapshots: true,
+            background: Color::NONE,
+            update_interval: 0.0,
+            last_update: None,
+        }
+    }
+
+    /// Creates a transparent image the minimap can render into.
+    /// 创建可供小地图渲染的透明图像
+    pub fn image(size: UVec2) -> Image {
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: size.x.max(1),
+                height: size.y.max(1),
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        image.texture_descriptor.usage = TextureUsages::RENDER_ATTACHMENT // Minimap pass target / 小地图渲染目标
+            | TextureUsages::TEXTURE_BINDING // Sampled by UI and sprites / 供界面和精灵采样
+            | TextureUsages::COPY_DST;
+        image
+    }
+
+    /// Sets the team whose fog is shown.
+    pub fn with_team(mut self, team: FogTeam) -> Self {
+        self.team = Some(team);
+        self
+    }
+
+    /// Sets whether explored areas show their snapshots.
+    pub fn with_snapshots(mut self, show_snapshots: bool) -> Self {
+        self.show_snapshots = show_snapshots;
+        self
+    }
+
+    /// Sets the color under the fog where there is no snapshot.
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Sets the seconds between redraws.
+    pub fn with_update_interval(mut self, seconds: f32) -> Self {
+        self.update_interval = seconds;
+        self
+    }
+
+    /// Redraws the minimap on the next frame regardless of the update interval.
+    /// 无论更新间隔如何，都在下一帧重绘小地图
+    pub fn request_redraw(&mut self) {
+        self.last_update = None;
+    }
+
+    /// Returns true if the minimap is due for a redraw at `now`, and marks it redrawn.
+    fn tick(&mut self, now: f32) -> bool {
+        let due = self
+            .last_update
+            .is_none_or(|last| now - last >= self.update_interval);
+        if due {
+            self.last_update = Some(now);
+        }
+        due
+    }
+}
+
+/// A minimap as seen by the render world.
+#[derive(Debug, Clone)]
+pub(crate) struct FogMinimapView {
+    pub image: Handle<Image>,
+    pub region: Rect,
+    /// Team index, already clamped to `team_count - 1`.
+    pub team: u32,
+    pub show_snapshots: bool,
+    /// Background color in linear RGBA.
+    pub background: Vec4,
+    /// Whether the minimap is redrawn this frame.
+    pub redraw: bool,
+}
+
+/// Every active minimap, extracted to the render world each frame.
+/// 所有活动的小地图，每帧提取到渲染世界
+#[derive(Resource, Debug, Clone, Default, ExtractResource)]
+pub(crate) struct FogMinimapViews {
+    pub views: Vec<FogMinimapView>,
+}
+
+/// Collects the minimaps and decides which ones are redrawn this frame.
+/// 收集小地图并决定本帧重绘哪些
+pub(crate) fn update_fog_minimaps(
+    time: Res<Time>,
+    fog: FogQuery,
+    mut minimaps: Query<&mut FogMinimap>,
+    mut views: ResMut<FogMinimapViews>,
+) {
+    views.views.clear();
+    if minimaps.is_empty() {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    let viewer = fog.viewer_team();
+    let last_team = fog.settings().effective_team_count() - 1;
+    for mut minimap in minimaps.iter_mut() {
+        if minimap.region.is_empty() {
+            continue;
+        }
+        let redraw = minimap.tick(now);
+        views.views.push(FogMinimapView {
+            image: minimap.image.clone(),
+            region: minimap.region,
+            team: u32::from(minimap.team.unwrap_or(viewer).0).min(last_team),
+            show_snapshots: minimap.show_snapshots,
+            background: minimap.background.to_linear().to_vec4(),
+            redraw,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_interval_throttles_redraws() {
+        let mut minimap = FogMinimap::new(Handle::default(), Rect::new(0.0, 0.0, 1.0, 1.0))
+            .with_update_interval(0.5);
+        assert!(minimap.tick(1.0));
+        assert!(!minimap.tick(1.2));
+        assert!(minimap.tick(1.5));
+
+        minimap.request_redraw();
+        assert!(minimap.tick(1.6));
+
+        // Without an interval every frame redraws
+        minimap.update_interval = 0.0;
+        assert!(minimap.tick(1.6));
+    }
+}
@@ -0,0 +1,239 @@
+//! Visibility change messages for tagged entities.
+//! 被标记实体的可见性变化消息
+//!
+//! Entities carrying [`FogObserved`] are checked against every team's fog once chunk
+//! state is up to date for the frame. Transitions are written as
+//! [`EntityEnteredVision`], [`EntityLeftVision`] and [`EntityExplored`] messages, so
+//! UI alerts and AI don't have to poll [`FogQuery`] themselves.
+
+use crate::prelude::*;
+use bevy_transform::components::GlobalTransform;
+
+/// Marks an entity whose vision state changes are reported as messages.
+/// 标记需要以消息报告其视野状态变化的实体
+///
+/// Visibility follows the same rules as [`FogHideable`], evaluated for every team:
+/// undetected [`Stealth`] entities are out of vision, and teams sharing vision with
+/// the entity's own [`FogTeam`] always see it and get no messages.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn spawn(mut commands: Commands) {
+///     commands.spawn((Transform::default(), FogObserved::default(), FogTeam(1)));
+/// }
+///
+/// fn enemy_spotted(mut entered: MessageReader<EntityEnteredVision>) {
+///     for message in entered.read() {
+///         if message.team == FogTeam(0) {
+///             info!("Enemy spotted: {:?}", message.entity);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Component, Default)]
+pub struct FogObserved {
+    /// Bit per team that saw the entity on the last update.
+    visible: u32,
+
+    /// Bit per team whose explored area contained the entity on the last update.
+    explored: u32,
+}
+
+impl FogObserved {
+    /// Returns true if `team` saw the entity on the last update.
+    /// 如果上次更新时 `team` 看到了该实体则返回 true
+    pub fn is_visible_to(&self, team: FogTeam) -> bool {
+        Self::has(self.visible, team)
+    }
+
+    /// Returns true if the entity stood in an area explored by `team` on the last update.
+    /// 如果上次更新时实体位于 `team` 已探索的区域内则返回 true
+    pub fn is_explored_by(&self, team: FogTeam) -> bool {
+        Self::has(self.explored, team)
+    }
+
+    fn has(mask: u32, team: FogTeam) -> bool {
+        u32::from(team.0) < MAX_TEAMS && mask & (1 << team.0) != 0
+    }
+}
+
+/// Message sent when a [`FogObserved`] entity enters a team's vision.
+/// 当被观察实体进入某队伍视野时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityEnteredVision {
+    /// The observed entity.
+    pub entity: Entity,
+    /// Team that now sees it.
+    pub team: FogTeam,
+}
+
+/// Message sent when a [`FogObserved`] entity leaves a team's vision.
+/// 当被观察实体离开某队伍视野时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityLeftVision {
+    /// The observed entity.
+    pub entity: Entity,
+    /// Team that no longer sees it.
+    pub team: FogTeam,
+}
+
+/// Message sent when a [`FogObserved`] entity's position becomes explored for a team,
+/// either because the team explored it or because the entity moved into explored area.
+/// 当被观察实体所在位置对某队伍变为已探索时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityExplored {
+    /// The observed entity.
+    pub entity: Entity,
+    /// Team whose explored area now contains it.
+    pub team: FogTeam,
+}
+
+/// Observed entities, with their team and stealth level.
+type ObservedQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static mut FogObserved,
+        Option<&'static FogTeam>,
+        Option<&'static Stealth>,
+    ),
+>;
+
+/// Compares every [`FogObserved`] entity's vision state with the last update and
+/// writes a message per changed team.
+/// 将每个被观察实体的视野状态与上次更新比较，并为每个发生变化的队伍写入消息
+pub(crate) fn update_fog_observed(
+    fog: FogQuery,
+    mut observed: ObservedQuery,
+    mut entered_writer: MessageWriter<EntityEnteredVision>,
+    mut left_writer: MessageWriter<EntityLeftVision>,
+    mut explored_writer: MessageWriter<EntityExplored>,
+) {
+    if observed.is_empty() {
+        return;
+    }
+
+    let sampler = fog.sampler();
+    let settings = fog.settings();
+    let teams = || (0..settings.effective_team_count()).map(|index| FogTeam(index as u8));
+
+    for (entity, transform, mut state, own_team, stealth) in observed.iter_mut() {
+        let position = settings.world_to_fog(transform.translation());
+        let own_team = own_team.copied();
+
+        let mut visible = 0;
+        let mut explored = 0;
+        for team in teams() {
+            let bit = 1 << team.0;
+            if sampler.sees_entity(team, position, own_team, stealth) {
+                visible |= bit;
+            }
+            if fog.is_explored_for(team, position) {
+                explored |= bit;
+            }
+        }
+        if visible == state.visible && explored == state.explored {
+            continue;
+        }
+
+        let previous = std::mem::replace(&mut *state, FogObserved { visible, explored });
+        for team in teams().filter(|&team| !sampler.is_friendly(team, own_team)) {
+            let bit = 1 << team.0;
+            if visible & bit != 0 && previous.visible & bit == 0 {
+                entered_writer.write(EntityEnteredVision { entity, team });
+            } else if visible & bit == 0 && previous.visible & bit != 0 {
+                left_writer.write(EntityLeftVision { entity, team });
+            }
+            if explored & bit != 0 && previous.explored & bit == 0 {
+                explored_writer.write(EntityExplored { entity, team });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::message::Messages;
+
+    fn drain<M: Message>(app: &mut App) -> Vec<M> {
+        app.world_mut()
+            .resource_mut::<Messages<M>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn test_vision_messages_fire_once_per_team_and_transition() {
+        let mut app = App::new();
+        app.insert_resource(FogMapSettings {
+            team_count: 3,
+            ..Default::default()
+        })
+        .init_resource::<ChunkStateCache>()
+        .init_resource::<FogAlliances>()
+        .init_resource::<ActiveReveals>()
+        .add_message::<EntityEnteredVision>()
+        .add_message::<EntityLeftVision>()
+        .add_message::<EntityExplored>()
+        .add_systems(Update, update_fog_observed);
+
+        let world = app.world_mut();
+        world.spawn((GlobalTransform::IDENTITY, VisionSource::circle(100.0)));
+        world.spawn((
+            GlobalTransform::from_xyz(300.0, 0.0, 0.0),
+            VisionSource::circle(100.0),
+            FogTeam(1),
+        ));
+        let scout = world
+            .spawn((
+                GlobalTransform::from_xyz(10.0, 0.0, 0.0),
+                FogObserved::default(),
+                FogTeam(2),
+            ))
+            .id();
+
+        app.update();
+        assert_eq!(
+            drain::<EntityEnteredVision>(&mut app),
+            vec![EntityEnteredVision {
+                entity: scout,
+                team: FogTeam(0)
+            }]
+        );
+        app.update();
+        assert!(drain::<EntityEnteredVision>(&mut app).is_empty());
+
+        // Walking from team 0's vision into team 1's
+        *app.world_mut().get_mut::<GlobalTransform>(scout).unwrap() =
+            GlobalTransform::from_xyz(300.0, 0.0, 0.0);
+        app.update();
+        assert_eq!(
+            drain::<EntityLeftVision>(&mut app),
+            vec![EntityLeftVision {
+                entity: scout,
+                team: FogTeam(0)
+            }]
+        );
+        assert_eq!(
+            drain::<EntityEnteredVision>(&mut app),
+            vec![EntityEnteredVision {
+                entity: scout,
+                team: FogTeam(1)
+            }]
+        );
+
+        app.update();
+        assert!(drain::<EntityEnteredVision>(&mut app).is_empty());
+        assert!(drain::<EntityLeftVision>(&mut app).is_empty());
+        let state = app.world().get::<FogObserved>(scout).unwrap();
+        assert!(state.is_visible_to(FogTeam(1)) && !state.is_visible_to(FogTeam(0)));
+        // The scout's own team always sees it without messages
+        assert!(state.is_visible_to(FogTeam(2)));
+    }
+}
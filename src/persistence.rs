@@ -73,7 +73,7 @@ use bevy_app::{App, Plugin};
 use bevy_asset::Assets;
 use bevy_ecs::system::SystemParam;
 use bevy_image::Image;
-use bevy_log::{error, info, warn};
+use bevy_log::{debug, error, info, warn};
 use bevy_math::{IVec2, Rect, UVec2};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -420,6 +420,10 @@ pub struct ChunkSaveData {
     /// 快照纹理数据（可选）
     /// Snapshot texture data (optional)
     pub snapshot_data: Option<Vec<u8>>,
+    /// 探索过此区块的队伍（为空表示队伍 0，兼容旧存档）
+    /// Teams that have explored this chunk (empty means team 0, for older saves)
+    #[serde(default)]
+    pub explored_by: Vec<FogTeam>,
 }
 
 /// 保存元数据
@@ -438,6 +442,14 @@ pub struct SaveMetadata {
     /// 地图名称或 ID（可选）
     /// Map name or ID (optional)
     pub map_id: Option<String>,
+    /// 队伍数量（用于验证，雾效数据每个队伍一层）
+    /// Team count (for validation, fog data holds one layer per team)
+    #[serde(default = "default_team_count")]
+    pub team_count: u32,
+}
+
+fn default_team_count() -> u32 {
+    1
 }
 
 /// 请求保存雾效数据的事件
@@ -519,6 +531,9 @@ pub struct PendingSaveData {
     /// 保存的区块信息（不包含纹理数据）
     /// Chunk information to save (without texture data)
     pub chunk_info: Vec<(IVec2, ChunkVisibility, Option<u32>, Option<u32>)>, // (coords, visibility, fog_idx, snap_idx)
+    /// 每个区块的探索队伍
+    /// Teams that explored each chunk
+    pub explored_by: HashMap<IVec2, Vec<FogTeam>>,
 }
 
 /// 雾效持久化错误
@@ -540,6 +555,9 @@ pub enum PersistenceError {
     /// 无效的纹理分辨率
     /// Invalid texture resolution
     InvalidTextureResolution { expected: UVec2, found: UVec2 },
+    /// 无效的队伍数量
+    /// Invalid team count
+    InvalidTeamCount { expected: u32, found: u32 },
 }
 
 impl std::fmt::Display for PersistenceError {
@@ -566,6 +584,9 @@ impl std::fmt::Display for PersistenceError {
                     "Invalid texture resolution: expected {expected:?}, found {found:?}"
                 )
             }
+            PersistenceError::InvalidTeamCount { expected, found } => {
+                write!(f, "Invalid team count: expected {expected}, found {found}")
+            }
         }
     }
 }
@@ -590,7 +611,9 @@ impl std::error::Error for PersistenceError {}
 /// # Validation Checks
 /// - **Chunk Size**: Must match current settings
 /// - **Texture Resolution**: Must match current settings
+/// - **Team Count**: Must match current settings (fog data holds one layer per team)
 /// - **Plugin Version**: Logged for compatibility tracking
+/// - **World Bounds**: Chunks outside `FogMapSettings::world_bounds` are skipped
 ///
 /// # Layer Index Strategy
 /// - **Preserve Original**: Attempts to restore to original layer indices
@@ -601,6 +624,7 @@ impl std::error::Error for PersistenceError {}
 /// Returns specific errors for different failure modes:
 /// - `InvalidChunkSize`: Chunk size mismatch
 /// - `InvalidTextureResolution`: Texture resolution mismatch
+/// - `InvalidTeamCount`: Team count mismatch
 ///
 /// # Performance Characteristics
 /// - **Memory Reset**: O(existing_chunks) for state cleanup
@@ -632,6 +656,12 @@ pub fn load_save_data(
                 found: metadata.texture_resolution,
             });
         }
+        if metadata.team_count != settings.effective_team_count() {
+            return Err(PersistenceError::InvalidTeamCount {
+                expected: settings.effective_team_count(),
+                found: metadata.team_count,
+            });
+        }
     }
 
     // 注意：加载数据时清除当前状态，但保存时不应该重置任何状态
@@ -643,9 +673,33 @@ pub fn load_save_data(
     // 恢复区块状态
     // Restore chunk states
     for chunk_data in &data.chunks {
-        // 添加到已探索区块集合
-        // Add to explored chunks set
+        // 跳过地图边界之外的区块（存档可能来自无边界或更大的地图）
+        // Skip chunks outside the map (the save may come from an unbounded or larger map)
+        if !settings.is_chunk_in_world(chunk_data.coords) {
+            debug!(
+                "Skipping saved chunk {:?} outside world bounds",
+                chunk_data.coords
+            );
+            continue;
+        }
+
+        // 添加到已探索区块集合（包括各队伍集合）
+        // Add to explored chunks set (including per-team sets)
         cache.explored_chunks.insert(chunk_data.coords);
+        if chunk_data.explored_by.is_empty() {
+            cache
+                .team_explored_chunks
+                .entry(FogTeam::default())
+                .or_default()
+                .insert(chunk_data.coords);
+        }
+        for team in &chunk_data.explored_by {
+            cache
+                .team_explored_chunks
+                .entry(*team)
+                .or_default()
+                .insert(chunk_data.coords);
+        }
 
         if chunk_data.visibility == ChunkVisibility::Visible {
             cache.visible_chunks.insert(chunk_data.coords);
@@ -809,6 +863,7 @@ pub fn save_fog_of_war_system(mut params: SaveSystemParams) {
         // 收集需要保存的区块信息
         // Collect chunk information to save
         let mut chunk_info = Vec::new();
+        let mut explored_by = HashMap::new();
         let mut awaiting_chunks = std::collections::HashSet::new();
 
         for &coords in &params.cache.explored_chunks {
@@ -836,6 +891,7 @@ pub fn save_fog_of_war_system(mut params: SaveSystemParams) {
                 };
 
             chunk_info.push((coords, visibility, fog_idx, snap_idx));
+            explored_by.insert(coords, params.cache.teams_exploring(coords));
 
             // 如果需要纹理数据且区块在GPU上，请求GPU到CPU传输
             // If texture data needed and chunk is on GPU, request GPU-to-CPU transfer
@@ -867,6 +923,7 @@ pub fn save_fog_of_war_system(mut params: SaveSystemParams) {
             match create_save_data_immediate(
                 &params.settings,
                 chunk_info,
+                explored_by,
                 HashMap::new(),
                 event.include_texture_data,
             ) {
@@ -887,6 +944,7 @@ pub fn save_fog_of_war_system(mut params: SaveSystemParams) {
                 awaiting_chunks: awaiting_chunks.clone(),
                 received_data: HashMap::new(),
                 chunk_info,
+                explored_by,
             };
 
             params.pending_saves.pending_save = Some(pending);
@@ -993,6 +1051,7 @@ pub fn handle_gpu_data_ready_system(
                         match create_save_data_immediate(
                             &settings,
                             pending.chunk_info,
+                            pending.explored_by,
                             pending.received_data,
                             pending.include_texture_data,
                         ) {
@@ -1045,6 +1104,7 @@ pub fn handle_gpu_data_ready_system(
 /// - **Plugin Version**: Current crate version from CARGO_PKG_VERSION
 /// - **Chunk Size**: World units per chunk for compatibility validation
 /// - **Texture Resolution**: Texture size per chunk for format validation
+/// - **Team Count**: Fog layers per chunk for format validation
 /// - **Timestamp**: Unix timestamp for save ordering and identification
 ///
 /// # Performance Characteristics
@@ -1083,6 +1143,7 @@ pub fn handle_gpu_data_ready_system(
 fn create_save_data_immediate(
     settings: &FogMapSettings,
     chunk_info: Vec<(IVec2, ChunkVisibility, Option<u32>, Option<u32>)>, // (coords, visibility, fog_idx, snap_idx)
+    mut explored_by: HashMap<IVec2, Vec<FogTeam>>,
     texture_data: HashMap<IVec2, (Vec<u8>, Vec<u8>)>, // (fog_data, snapshot_data)
     include_texture_data: bool,
 ) -> Result<FogOfWarSaveData, PersistenceError> {
@@ -1122,6 +1183,7 @@ fn create_save_data_immediate(
             snapshot_layer_index: snap_idx,
             fog_data,
             snapshot_data,
+            explored_by: explored_by.remove(&coords).unwrap_or_default(),
         });
     }
 
@@ -1136,6 +1198,7 @@ fn create_save_data_immediate(
             chunk_size: settings.chunk_size,
             texture_resolution: settings.texture_resolution_per_chunk,
             map_id: None,
+            team_count: settings.effective_team_count(),
         }),
     })
 }
@@ -1612,3 +1675,108 @@ impl Plugin for FogOfWarPersistencePlugin {
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::world::CommandQueue;
+
+    fn two_team_settings() -> FogMapSettings {
+        FogMapSettings {
+            texture_resolution_per_chunk: UVec2::splat(4),
+            team_count: 2,
+            ..Default::default()
+        }
+    }
+
+    fn two_team_save(settings: &FogMapSettings) -> FogOfWarSaveData {
+        let shared = IVec2::ZERO;
+        let team_1_only = IVec2::new(1, 0);
+        // One 4x4 fog layer per team: team 0 explored fully, team 1 half way
+        let mut fog = vec![255u8; 16];
+        fog.extend([128u8; 16]);
+        let chunk_info = vec![
+            (shared, ChunkVisibility::Explored, Some(0), Some(0)),
+            (team_1_only, ChunkVisibility::Explored, Some(1), Some(1)),
+        ];
+        let explored_by = HashMap::from([
+            (shared, vec![FogTeam(0), FogTeam(1)]),
+            (team_1_only, vec![FogTeam(1)]),
+        ]);
+        let texture_data = HashMap::from([(shared, (fog, vec![0u8; 64]))]);
+        create_save_data_immediate(settings, chunk_info, explored_by, texture_data, true).unwrap()
+    }
+
+    fn load(
+        data: &FogOfWarSaveData,
+        settings: &FogMapSettings,
+    ) -> (World, ChunkStateCache, Result<usize, PersistenceError>) {
+        let mut world = World::new();
+        world.init_resource::<Messages<RequestChunkSnapshot>>();
+        let mut cache = ChunkStateCache::default();
+        let mut chunk_manager = ChunkEntityManager::default();
+        let mut texture_manager = TextureArrayManager::new(MAX_LAYERS);
+        let mut images = Assets::<Image>::default();
+        let mut queue = CommandQueue::default();
+        let result = load_save_data(
+            data,
+            settings,
+            &mut cache,
+            &mut Commands::new(&mut queue, &world),
+            &mut chunk_manager,
+            &mut texture_manager,
+            &mut images,
+        );
+        queue.apply(&mut world);
+        world.insert_resource(chunk_manager);
+        world.insert_resource(images);
+        (world, cache, result)
+    }
+
+    #[test]
+    fn test_two_team_save_restores_each_team_separately() {
+        let settings = two_team_settings();
+        let data = two_team_save(&settings);
+        assert_eq!(data.metadata.as_ref().unwrap().team_count, 2);
+
+        let (mut world, cache, result) = load(&data, &settings);
+        assert_eq!(result.unwrap(), 2);
+        assert!(cache.is_explored_by(FogTeam(0), IVec2::ZERO));
+        assert!(cache.is_explored_by(FogTeam(1), IVec2::ZERO));
+        assert!(!cache.is_explored_by(FogTeam(0), IVec2::new(1, 0)));
+        assert!(cache.is_explored_by(FogTeam(1), IVec2::new(1, 0)));
+
+        // Each team's layer comes back in its own slice of the chunk's fog image
+        let entity = world.resource::<ChunkEntityManager>().map[&IVec2::ZERO];
+        let handle = world
+            .query::<&FogChunkImage>()
+            .get(&world, entity)
+            .unwrap()
+            .fog_image_handle
+            .clone();
+        let images = world.resource::<Assets<Image>>();
+        let fog = images.get(&handle).unwrap().data.as_ref().unwrap();
+        assert_eq!(fog.len(), 32);
+        assert!(fog[..16].iter().all(|&v| v == 255));
+        assert!(fog[16..].iter().all(|&v| v == 128));
+    }
+
+    #[test]
+    fn test_save_with_other_team_count_is_rejected() {
+        let data = two_team_save(&two_team_settings());
+        let one_team = FogMapSettings {
+            team_count: 1,
+            ..two_team_settings()
+        };
+
+        let (_, cache, result) = load(&data, &one_team);
+        assert!(matches!(
+            result,
+            Err(PersistenceError::InvalidTeamCount {
+                expected: 1,
+                found: 2
+            })
+        ));
+        assert!(cache.explored_chunks.is_empty());
+    }
+}
@@ -100,6 +100,45 @@ pub use crate::settings::*;
 // GPU纹理资源句柄
 pub use crate::texture_handles::*;
 
+// Team Alliances
+// 队伍同盟
+pub use crate::alliances::FogAlliances;
+
+// CPU-Side Fog Queries
+// CPU 端雾效查询
+pub use crate::visibility::{FogQuery, VisionSampler};
+
+// Timed Reveal Areas
+// 定时揭示区域
+pub use crate::reveal::{ActiveReveal, ActiveReveals, RevealArea};
+
+// Last-Seen Ghost Markers
+// 最后目击幽灵标记
+pub use crate::ghost::{FogGhost, FogLastSeen};
+
+// Vision Change Messages
+// 视野变化消息
+pub use crate::observed::{EntityEnteredVision, EntityExplored, EntityLeftVision, FogObserved};
+
+// Fog Minimaps
+// 迷雾小地图
+pub use crate::minimap::FogMinimap;
+
+// Exploration Progress
+// 探索进度
+pub use crate::stats::{
+    ChunkBecameVisible, ChunkExplored, ChunkLeftVision, ExploredFractionReady, FogStats,
+    RequestExploredFraction,
+};
+
+// Stealth and Detection
+// 隐身与侦测
+pub use crate::stealth::{Detection, EntityDetected, EntityUndetected, Stealth};
+
+// Tile-Grid Vision Blockers
+// 网格视野阻挡
+pub use crate::vision_blocker::VisionBlockerGrid;
+
 // Core Plugin, Components, and Snapshot System
 // 核心插件、组件和快照系统
 pub use crate::{FogOfWarPlugin, components::*, snapshot::*};
@@ -46,7 +46,7 @@
 //! ```text
 //! workgroups_x = texture_resolution.x.div_ceil(8)  // X-axis workgroups
 //! workgroups_y = texture_resolution.y.div_ceil(8)  // Y-axis workgroups
-//! workgroups_z = active_chunk_count               // Z-axis per chunk
+//! workgroups_z = active_chunk_count * team_count  // Z-axis per chunk and team
 //! total_threads = workgroups_x * workgroups_y * workgroups_z * 64
 //! ```
 //!
@@ -58,6 +58,8 @@
 //! - **2**: Vision source buffer (Storage, ReadOnly) - Vision source parameters
 //! - **3**: Chunk compute buffer (Storage, ReadOnly) - Chunk metadata
 //! - **4**: Fog settings uniform (Uniform, ReadOnly) - Global fog configuration
+//! - **5**: Occluder segment buffer (Storage, ReadOnly) - Line-of-sight blockers
+//! - **6**: Vision blocker texture array (R8Unorm, ReadOnly) - Tile-grid blockers
 //!
 //! ## Texture Format Details
 //! - **R8Unorm**: Single-channel 8-bit normalized format (0.0-1.0 range)
@@ -97,14 +99,17 @@
 //! - **GPU Culling**: GPU-side chunk visibility culling
 
 use super::prepare::{FogBindGroups, GpuChunkInfoBuffer};
-use crate::render::extract::{ChunkComputeData, RenderFogMapSettings, VisionSourceData};
+use crate::render::extract::{
+    ChunkComputeData, OccluderSegmentData, RenderFogMapSettings, VisionSourceData,
+};
 use bevy_asset::DirectAssetAccessExt;
 use bevy_ecs::prelude::*;
+use bevy_math::Vec2;
 use bevy_render::{
     render_resource::{
         BindGroupLayoutDescriptor, BindGroupLayoutEntries, CachedComputePipelineId,
         ComputePassDescriptor, ComputePipelineDescriptor, PipelineCache, ShaderStages,
-        StorageTextureAccess::{ReadWrite, WriteOnly},
+        StorageTextureAccess::{ReadOnly, ReadWrite, WriteOnly},
         TextureFormat,
         binding_types::{storage_buffer_read_only, texture_storage_2d_array, uniform_buffer},
     },
@@ -150,6 +155,9 @@ const SHADER_ASSET_PATH: &str = "shaders/fog_compute.wgsl";
 /// @group(0) @binding(2) var<storage, read> vision_sources: array<VisionSourceData>;
 /// @group(0) @binding(3) var<storage, read> chunks: array<ChunkComputeData>;
 /// @group(0) @binding(4) var<uniform> settings: RenderFogMapSettings;
+/// @group(0) @binding(5) var<storage, read> occluder_segments: array<OccluderSegmentData>;
+/// @group(0) @binding(6) var vision_blocker_texture: texture_storage_2d_array<r8unorm, read>;
+/// @group(0) @binding(7) var<storage, read> polygon_vertices: array<vec2<f32>>;
 /// ```
 ///
 /// # Performance Characteristics
@@ -160,7 +168,7 @@ const SHADER_ASSET_PATH: &str = "shaders/fog_compute.wgsl";
 ///
 /// # Shader Integration
 /// The pipeline interfaces with the fog compute shader which implements:
-/// - **Vision Shape Algorithms**: Circle, cone, and square vision calculations
+/// - **Vision Shape Algorithms**: Circle, cone, square, and polygon vision calculations
 /// - **Line-of-Sight**: Raycasting for vision blocking obstacles
 /// - **Texture Updates**: Direct fog texture modification
 /// - **Parallel Processing**: Multi-threaded GPU execution
@@ -198,12 +206,15 @@ pub struct FogComputePipeline {
 /// 5. **Resource Storage**: Store pipeline ID and layout for runtime use
 ///
 /// # Bind Group Layout Structure
-/// Creates a sequential binding layout with 5 bindings:
+/// Creates a sequential binding layout with 8 bindings:
 /// - **Binding 0**: Visibility texture array (R8Unorm, ReadWrite)
 /// - **Binding 1**: Fog texture array (R8Unorm, WriteOnly)
 /// - **Binding 2**: Vision source storage buffer (ReadOnly)
 /// - **Binding 3**: Chunk compute data storage buffer (ReadOnly)
 /// - **Binding 4**: Fog settings uniform buffer (ReadOnly)
+/// - **Binding 5**: Occluder segment storage buffer (ReadOnly)
+/// - **Binding 6**: Vision blocker texture array (R8Unorm, ReadOnly)
+/// - **Binding 7**: Polygon vertex storage buffer (ReadOnly)
 ///
 /// # Shader Compilation
 /// The pipeline descriptor specifies:
@@ -260,6 +271,9 @@ impl FromWorld for FogComputePipeline {
                     storage_buffer_read_only::<VisionSourceData>(false),         // 2
                     storage_buffer_read_only::<ChunkComputeData>(false),         // 3
                     uniform_buffer::<RenderFogMapSettings>(false),               // 4
+                    storage_buffer_read_only::<OccluderSegmentData>(false),      // 5
+                    texture_storage_2d_array(TextureFormat::R8Unorm, ReadOnly),  // 6
+                    storage_buffer_read_only::<Vec2>(false),                     // 7
                 ),
             ),
         );
@@ -305,7 +319,7 @@ impl FromWorld for FogComputePipeline {
 /// // Each workgroup covers 8x8 texture pixels
 /// workgroups_x = texture_resolution.x.div_ceil(8)
 /// workgroups_y = texture_resolution.y.div_ceil(8)
-/// workgroups_z = active_chunk_count
+/// workgroups_z = active_chunk_count * team_count
 ///
 /// // Total threads = workgroups_x × workgroups_y × workgroups_z × 64
 /// ```
@@ -349,8 +363,8 @@ pub fn fog_compute_system(
     let workgroup_size_y = 8;
     let workgroups_x = texture_res.x.div_ceil(workgroup_size_x);
     let workgroups_y = texture_res.y.div_ceil(workgroup_size_y);
-    // Dispatch per chunk / 按区块分派
-    let workgroups_z = chunk_count as u32;
+    // Dispatch per chunk and team / 按区块和队伍分派
+    let workgroups_z = chunk_count as u32 * settings.team_count.max(1);
 
     let mut compute_pass =
         render_context
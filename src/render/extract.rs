@@ -70,6 +70,7 @@
 //! ## Render World Resources
 //! - **RenderFogMapSettings**: GPU-compatible settings structure
 //! - **ExtractedVisionSources**: Processed vision source data
+//! - **ExtractedVisionOccluders**: World-space occluder segments for line of sight
 //! - **ExtractedGpuChunkData**: Culled and formatted chunk information
 //! - **Render Texture Handles**: GPU texture access handles
 //!
@@ -95,15 +96,19 @@
 //! - **Overlay Systems**: Render final fog effects using extracted information
 //! - **Transfer Systems**: Handle CPU↔GPU memory operations
 
+use crate::camera_ground_rect;
+use crate::decay::ExploredDecay;
+use crate::minimap::FogMinimapViews;
 use crate::prelude::*;
 use bevy_asset::Handle;
-use bevy_camera::Projection;
+use bevy_camera::{Camera, Projection};
 use bevy_color::ColorToComponents;
 use bevy_derive::{Deref, DerefMut};
 use bevy_image::Image;
-use bevy_math::{IVec2, Rect, UVec2, Vec2, Vec3, Vec4};
+use bevy_math::{Affine2, IVec2, Rect, UVec2, Vec2, Vec3, Vec4};
 use bevy_render::Extract;
 use bevy_render::render_resource::ShaderType;
+use bevy_render::sync_world::RenderEntity;
 use bevy_transform::components::GlobalTransform;
 use bytemuck::{Pod, Zeroable};
 
@@ -130,6 +135,18 @@ use bytemuck::{Pod, Zeroable};
 ///     fog_color_explored: vec4<f32>,
 ///     vision_clear_color: vec4<f32>,
 ///     enabled: u32,
+///     blocker_grid_enabled: u32,
+///     blocker_cells_per_chunk: vec2<u32>,
+///     team_count: u32,
+///     shared_vision: u32,
+///     shared_explored: u32,
+///     explored_decay: f32,
+///     fog_color_outside: vec4<f32>,
+///     world_bounds: vec4<f32>,
+///     world_bounds_enabled: u32,
+///     ground_plane: u32,
+///     fog_translation: vec2<f32>,
+///     fog_matrix: vec4<f32>,
 /// }
 /// @group(0) @binding(4) var<uniform> settings: FogSettings;
 /// ```
@@ -150,14 +167,25 @@ use bytemuck::{Pod, Zeroable};
 /// 32     | 16   | fog_color_explored (Vec4)
 /// 48     | 16   | vision_clear_color (Vec4)
 /// 64     | 4    | enabled (u32)
-/// 68     | 12   | _padding1 (alignment)
-/// Total: 80 bytes (GPU-aligned)
+/// 68     | 4    | blocker_grid_enabled (u32)
+/// 72     | 8    | blocker_cells_per_chunk (UVec2)
+/// 80     | 4    | team_count (u32)
+/// 84     | 4    | shared_vision (u32)
+/// 88     | 4    | shared_explored (u32)
+/// 92     | 4    | explored_decay (f32)
+/// 96     | 16   | fog_color_outside (Vec4)
+/// 112    | 16   | world_bounds (Vec4)
+/// 128    | 4    | world_bounds_enabled (u32)
+/// 132    | 4    | ground_plane (u32)
+/// 136    | 8    | fog_translation (Vec2)
+/// 144    | 16   | fog_matrix (Vec4)
+/// Total: 160 bytes (GPU-aligned)
 /// ```
 ///
 /// # Performance Characteristics
 /// - **Transfer Cost**: Minimal - single small uniform buffer update per frame
 /// - **GPU Access**: Extremely fast uniform buffer access in shaders
-/// - **Memory Usage**: 80 bytes total, negligible memory overhead
+/// - **Memory Usage**: 160 bytes total, negligible memory overhead
 /// - **Cache Efficiency**: Small size fits in GPU cache lines
 #[allow(dead_code)]
 #[derive(Resource, Debug, Clone, Copy, Pod, Zeroable, ShaderType)]
@@ -210,12 +238,59 @@ pub struct RenderFogMapSettings {
     /// Boolean values are represented as u32 for GPU compatibility.
     pub enabled: u32,
 
-    /// Padding to ensure proper GPU memory alignment.
-    /// 确保适当GPU内存对齐的填充
+    /// Whether a `VisionBlockerGrid` is present (0 = no, 1 = yes).
+    /// 是否存在 `VisionBlockerGrid`（0 = 否，1 = 是）
+    ///
+    /// Lets the compute shader skip grid ray-marching entirely when unused.
+    pub blocker_grid_enabled: u32,
+
+    /// Number of blocker cells along each axis of a chunk.
+    /// 每个区块每个轴上的阻挡单元格数量
+    ///
+    /// Also the per-layer size of the blocker texture array.
+    pub blocker_cells_per_chunk: UVec2,
+
+    /// Number of fog teams; fog and visibility layers are `slot * team_count + team`.
+    /// 雾效队伍数量；雾效和可见性层索引为 `slot * team_count + team`
+    pub team_count: u32,
+
+    /// `FogAlliances` shared-vision matrix, bit `viewer * MAX_TEAMS + source`.
+    /// `FogAlliances` 共享视野矩阵，位索引为 `viewer * MAX_TEAMS + source`
+    pub shared_vision: u32,
+
+    /// `FogAlliances` shared-explored matrix, same bit layout as `shared_vision`.
+    /// `FogAlliances` 共享探索矩阵，位布局与 `shared_vision` 相同
+    pub shared_explored: u32,
+
+    /// Explored value texels out of sight lose this frame (0 when decay is off).
+    /// 视野外纹素本帧损失的探索值（关闭衰减时为 0）
     ///
-    /// This padding ensures the structure size is a multiple of 16 bytes
-    /// as required by GPU uniform buffer alignment rules.
-    pub _padding1: [u32; 3],
+    /// Always a whole number of 8-bit steps, see `FogMapSettings::explored_decay_rate`.
+    pub explored_decay: f32,
+
+    /// Color drawn outside the world bounds.
+    /// 世界边界之外区域的颜色
+    pub fog_color_outside: Vec4,
+
+    /// `FogMapSettings::world_bounds` as `(min.x, min.y, max.x, max.y)`.
+    /// 以 `(min.x, min.y, max.x, max.y)` 表示的 `FogMapSettings::world_bounds`
+    pub world_bounds: Vec4,
+
+    /// Whether `world_bounds` applies (0 = unbounded, 1 = bounded).
+    /// `world_bounds` 是否生效（0 = 无边界，1 = 有边界）
+    pub world_bounds_enabled: u32,
+
+    /// `FogMapSettings::ground_plane` (0 = XY, 1 = XZ).
+    /// `FogMapSettings::ground_plane`（0 = XY，1 = XZ）
+    pub ground_plane: u32,
+
+    /// Translation of `FogMapSettings::fog_transform`.
+    /// `FogMapSettings::fog_transform` 的平移部分
+    pub fog_translation: Vec2,
+
+    /// Linear part of `FogMapSettings::fog_transform`, columns packed as `(x_axis, y_axis)`.
+    /// `FogMapSettings::fog_transform` 的线性部分，按列打包为 `(x_axis, y_axis)`
+    pub fog_matrix: Vec4,
 }
 
 /// Render world resource containing processed vision source data for GPU consumption.
@@ -261,8 +336,44 @@ pub struct ExtractedVisionSources {
     /// converted to a format suitable for direct GPU buffer creation. The vector
     /// is cleared and repopulated each frame during the extraction phase.
     pub sources: Vec<VisionSourceData>,
+
+    /// World-space vertices of every polygon source, indexed by `polygon_offset`.
+    /// 所有多边形视野源的世界空间顶点，通过 `polygon_offset` 索引
+    ///
+    /// Never empty: a single unused vertex is kept so the storage buffer can be bound.
+    pub polygon_vertices: Vec<Vec2>,
 }
 
+/// Render world resource containing world-space occluder segments for line-of-sight tests.
+/// 包含用于视线测试的世界空间遮挡线段的渲染世界资源
+///
+/// Every enabled `VisionOccluder` is flattened into its outline segments during
+/// extraction. The compute shader tests the ray from each vision source to each
+/// texel against these segments and discards visibility that is blocked.
+///
+/// # GPU Buffer Usage
+/// ```wgsl
+/// @group(0) @binding(5) var<storage, read> occluder_segments: array<OccluderSegmentData>;
+/// ```
+///
+/// # Fallback Behavior
+/// If no occluders exist, a single zero-length segment is inserted so the storage
+/// buffer is never empty. Degenerate segments never block anything in the shader.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ExtractedVisionOccluders {
+    /// World-space occluder segments, rebuilt every frame.
+    /// 每帧重建的世界空间遮挡线段
+    pub segments: Vec<OccluderSegmentData>,
+}
+
+/// Team whose fog a camera view renders, extracted from the camera's `FogTeam`.
+/// 相机视图所渲染的队伍雾效，从相机的 `FogTeam` 提取
+///
+/// Inserted on every camera's render entity. Cameras without a `FogTeam` render team
+/// `0`, and teams outside `FogMapSettings::team_count` are clamped to the last team.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct ExtractedViewFogTeam(pub u32);
+
 /// Render world resource containing processed chunk data for GPU compute and overlay operations.
 /// 包含用于GPU计算和覆盖操作的已处理区块数据的渲染世界资源
 ///
@@ -356,6 +467,17 @@ pub struct RenderVisibilityTexture(pub Handle<Image>);
 #[derive(Resource, Clone, Deref, DerefMut)]
 pub struct RenderSnapshotTexture(pub Handle<Image>);
 
+/// Render world resource providing access to the vision blocker texture array.
+/// 为GPU操作提供视野阻挡纹理数组访问的渲染世界资源
+///
+/// # Usage in Shaders
+/// Bound as a read-only storage texture in the compute shader:
+/// ```wgsl
+/// @group(0) @binding(6) var vision_blocker_texture: texture_storage_2d_array<r8unorm, read>;
+/// ```
+#[derive(Resource, Clone, Deref, DerefMut)]
+pub struct RenderVisionBlockerTexture(pub Handle<Image>);
+
 /// Render world resource providing access to the temporary snapshot texture for capture operations.
 /// 为捕获操作提供临时快照纹理访问的渲染世界资源
 ///
@@ -393,14 +515,31 @@ pub struct RenderSnapshotTempTexture(pub Handle<Image>);
 /// 32     | 4    | cos_direction       | Precomputed cos(direction)
 /// 36     | 4    | sin_direction       | Precomputed sin(direction)
 /// 40     | 4    | cone_half_angle_cos | Precomputed cos(angle/2)
-/// 44     | 4    | _padding1           | Alignment padding
-/// Total: 48 bytes (GPU-aligned)
+/// 44     | 4    | eye_height          | Viewer height for blockers
+/// 48     | 4    | team                | Owning `FogTeam`
+/// 52     | 4    | polygon_offset      | First polygon vertex
+/// 56     | 4    | polygon_len         | Polygon vertex count
+/// 60     | 4    | shape_param         | Annulus inner radius / capsule length
+/// 64     | 4    | falloff_type        | Falloff curve (0-4)
+/// 68     | 4    | falloff_param       | Sharpness / curve sample count
+/// 72     | 32   | falloff_curve       | Sampled falloff curve
+/// 104    | 4    | flags               | `VISION_FLAG_*` bits
+/// 108    | 4    | _padding            | Keeps the stride a multiple of 8
+/// Total: 112 bytes (GPU-aligned)
 /// ```
 ///
 /// # Vision Shape Types
 /// - **0 (Circle)**: Omnidirectional vision with configurable radius
 /// - **1 (Cone)**: Directional vision with angle and direction parameters
-/// - **2 (Square/Rectangle)**: Rectangular vision area (future implementation)
+/// - **2 (Square/Rectangle)**: Square vision area rotated by direction
+/// - **3 (Polygon)**: World-space vertices in `ExtractedVisionSources::polygon_vertices`
+/// - **4 (Annulus)**: Ring from `shape_param` out to the radius
+/// - **5 (Capsule)**: Beam of length `shape_param` along the direction
+///
+/// # Falloff Types
+/// - **0 (Linear)**, **1 (Smoothstep)**, **3 (Step)**: No parameters
+/// - **2 (Exponential)**: Sharpness in `falloff_param`
+/// - **4 (Curve)**: Sample count in `falloff_param`, samples in `falloff_curve`
 ///
 /// # Performance Optimizations
 /// - **Precomputed Trigonometry**: cos/sin values calculated on CPU
@@ -422,6 +561,16 @@ pub struct RenderSnapshotTempTexture(pub Handle<Image>);
 ///     cos_direction: f32,
 ///     sin_direction: f32,
 ///     cone_half_angle_cos: f32,
+///     eye_height: f32,
+///     team: u32,
+///     polygon_offset: u32,
+///     polygon_len: u32,
+///     shape_param: f32,
+///     falloff_type: u32,
+///     falloff_param: f32,
+///     falloff_curve: array<f32, 8>,
+///     flags: u32,
+///     _padding: u32,
 /// }
 /// ```
 #[derive(Copy, Clone, ShaderType, Pod, Zeroable, Debug)]
@@ -441,13 +590,16 @@ pub struct VisionSourceData {
     /// are not affected by this vision source.
     pub radius: f32,
 
-    /// Vision shape type identifier (0=Circle, 1=Cone, 2=Rectangle).
-    /// 视野形状类型标识符（0=圆形，1=锥形，2=矩形）
+    /// Vision shape type identifier (0=Circle, 1=Cone, 2=Rectangle, 3=Polygon, 4=Annulus, 5=Capsule).
+    /// 视野形状类型标识符（0=圆形，1=锥形，2=矩形，3=多边形，4=环形，5=胶囊）
     ///
     /// Determines which algorithm the shader uses for vision calculations:
     /// - **0**: Circle - omnidirectional vision
     /// - **1**: Cone - directional vision with angle constraints
-    /// - **2**: Rectangle - rectangular area vision (planned)
+    /// - **2**: Rectangle - square area vision rotated by direction
+    /// - **3**: Polygon - arbitrary simple polygon
+    /// - **4**: Annulus - ring between an inner and outer radius
+    /// - **5**: Capsule - beam along the direction
     pub shape_type: u32,
 
     /// Vision direction in radians (for directional vision shapes).
@@ -499,11 +651,85 @@ pub struct VisionSourceData {
     /// the cone's angular bounds. Precomputed for GPU efficiency.
     pub cone_half_angle_cos: f32,
 
-    /// Padding to ensure proper GPU memory alignment.
-    /// 确保适当GPU内存对齐的填充
-    ///
-    /// Ensures the structure size is 48 bytes, meeting GPU alignment requirements.
-    pub _padding1: f32,
+    /// Height of the viewer's eyes, compared against occluder and grid heights.
+    /// 视野源的眼睛高度，用于与遮挡物和网格高度比较
+    pub eye_height: f32,
+
+    /// Team whose fog layers this source writes to (`FogTeam`, default 0).
+    /// 此视野源写入的雾效层所属队伍（`FogTeam`，默认 0）
+    pub team: u32,
+
+    /// Index of the first vertex in the polygon vertex buffer (polygon shape only).
+    /// 多边形顶点缓冲区中第一个顶点的索引（仅多边形）
+    pub polygon_offset: u32,
+
+    /// Number of polygon vertices; zero for other shapes.
+    /// 多边形顶点数量；其他形状为零
+    pub polygon_len: u32,
+
+    /// Extra shape parameter: annulus inner radius or capsule length, otherwise zero.
+    /// 额外形状参数：环形内半径或胶囊长度，其他形状为零
+    pub shape_param: f32,
+
+    /// Falloff curve identifier (0=Linear, 1=Smoothstep, 2=Exponential, 3=Step, 4=Curve).
+    /// 衰减曲线标识符（0=线性，1=平滑，2=指数，3=阶跃，4=采样曲线）
+    pub falloff_type: u32,
+
+    /// Exponential sharpness, or the number of used samples for a sampled curve.
+    /// 指数衰减的锐度，或采样曲线使用的样本数
+    pub falloff_param: f32,
+
+    /// Samples of a sampled falloff curve, unused entries zeroed.
+    /// 采样衰减曲线的样本，未使用的条目为零
+    pub falloff_curve: [f32; MAX_FALLOFF_SAMPLES],
+
+    /// `VISION_FLAG_*` bits controlling what the source writes.
+    /// 控制视野源写入内容的 `VISION_FLAG_*` 标志位
+    pub flags: u32,
+
+    /// Unused, pads the struct to the WGSL array stride.
+    /// 未使用，将结构体填充到 WGSL 数组步长
+    pub _padding: u32,
+}
+
+/// `VisionSourceData::flags` bit: texels this source sees are marked explored.
+/// `VisionSourceData::flags` 标志位：此视野源看到的纹素会被标记为已探索
+pub const VISION_FLAG_EXPLORES: u32 = 1;
+
+/// `VisionSourceData::flags` bit: the source adds to this frame's visibility.
+/// `VisionSourceData::flags` 标志位：此视野源计入本帧可见度
+pub const VISION_FLAG_REVEALS: u32 = 2;
+
+/// GPU-compatible occluder segment matching the WGSL shader layout.
+/// 与WGSL着色器布局匹配的GPU兼容遮挡线段
+///
+/// # Memory Layout
+/// ```text
+/// Offset | Size | Field    | Purpose
+/// -------|------|----------|------------------------
+/// 0      | 8    | start    | Segment start (world)
+/// 8      | 8    | end      | Segment end (world)
+/// 16     | 4    | height   | Occluder height
+/// 20     | 4    | _padding | Alignment padding
+/// Total: 24 bytes (GPU-aligned)
+/// ```
+#[derive(Copy, Clone, ShaderType, Pod, Zeroable, Debug)]
+#[repr(C)]
+pub struct OccluderSegmentData {
+    /// Segment start point in world coordinates.
+    /// 线段起点（世界坐标）
+    pub start: Vec2,
+
+    /// Segment end point in world coordinates.
+    /// 线段终点（世界坐标）
+    pub end: Vec2,
+
+    /// Height of the owning occluder; sources with a lower eye height are blocked.
+    /// 所属遮挡物的高度；眼睛高度更低的视野源会被阻挡
+    pub height: f32,
+
+    /// Padding to keep the array stride a multiple of 8 bytes.
+    pub _padding: f32,
 }
 
 /// Minimal chunk data structure optimized for compute shader processing.
@@ -640,14 +866,21 @@ pub struct OverlayChunkData {
 /// # Performance Characteristics
 /// - **Frequency**: Runs every frame during extraction phase
 /// - **Cost**: Minimal - simple data conversion and copy
-/// - **Memory**: Single 80-byte structure allocation
+/// - **Memory**: Single 160-byte structure allocation
 /// - **Time Complexity**: O(1) - constant time conversion
 ///
 /// # Integration Points
-/// - **Main World**: Reads from FogMapSettings resource
+/// - **Main World**: Reads from FogMapSettings and FogAlliances resources
 /// - **Render World**: Creates RenderFogMapSettings resource
 /// - **GPU Shaders**: Consumed as uniform buffer in compute and overlay shaders
-pub fn extract_fog_settings(mut commands: Commands, settings: Extract<Res<FogMapSettings>>) {
+pub fn extract_fog_settings(
+    mut commands: Commands,
+    settings: Extract<Res<FogMapSettings>>,
+    blocker_grid: Extract<Option<Res<VisionBlockerGrid>>>,
+    alliances: Extract<Res<FogAlliances>>,
+    explored_decay: Extract<Res<ExploredDecay>>,
+) {
+    let blocker_grid = blocker_grid.as_deref();
     commands.insert_resource(RenderFogMapSettings {
         enabled: settings.enabled as u32,
         chunk_size: settings.chunk_size,
@@ -655,7 +888,23 @@ pub fn extract_fog_settings(mut commands: Commands, settings: Extract<Res<FogMap
         fog_color_unexplored: settings.fog_color_unexplored.to_linear().to_vec4(),
         fog_color_explored: settings.fog_color_explored.to_linear().to_vec4(),
         vision_clear_color: settings.vision_clear_color.to_linear().to_vec4(),
-        _padding1: [0; 3],
+        blocker_grid_enabled: blocker_grid.is_some() as u32,
+        blocker_cells_per_chunk: blocker_grid.map_or(UVec2::ONE, |grid| grid.cells_per_chunk()),
+        team_count: settings.effective_team_count(),
+        shared_vision: alliances.shared_vision_bits(),
+        shared_explored: alliances.shared_explored_bits(),
+        explored_decay: explored_decay.frame_amount(),
+        fog_color_outside: settings.fog_color_outside.to_linear().to_vec4(),
+        world_bounds: settings.world_bounds.map_or(Vec4::ZERO, |bounds| {
+            Vec4::new(bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y)
+        }),
+        world_bounds_enabled: settings.world_bounds.is_some() as u32,
+        ground_plane: match settings.ground_plane {
+            FogGroundPlane::XY => 0,
+            FogGroundPlane::XZ => 1,
+        },
+        fog_translation: settings.fog_transform.translation,
+        fog_matrix: Vec4::from_array(settings.fog_transform.matrix2.to_cols_array()),
     });
 }
 
@@ -671,6 +920,7 @@ pub fn extract_fog_settings(mut commands: Commands, settings: Extract<Res<FogMap
 /// - **VisibilityTextureArray**: Real-time visibility calculations
 /// - **SnapshotTextureArray**: Captured entity snapshots
 /// - **SnapshotTempTexture**: Temporary texture for snapshot capture
+/// - **VisionBlockerTextureArray**: Tile-grid line-of-sight blockers
 ///
 /// # Handle Management
 /// - **Clone Operations**: Handles are cloned, not moved, preserving main world access
@@ -698,6 +948,7 @@ pub fn extract_texture_handles(
     visibility_texture: Extract<Res<VisibilityTextureArray>>,
     snapshot_texture: Extract<Res<SnapshotTextureArray>>,
     snapshot_temp_texture: Extract<Res<SnapshotTempTexture>>,
+    blocker_texture: Extract<Res<VisionBlockerTextureArray>>,
 ) {
     // Ensure the handles exist in the RenderWorld / 确保句柄存在于 RenderWorld 中
     commands.insert_resource(RenderFogTexture(fog_texture.handle.clone()));
@@ -706,6 +957,7 @@ pub fn extract_texture_handles(
     commands.insert_resource(RenderSnapshotTempTexture(
         snapshot_temp_texture.handle.clone(),
     ));
+    commands.insert_resource(RenderVisionBlockerTexture(blocker_texture.handle.clone()));
 }
 
 /// Extracts and processes vision source entities for GPU shader consumption.
@@ -717,7 +969,8 @@ pub fn extract_texture_handles(
 ///
 /// # Processing Pipeline
 /// 1. **Entity Query**: Find all entities with GlobalTransform and VisionSource
-/// 2. **Filtering**: Include only enabled vision sources
+/// 2. **Filtering**: Include only enabled vision sources, then running `RevealArea`s
+///    with their faded intensity
 /// 3. **Coordinate Extraction**: Get world position from GlobalTransform
 /// 4. **Shape Conversion**: Convert VisionShape enum to numeric GPU format
 /// 5. **Trigonometric Precalculation**: Compute cos/sin values for GPU efficiency
@@ -726,7 +979,10 @@ pub fn extract_texture_handles(
 /// # Shape Type Mapping
 /// - **VisionShape::Circle** → 0: Omnidirectional circular vision
 /// - **VisionShape::Cone** → 1: Directional cone-shaped vision
-/// - **VisionShape::Square** → 2: Rectangular area vision (future)
+/// - **VisionShape::Square** → 2: Square area vision rotated by direction
+/// - **VisionShape::Polygon** → 3: Polygon, vertices transformed to world space
+/// - **VisionShape::Annulus** → 4: Ring, inner radius in `shape_param`
+/// - **VisionShape::Capsule** → 5: Beam, length in `shape_param`
 ///
 /// # Performance Optimizations
 /// - **CPU Trigonometry**: cos/sin calculated on CPU to reduce GPU load
@@ -741,7 +997,7 @@ pub fn extract_texture_handles(
 /// # Performance Characteristics
 /// - **Entity Processing**: O(n) where n = number of vision source entities
 /// - **Trigonometric Cost**: O(n) CPU trigonometric calculations
-/// - **Memory**: Allocates n × 48 bytes for vision source data
+/// - **Memory**: Allocates n × 112 bytes for vision source data
 /// - **GPU Benefit**: Eliminates trigonometric calculations in GPU shaders
 ///
 /// # Integration Points
@@ -750,48 +1006,49 @@ pub fn extract_texture_handles(
 /// - **GPU Systems**: Used to create vision source storage buffer for shaders
 pub fn extract_vision_sources(
     mut sources_res: ResMut<ExtractedVisionSources>,
-    vision_sources: Extract<Query<(&GlobalTransform, &VisionSource)>>,
+    settings: Extract<Res<FogMapSettings>>,
+    vision_sources: Extract<Query<(&GlobalTransform, &VisionSource, Option<&FogTeam>)>>,
+    reveals: Extract<Res<ActiveReveals>>,
 ) {
-    sources_res.sources.clear();
-    sources_res
-        .sources
-        .extend(
-            vision_sources
-                .iter()
-                .filter(|(_, src)| src.enabled)
-                .map(|(transform, src)| {
-                    // 将形状枚举转换为数值
-                    // Convert shape enum to numeric value
-                    let shape_type = match src.shape {
-                        VisionShape::Circle => 0u32,
-                        VisionShape::Cone => 1u32,
-                        VisionShape::Square => 2u32,
-                    };
-
-                    let cos_dir = src.direction.cos();
-                    let sin_dir = src.direction.sin();
-                    // For cone, angle is the full FOV. Shader uses half_angle.
-                    // 对于扇形，angle 是完整的视场角。Shader 使用半角。
-                    let cone_cos_half_angle = (src.angle * 0.5).cos();
-
-                    VisionSourceData {
-                        position: transform.translation().truncate(),
-                        radius: src.range,
-                        shape_type,
-                        direction_rad: src.direction, // Store original direction in radians / 存储原始方向（弧度）
-                        angle_rad: src.angle, // Store original angle in radians / 存储原始角度（弧度）
-                        intensity: src.intensity,
-                        transition_ratio: src.transition_ratio,
-                        cos_direction: cos_dir,
-                        sin_direction: sin_dir,
-                        cone_half_angle_cos: cone_cos_half_angle,
-                        _padding1: 0.0, // Initialize padding / 初始化填充
-                    }
-                }),
-        );
-
-    if sources_res.sources.is_empty() {
-        sources_res.sources.push(VisionSourceData {
+    let ExtractedVisionSources {
+        sources,
+        polygon_vertices,
+    } = &mut *sources_res;
+    sources.clear();
+    polygon_vertices.clear();
+
+    // Same sources as the CPU side: enabled and on a team in use
+    // 与 CPU 端相同的视野源：已启用且属于使用中的队伍
+    for (transform, src, team) in vision_sources.iter() {
+        let team = team.copied().unwrap_or_default();
+        if !src.enabled || !settings.contains_team(team) {
+            continue;
+        }
+        sources.push(vision_source_data(
+            settings.world_to_fog(transform.translation()),
+            src,
+            team,
+            polygon_vertices,
+        ));
+    }
+
+    // Running reveal areas use their faded copy of the source
+    // 正在生效的揭示区域使用经过淡入淡出处理的视野源副本
+    for reveal in reveals.active(&settings) {
+        sources.push(vision_source_data(
+            reveal.area().position,
+            reveal.source(),
+            reveal.area().team,
+            polygon_vertices,
+        ));
+    }
+
+    if polygon_vertices.is_empty() {
+        polygon_vertices.push(Vec2::ZERO);
+    }
+
+    if sources.is_empty() {
+        sources.push(VisionSourceData {
             position: Default::default(),
             radius: 0.0,
             shape_type: 0, // Circle by default / 默认为圆形
@@ -802,7 +1059,161 @@ pub fn extract_vision_sources(
             cos_direction: 1.0,       // cos(0)
             sin_direction: 0.0,       // sin(0)
             cone_half_angle_cos: 1.0, // cos(0 * 0.5)
-            _padding1: 0.0,
+            eye_height: 0.0,
+            team: 0,
+            polygon_offset: 0,
+            polygon_len: 0,
+            shape_param: 0.0,
+            falloff_type: 0,
+            falloff_param: 0.0,
+            falloff_curve: [0.0; MAX_FALLOFF_SAMPLES],
+            flags: 0,
+            _padding: 0,
+        });
+    }
+}
+
+/// Packs one vision source at `position` into its GPU layout, appending polygon
+/// vertices in world space.
+/// 将位于 `position` 的视野源打包为 GPU 布局，并以世界坐标追加多边形顶点
+fn vision_source_data(
+    position: Vec2,
+    src: &VisionSource,
+    team: FogTeam,
+    polygon_vertices: &mut Vec<Vec2>,
+) -> VisionSourceData {
+    // 将形状枚举转换为数值
+    // Convert shape enum to numeric value
+    let (shape_type, shape_param) = match &src.shape {
+        VisionShape::Circle => (0u32, 0.0),
+        VisionShape::Cone => (1u32, 0.0),
+        VisionShape::Square => (2u32, 0.0),
+        VisionShape::Polygon { .. } => (3u32, 0.0),
+        VisionShape::Annulus { inner_radius } => (4u32, *inner_radius),
+        VisionShape::Capsule { length } => (5u32, *length),
+    };
+
+    let mut falloff_curve = [0.0; MAX_FALLOFF_SAMPLES];
+    let (falloff_type, falloff_param) = match &src.falloff {
+        VisionFalloff::Linear => (0u32, 0.0),
+        VisionFalloff::Smoothstep => (1u32, 0.0),
+        VisionFalloff::Exponential { sharpness } => (2u32, *sharpness),
+        VisionFalloff::Step => (3u32, 0.0),
+        VisionFalloff::Curve { samples } => {
+            let len = samples.len().min(MAX_FALLOFF_SAMPLES);
+            falloff_curve[..len].copy_from_slice(&samples[..len]);
+            (4u32, len as f32)
+        }
+    };
+
+    let mut flags = 0;
+    if src.reveals {
+        flags |= VISION_FLAG_REVEALS;
+    }
+    if src.explores {
+        flags |= VISION_FLAG_EXPLORES;
+    }
+
+    let cos_dir = src.direction.cos();
+    let sin_dir = src.direction.sin();
+    // For cone, angle is the full FOV. Shader uses half_angle.
+    // 对于扇形，angle 是完整的视场角。Shader 使用半角。
+    let cone_cos_half_angle = (src.angle * 0.5).cos();
+
+    // Polygon vertices are rotated and translated here so the shader reads world space
+    // 多边形顶点在此旋转并平移，着色器直接读取世界空间坐标
+    let polygon: &[Vec2] = match &src.shape {
+        VisionShape::Polygon { vertices } if vertices.len() >= 3 => vertices,
+        _ => &[],
+    };
+    let rotation = Vec2::new(cos_dir, sin_dir);
+    let polygon_offset = polygon_vertices.len() as u32;
+    polygon_vertices.extend(polygon.iter().map(|v| position + rotation.rotate(*v)));
+    let polygon_len = polygon.len() as u32;
+
+    VisionSourceData {
+        position,
+        radius: src.outer_radius(),
+        shape_type,
+        direction_rad: src.direction, // Store original direction in radians / 存储原始方向（弧度）
+        angle_rad: src.angle,         // Store original angle in radians / 存储原始角度（弧度）
+        intensity: src.intensity,
+        transition_ratio: src.transition_ratio,
+        cos_direction: cos_dir,
+        sin_direction: sin_dir,
+        cone_half_angle_cos: cone_cos_half_angle,
+        eye_height: src.eye_height,
+        team: u32::from(team.0),
+        polygon_offset,
+        polygon_len,
+        shape_param,
+        falloff_type,
+        falloff_param,
+        falloff_curve,
+        flags,
+        _padding: 0,
+    }
+}
+
+/// Cameras with their render entity and optional `FogTeam`.
+type CameraTeamsQuery<'w, 's> =
+    Query<'w, 's, (RenderEntity, Option<&'static FogTeam>), With<Camera>>;
+
+/// Extracts the `FogTeam` of every camera onto its render entity.
+/// 将每个相机的 `FogTeam` 提取到其渲染实体上
+pub fn extract_view_fog_teams(
+    mut commands: Commands,
+    settings: Extract<Res<FogMapSettings>>,
+    cameras: Extract<CameraTeamsQuery>,
+) {
+    let last_team = settings.effective_team_count() - 1;
+    for (render_entity, team) in cameras.iter() {
+        let team = u32::from(team.copied().unwrap_or_default().0).min(last_team);
+        if let Ok(mut entity) = commands.get_entity(render_entity) {
+            entity.insert(ExtractedViewFogTeam(team));
+        }
+    }
+}
+
+/// Extracts `VisionOccluder` geometry as world-space segments for the compute shader.
+/// 将 `VisionOccluder` 几何体提取为供计算着色器使用的世界空间线段
+///
+/// Segments, polygons and boxes are all reduced to their outline edges so the
+/// shader only needs a single segment-intersection test.
+///
+/// # Performance Characteristics
+/// - **Entity Processing**: O(n) where n = total occluder edges
+/// - **Memory**: 24 bytes per edge
+/// - **GPU Cost**: Each texel tests every edge for every vision source it is inside of
+pub fn extract_vision_occluders(
+    mut occluders_res: ResMut<ExtractedVisionOccluders>,
+    settings: Extract<Res<FogMapSettings>>,
+    occluders: Extract<Query<(&GlobalTransform, &VisionOccluder)>>,
+) {
+    occluders_res.segments.clear();
+    for (transform, occluder) in occluders.iter().filter(|(_, o)| o.enabled) {
+        occluders_res
+            .segments
+            .extend(
+                occluder
+                    .fog_segments(transform, &settings)
+                    .into_iter()
+                    .map(|(start, end)| OccluderSegmentData {
+                        start,
+                        end,
+                        height: occluder.height,
+                        _padding: 0.0,
+                    }),
+            );
+    }
+
+    if occluders_res.segments.is_empty() {
+        // Zero-length segment never intersects / 零长度线段永远不会相交
+        occluders_res.segments.push(OccluderSegmentData {
+            start: Vec2::ZERO,
+            end: Vec2::ZERO,
+            height: 0.0,
+            _padding: 0.0,
         });
     }
 }
@@ -815,6 +1226,18 @@ pub fn extract_vision_sources(
 /// invalid chunks.
 const GFX_INVALID_LAYER: i32 = -1;
 
+/// Fog cameras with the transform and projection their ground footprint needs.
+type FogCamerasQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Camera,
+        &'static GlobalTransform,
+        &'static Projection,
+    ),
+    With<FogOfWarCamera>,
+>;
+
 /// Extracts and processes chunk data with frustum culling for efficient GPU processing.
 /// 提取和处理区块数据并进行视锥剔除以实现高效GPU处理
 ///
@@ -832,7 +1255,8 @@ const GFX_INVALID_LAYER: i32 = -1;
 /// 6. **Fallback Insertion**: Ensure minimum data for GPU operations
 ///
 /// # Frustum Culling Algorithm
-/// Uses axis-aligned bounding box intersection for orthographic cameras:
+/// Uses axis-aligned bounding box intersection against the camera's view on the
+/// ground plane:
 /// ```rust,ignore
 /// // AABB intersection test
 /// is_visible = !(chunk.max.x < view.min.x || chunk.min.x > view.max.x ||
@@ -841,8 +1265,14 @@ const GFX_INVALID_LAYER: i32 = -1;
 ///
 /// # Camera Support
 /// - **Orthographic**: Full frustum culling support with accurate bounds calculation
-/// - **Perspective**: Warning issued, falls back to no culling (future enhancement)
+/// - **Perspective / Tilted**: Bounds of the frustum's footprint on `FogMapSettings::ground_plane`
+/// - **Custom Projection**: Warning issued, falls back to no culling
+/// - **Multiple Cameras**: A chunk is kept if it is in view of any active `FogOfWarCamera`
+/// - **Minimaps**: Each `FogMinimap` region counts as one more view
 /// - **Missing Camera**: No culling performed, all GPU chunks processed
+/// - **Explored Decay**: While `explored_decay_rate` is set, `compute_chunks` is not
+///   culled so that explored chunks out of view keep decaying; `overlay_mapping` is
+///   still culled
 ///
 /// # Memory State Filtering
 /// Only processes chunks in specific memory states:
@@ -856,9 +1286,12 @@ const GFX_INVALID_LAYER: i32 = -1;
 /// - **Small Scenes**: Minimal overhead, still beneficial
 /// - **Camera Movement**: Culling adapts dynamically to camera position
 /// - **GPU Workload**: Directly proportional to visible chunk count
+/// - **Explored Decay**: The compute pass runs over every GPU-resident chunk, so its
+///   cost grows with the number of resident chunks rather than those in view; the
+///   overlay and minimap lookups stay proportional to the chunks in view
 ///
 /// # Data Structure Creation
-/// Creates two synchronized data sets:
+/// Creates two data sets, culled separately (see Explored Decay above):
 /// - **compute_chunks**: Minimal data for compute shader operations
 /// - **overlay_mapping**: Extended data for overlay rendering with snapshots
 ///
@@ -876,44 +1309,70 @@ const GFX_INVALID_LAYER: i32 = -1;
 pub fn extract_gpu_chunk_data(
     mut chunk_data_res: ResMut<ExtractedGpuChunkData>,
     settings: Extract<Res<FogMapSettings>>,
-    camera_query: Extract<Query<(&GlobalTransform, &Projection), With<FogOfWarCamera>>>,
+    camera_query: Extract<FogCamerasQuery>,
+    minimaps: Extract<Res<FogMinimapViews>>,
     fog_chunk_query: Extract<Query<&FogChunk>>,
 ) {
     chunk_data_res.compute_chunks.clear();
     chunk_data_res.overlay_mapping.clear();
 
-    let mut view_aabb_world: Option<Rect> = None;
-
-    if let Ok((camera_transform, projection)) = camera_query.single() {
-        // Calculate view AABB for an orthographic camera
-        // This assumes the FogOfWarCamera is orthographic. Handle perspective if needed.
-        if let Projection::Orthographic(ortho_projection) = projection {
-            let camera_scale: Vec3 = camera_transform.compute_transform().scale;
-            // ortho_projection.area gives the size of the projection area.
-            // For WindowSize scale mode, this area is in logical pixels, needing viewport size.
-            // For Fixed scale mode, this area is in world units.
-            // We'll assume Fixed scale mode or that area is already in appropriate units
-            // that can be scaled by camera_transform.scale to get world dimensions.
-            // A more robust way for WindowSize would be to use camera.logical_viewport_size().
-            let half_width = ortho_projection.area.width() * 0.5 * camera_scale.x;
-            let half_height = ortho_projection.area.height() * 0.5 * camera_scale.y;
-            let camera_pos_2d = camera_transform.translation().truncate();
-
-            view_aabb_world = Some(Rect {
-                min: Vec2::new(camera_pos_2d.x - half_width, camera_pos_2d.y - half_height),
-                max: Vec2::new(camera_pos_2d.x + half_width, camera_pos_2d.y + half_height),
-            });
-        } else {
-            warn!(
-                "FogOfWarCamera is not using an OrthographicProjection. Culling might not work as expected for perspective cameras with this AABB logic."
-            );
-            // For perspective, you'd need to implement frustum culling.
+    // View AABB of every active FogOfWarCamera; `None` disables culling
+    // 每个活动 FogOfWarCamera 的视图 AABB；为 `None` 时不进行剔除
+    let mut view_aabbs_world: Option<Vec<Rect>> = Some(Vec::new());
+
+    for (camera, camera_transform, projection) in camera_query.iter() {
+        if !camera.is_active {
+            continue;
+        }
+        let view_aabb_world = match projection {
+            // Calculate view AABB for a top-down orthographic camera
+            Projection::Orthographic(ortho_projection)
+                if settings.ground_plane == FogGroundPlane::XY
+                    && settings.fog_transform == Affine2::IDENTITY =>
+            {
+                let camera_scale: Vec3 = camera_transform.compute_transform().scale;
+                // ortho_projection.area gives the size of the projection area.
+                // For WindowSize scale mode, this area is in logical pixels, needing viewport size.
+                // For Fixed scale mode, this area is in world units.
+                // We'll assume Fixed scale mode or that area is already in appropriate units
+                // that can be scaled by camera_transform.scale to get world dimensions.
+                // A more robust way for WindowSize would be to use camera.logical_viewport_size().
+                let half_width = ortho_projection.area.width() * 0.5 * camera_scale.x;
+                let half_height = ortho_projection.area.height() * 0.5 * camera_scale.y;
+                let camera_pos_2d = camera_transform.translation().truncate();
+
+                Some(Rect {
+                    min: Vec2::new(camera_pos_2d.x - half_width, camera_pos_2d.y - half_height),
+                    max: Vec2::new(camera_pos_2d.x + half_width, camera_pos_2d.y + half_height),
+                })
+            }
+            // Perspective or tilted cameras: the frustum's footprint on the ground plane
+            // 透视或倾斜相机：视锥在地面平面上的覆盖范围
+            _ => camera_ground_rect(camera, camera_transform, projection, &settings),
+        };
+
+        match (view_aabb_world, view_aabbs_world.as_mut()) {
+            (Some(view_rect), Some(view_rects)) => view_rects.push(view_rect),
+            (None, Some(_)) => {
+                warn!(
+                    "FogOfWarCamera footprint on the ground plane could not be computed. Fog chunk culling will not be performed."
+                );
+                view_aabbs_world = None;
+            }
+            (_, None) => {}
         }
-    } else {
-        warn!(
-            "No single FogOfWarCamera found, or multiple were found. Fog chunk culling will not be performed."
-        );
+    }
+
+    if view_aabbs_world.as_ref().is_some_and(Vec::is_empty) {
+        warn!("No active FogOfWarCamera found. Fog chunk culling will not be performed.");
         // If no camera, all GPU-ready chunks will be processed (original behavior for this path)
+        view_aabbs_world = None;
+    }
+
+    // Minimap regions count as views, so exploration inside them stays current
+    // 小地图区域视为视图，使其中的探索保持最新
+    if let Some(view_rects) = view_aabbs_world.as_mut() {
+        view_rects.extend(minimaps.views.iter().map(|view| view.region));
     }
 
     let chunk_world_size_f32 = settings.chunk_size.as_vec2();
@@ -933,27 +1392,34 @@ pub fn extract_gpu_chunk_data(
         };
 
         let mut is_visible_or_no_culling = true; // Default to true if culling is not active
-        if let Some(view_rect) = view_aabb_world {
-            // AABB intersection test
-            is_visible_or_no_culling = !(chunk_aabb_world.max.x < view_rect.min.x
-                || chunk_aabb_world.min.x > view_rect.max.x
-                || chunk_aabb_world.max.y < view_rect.min.y
-                || chunk_aabb_world.min.y > view_rect.max.y);
+        if let Some(view_rects) = view_aabbs_world.as_ref() {
+            // AABB intersection test against any camera view
+            // 与任一相机视图进行 AABB 相交测试
+            is_visible_or_no_culling = view_rects.iter().any(|view_rect| {
+                !(chunk_aabb_world.max.x < view_rect.min.x
+                    || chunk_aabb_world.min.x > view_rect.max.x
+                    || chunk_aabb_world.max.y < view_rect.min.y
+                    || chunk_aabb_world.min.y > view_rect.max.y)
+            });
         }
 
-        if is_visible_or_no_culling {
-            let fog_idx_gfx = chunk
-                .fog_layer_index
-                .map_or(GFX_INVALID_LAYER, |val| val as i32);
-            let snap_idx_gfx = chunk
-                .snapshot_layer_index
-                .map_or(GFX_INVALID_LAYER, |val| val as i32);
+        let fog_idx_gfx = chunk
+            .fog_layer_index
+            .map_or(GFX_INVALID_LAYER, |val| val as i32);
+        let snap_idx_gfx = chunk
+            .snapshot_layer_index
+            .map_or(GFX_INVALID_LAYER, |val| val as i32);
 
+        // Explored chunks out of view still have to decay in the compute pass
+        // 视野外的已探索区块仍需在计算通道中衰减
+        if is_visible_or_no_culling || settings.explored_decay_enabled() {
             chunk_data_res.compute_chunks.push(ChunkComputeData {
                 coords: chunk.coords,
                 fog_layer_index: fog_idx_gfx,
                 _padding: 0,
             });
+        }
+        if is_visible_or_no_culling {
             chunk_data_res.overlay_mapping.push(OverlayChunkData {
                 coords: chunk.coords,
                 fog_layer_index: fog_idx_gfx,
@@ -969,6 +1435,8 @@ pub fn extract_gpu_chunk_data(
             fog_layer_index: -1,
             _padding: 0,
         });
+    }
+    if chunk_data_res.overlay_mapping.is_empty() {
         chunk_data_res.overlay_mapping.push(OverlayChunkData {
             coords: Default::default(),
             fog_layer_index: -1,
@@ -0,0 +1,339 @@
+//! Minimap pass that draws the fog state of a region into an image.
+//! 将某一区域的迷雾状态绘制到图像中的小地图渲染通道
+//!
+//! Each [`FogMinimap`](crate::minimap::FogMinimap) due for a redraw gets one fullscreen
+//! triangle drawn into its image. The fragment shader maps the image's UVs onto the
+//! minimap region and samples the same visibility, fog and snapshot texture arrays
+//! and chunk mapping as the overlay, so no scene geometry is rendered again.
+//!
+//! # Scheduling
+//! The pass runs after `render_system`, once this frame's fog compute has been
+//! submitted, and records into its own command encoder:
+//! ```text
+//! FogComputeNode → FogOverlayNode → [render_system] → fog_minimap_system
+//! ```
+//!
+//! # Shader Resources
+//! - **Group 0**: sampler, visibility / fog / snapshot arrays, fog settings, chunk mapping
+//! - **Group 1**: per-minimap `FogMinimapUniform` (region, background, team)
+
+use super::RenderFogMapSettings;
+use super::extract::{
+    OverlayChunkData, RenderFogTexture, RenderSnapshotTexture, RenderVisibilityTexture,
+};
+use super::prepare::{FogUniforms, OverlayChunkMappingBuffer};
+use crate::minimap::FogMinimapViews;
+use bevy_asset::DirectAssetAccessExt;
+use bevy_core_pipeline::FullscreenShader;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use bevy_log::prelude::*;
+use bevy_math::Vec4;
+use bevy_render::{
+    render_asset::RenderAssets,
+    render_resource::binding_types::{
+        sampler, storage_buffer_read_only, texture_2d_array, uniform_buffer,
+    },
+    render_resource::*,
+    renderer::{RenderDevice, RenderQueue},
+    texture::{FallbackImage, GpuImage},
+};
+use bytemuck::{Pod, Zeroable};
+
+/// Path to the WGSL shader drawing minimap images.
+/// 绘制小地图图像的 WGSL 着色器路径
+const SHADER_ASSET_PATH: &str = "shaders/fog_minimap.wgsl";
+
+/// Texture format minimap images must use.
+/// 小地图图像必须使用的纹理格式
+const MINIMAP_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// Per-minimap uniform describing what to draw.
+/// 描述绘制内容的每个小地图 uniform
+///
+/// ```wgsl
+/// struct Minimap {
+///     region: vec4<f32>,
+///     background: vec4<f32>,
+///     team: u32,
+///     show_snapshots: u32,
+///     _padding1: u32,
+///     _padding2: u32,
+/// }
+/// @group(1) @binding(0) var<uniform> minimap: Minimap;
+/// ```
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable, ShaderType)]
+#[repr(C)]
+pub struct FogMinimapUniform {
+    /// Region in fog coordinates as `(min.x, min.y, max.x, max.y)`.
+    /// 雾效坐标下的区域 `(min.x, min.y, max.x, max.y)`
+    pub region: Vec4,
+    /// Linear background color under the fog.
+    /// 迷雾下方的线性背景颜色
+    pub background: Vec4,
+    /// Team index, already clamped to `team_count - 1`.
+    /// 队伍索引，已限制在 `team_count - 1` 以内
+    pub team: u32,
+    /// 1 if explored areas show their snapshots.
+    /// 已探索区域显示快照时为 1
+    pub show_snapshots: u32,
+    pub _padding1: u32,
+    pub _padding2: u32,
+}
+
+/// GPU render pipeline for minimap images.
+/// 小地图图像的 GPU 渲染管线
+///
+/// Uses Bevy's fullscreen vertex shader and replaces the target's contents, so
+/// every texel of the image is rewritten on each redraw.
+#[derive(Resource)]
+pub struct FogMinimapPipeline {
+    /// Layout of the shared fog resources (group 0).
+    /// 共享雾效资源的绑定组布局（group 0）
+    layout: BindGroupLayoutDescriptor,
+
+    /// Layout of the per-minimap `FogMinimapUniform` (group 1).
+    /// 每个小地图 `FogMinimapUniform` 的绑定组布局（group 1）
+    minimap_layout: BindGroupLayoutDescriptor,
+
+    /// Linear clamp-to-edge sampler for the texture arrays.
+    /// 纹理数组使用的线性、边缘钳制采样器
+    sampler: Sampler,
+
+    /// Cached render pipeline identifier.
+    /// 缓存的渲染管线标识符
+    pipeline_id: CachedRenderPipelineId,
+}
+
+impl FromWorld for FogMinimapPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = BindGroupLayoutDescriptor::new(
+            "fog_minimap_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    sampler(SamplerBindingType::Filtering), // 0: Texture sampler for filtering
+                    texture_2d_array(TextureSampleType::Float { filterable: true }), // 1: Visibility texture array
+                    texture_2d_array(TextureSampleType::Float { filterable: true }), // 2: Fog texture array
+                    texture_2d_array(TextureSampleType::Float { filterable: true }), // 3: Snapshot texture array
+                    uniform_buffer::<RenderFogMapSettings>(false), // 4: Fog settings uniform
+                    storage_buffer_read_only::<OverlayChunkData>(false), // 5: Chunk mapping storage buffer
+                ),
+            ),
+        );
+
+        let minimap_layout = BindGroupLayoutDescriptor::new(
+            "fog_minimap_uniform_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                uniform_buffer::<FogMinimapUniform>(false), // 0: Region, background and team
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("fog_minimap_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: MipmapFilterMode::Linear,
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            ..Default::default()
+        });
+
+        let shader = world.load_asset(SHADER_ASSET_PATH);
+        let fullscreen_shader = world.resource::<FullscreenShader>().shader().clone();
+
+        let pipeline_id =
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("fog_minimap_pipeline".into()),
+                    layout: vec![layout.clone(), minimap_layout.clone()],
+                    vertex: VertexState {
+                        shader: fullscreen_shader,
+                        shader_defs: vec![],
+                        entry_point: None,
+                        buffers: vec![],
+                    },
+                    fragment: Some(FragmentState {
+                        shader,
+                        shader_defs: vec![],
+                        entry_point: None,
+                        targets: vec![Some(ColorTargetState {
+                            format: MINIMAP_FORMAT,
+                            blend: None, // The minimap replaces the image / 小地图替换整张图像
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    immediate_size: 0,
+                    zero_initialize_workgroup_memory: false,
+                });
+
+        FogMinimapPipeline {
+            layout,
+            minimap_layout,
+            sampler,
+            pipeline_id,
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub(crate) struct FogMinimapSystemParams<'w> {
+    render_device: Res<'w, RenderDevice>,
+    render_queue: Res<'w, RenderQueue>,
+    minimaps: Option<Res<'w, FogMinimapViews>>,
+    minimap_pipeline: Res<'w, FogMinimapPipeline>,
+    pipeline_cache: Res<'w, PipelineCache>,
+    fog_uniforms: Res<'w, FogUniforms>,
+    overlay_chunk_buffer: Res<'w, OverlayChunkMappingBuffer>,
+    visibility_texture: Res<'w, RenderVisibilityTexture>,
+    fog_texture: Res<'w, RenderFogTexture>,
+    snapshot_texture: Res<'w, RenderSnapshotTexture>,
+    images: Res<'w, RenderAssets<GpuImage>>,
+    fallback_image: Res<'w, FallbackImage>,
+}
+
+/// Draws every minimap due for a redraw into its image.
+/// 将每个需要重绘的小地图绘制到其图像中
+///
+/// Minimaps whose image is not uploaded yet are skipped and drawn on their next
+/// redraw. Images in another format than `Rgba8UnormSrgb` are skipped with a warning.
+///
+/// # Time Complexity: O(m × w × h × c) for m minimaps of w × h texels and c mapped chunks
+pub(crate) fn fog_minimap_system(params: FogMinimapSystemParams) {
+    let Some(minimaps) = params.minimaps.as_ref() else {
+        return;
+    };
+    if !minimaps.views.iter().any(|view| view.redraw) {
+        return;
+    }
+
+    let Some(pipeline) = params
+        .pipeline_cache
+        .get_render_pipeline(params.minimap_pipeline.pipeline_id)
+    else {
+        // Pipeline not compiled yet / 管线尚未编译
+        return;
+    };
+    let (Some(uniform_buf), Some(mapping_buf)) = (
+        params.fog_uniforms.buffer.as_ref(),
+        params.overlay_chunk_buffer.buffer.as_ref(),
+    ) else {
+        return;
+    };
+
+    let visibility_texture_view = params
+        .images
+        .get(&params.visibility_texture.0)
+        .map(|img| &img.texture_view)
+        .unwrap_or(&params.fallback_image.d2.texture_view);
+    let fog_texture_view = params
+        .images
+        .get(&params.fog_texture.0)
+        .map(|img| &img.texture_view)
+        .unwrap_or(&params.fallback_image.d2.texture_view);
+    let snapshot_texture_view = params
+        .images
+        .get(&params.snapshot_texture.0)
+        .map(|img| &img.texture_view)
+        .unwrap_or(&params.fallback_image.d2.texture_view);
+
+    let layout = params
+        .pipeline_cache
+        .get_bind_group_layout(&params.minimap_pipeline.layout);
+    let bind_group = params.render_device.create_bind_group(
+        "fog_minimap_bind_group",
+        &layout,
+        &BindGroupEntries::sequential((
+            &params.minimap_pipeline.sampler,
+            visibility_texture_view,
+            fog_texture_view,
+            snapshot_texture_view,
+            uniform_buf.as_entire_binding(),
+            mapping_buf.as_entire_binding(),
+        )),
+    );
+    let minimap_layout = params
+        .pipeline_cache
+        .get_bind_group_layout(&params.minimap_pipeline.minimap_layout);
+
+    let mut command_encoder =
+        params
+            .render_device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("fog_minimap_encoder"),
+            });
+    let mut drawn = false;
+    for view in minimaps.views.iter().filter(|view| view.redraw) {
+        let Some(target) = params.images.get(&view.image) else {
+            continue; // Image not uploaded yet / 图像尚未上传
+        };
+        if target.texture_descriptor.format != MINIMAP_FORMAT {
+            warn!(
+                "FogMinimap image has format {:?}, expected {:?}. Skipping it.",
+                target.texture_descriptor.format, MINIMAP_FORMAT
+            );
+            continue;
+        }
+
+        let minimap_uniform = FogMinimapUniform {
+            region: Vec4::new(
+                view.region.min.x,
+                view.region.min.y,
+                view.region.max.x,
+                view.region.max.y,
+            ),
+            background: view.background,
+            team: view.team,
+            show_snapshots: u32::from(view.show_snapshots),
+            ..Default::default()
+        };
+        let minimap_buffer = params
+            .render_device
+            .create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("fog_minimap_uniform_buffer"),
+                contents: bytemuck::bytes_of(&minimap_uniform),
+                usage: BufferUsages::UNIFORM,
+            });
+        let minimap_bind_group = params.render_device.create_bind_group(
+            "fog_minimap_uniform_bind_group",
+            &minimap_layout,
+            &BindGroupEntries::single(minimap_buffer.as_entire_binding()),
+        );
+
+        let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("fog_minimap_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &target.texture_view,
+                resolve_target: None,
+                depth_slice: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Default::default()),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.set_bind_group(1, &minimap_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        drawn = true;
+    }
+
+    if drawn {
+        params
+            .render_queue
+            .submit(std::iter::once(command_encoder.finish()));
+    }
+}
@@ -17,7 +17,7 @@
 //! ```
 //!
 //! ## Render Graph Integration
-//! The fog of war system integrates into Bevy's Core2d render graph:
+//! The fog of war system integrates into Bevy's Core2d and Core3d render graphs:
 //! ```text
 //! MainTransparentPass → FogComputeNode → FogOverlayNode → EndMainPass
 //! ```
@@ -27,6 +27,8 @@
 //! ### Extract Systems (Main → Render World)
 //! - **extract_fog_settings**: Transfers FogMapSettings configuration
 //! - **extract_vision_sources**: Copies VisionSource entity data
+//! - **extract_vision_occluders**: Flattens VisionOccluder geometry into segments
+//! - **extract_view_fog_teams**: Copies each camera's FogTeam onto its render entity
 //! - **extract_gpu_chunk_data**: Transfers chunk state and texture information
 //! - **extract_texture_handles**: Synchronizes GPU texture array handles
 //!
@@ -61,13 +63,15 @@
 //! # Submodules
 //! - **compute**: GPU compute shader pipeline for fog calculations
 //! - **extract**: Main world to render world data extraction
+//! - **minimap**: Minimap images drawn from the fog texture arrays
 //! - **overlay**: Final fog overlay rendering and composition
 //! - **prepare**: GPU resource preparation and bind group management
 //! - **transfer**: CPU↔GPU memory transfer coordination
 
 use crate::prelude::*;
-use bevy_core_pipeline::Core2dSystems;
-use bevy_core_pipeline::schedule::Core2d;
+use bevy_core_pipeline::schedule::{Core2d, Core3d};
+use bevy_core_pipeline::{Core2dSystems, Core3dSystems};
+use bevy_render::render_resource::SpecializedRenderPipelines;
 use bevy_render::renderer::render_system;
 use bevy_render::{Render, RenderApp, RenderSystems};
 
@@ -75,13 +79,14 @@ use bevy_render::{Render, RenderApp, RenderSystems};
 // 渲染管线子模块
 mod compute; // GPU compute shader pipeline for fog calculations / GPU计算着色器管线用于雾效计算
 mod extract; // Main world to render world data extraction / 主世界到渲染世界的数据提取
+mod minimap; // Minimap images drawn from the fog textures / 由雾效纹理绘制的小地图图像
 mod overlay; // Final fog overlay rendering and composition / 最终雾效叠加渲染和合成
 mod prepare; // GPU resource preparation and bind group management / GPU资源准备和绑定组管理
 mod transfer; // CPU↔GPU memory transfer coordination / CPU↔GPU内存传输协调
 
 // Internal module imports for transfer system coordination
 // 用于传输系统协调的内部模块导入
-use crate::render::transfer::{CpuToGpuRequests, GpuToCpuActiveCopies};
+use crate::render::transfer::{ActiveFogReadbacks, CpuToGpuRequests, GpuToCpuActiveCopies};
 
 // Public re-exports of render system components
 // 渲染系统组件的公共重新导出
@@ -101,7 +106,8 @@ pub use overlay::fog_overlay_system;
 // GPU resource management components
 // GPU资源管理组件
 pub use prepare::{
-    FogBindGroups, FogUniforms, GpuChunkInfoBuffer, OverlayChunkMappingBuffer, VisionSourceBuffer,
+    FogBindGroups, FogUniforms, GpuChunkInfoBuffer, OverlayChunkMappingBuffer,
+    VisionOccluderBuffer, VisionPolygonBuffer, VisionSourceBuffer,
 };
 
 /// Plugin that configures the complete fog of war rendering pipeline in Bevy's render world.
@@ -162,7 +168,7 @@ impl Plugin for FogOfWarRenderPlugin {
     /// - **Reset Operations**: Clear textures during fog reset operations
     ///
     /// # Render Graph Integration
-    /// Adds fog nodes to Core2d render graph with proper dependencies:
+    /// Adds fog nodes to the Core2d and Core3d render graphs with proper dependencies:
     /// ```text
     /// MainTransparentPass → FogComputeNode → FogOverlayNode → EndMainPass
     /// ```
@@ -182,10 +188,14 @@ impl Plugin for FogOfWarRenderPlugin {
         render_app
             // Resources for extracted data / 用于提取数据的资源
             .init_resource::<extract::ExtractedVisionSources>()
+            .init_resource::<extract::ExtractedVisionOccluders>()
             .init_resource::<extract::ExtractedGpuChunkData>()
             .init_resource::<FogUniforms>()
             .init_resource::<VisionSourceBuffer>()
+            .init_resource::<VisionOccluderBuffer>()
+            .init_resource::<VisionPolygonBuffer>()
             .init_resource::<GpuToCpuActiveCopies>()
+            .init_resource::<ActiveFogReadbacks>()
             .init_resource::<GpuChunkInfoBuffer>()
             .init_resource::<OverlayChunkMappingBuffer>()
             .init_resource::<FogBindGroups>()
@@ -198,10 +208,13 @@ impl Plugin for FogOfWarRenderPlugin {
                 (
                     extract::extract_fog_settings,
                     extract::extract_vision_sources,
+                    extract::extract_vision_occluders,
+                    extract::extract_view_fog_teams,
                     extract::extract_gpu_chunk_data,
                     extract::extract_texture_handles,
                     transfer::check_and_process_mapped_buffers,
                     transfer::check_cpu_to_gpu_request,
+                    transfer::check_fog_readbacks,
                 ),
             )
             .add_systems(
@@ -215,6 +228,12 @@ impl Plugin for FogOfWarRenderPlugin {
                     (
                         transfer::initiate_gpu_to_cpu_copies_and_request_map,
                         transfer::map_buffers,
+                        // Fog-only readbacks for explored-fraction measurements
+                        // 用于已探索比例测量的仅雾效回读
+                        transfer::initiate_fog_readbacks,
+                        // Minimap images from this frame's fog textures
+                        // 根据本帧雾效纹理绘制的小地图图像
+                        minimap::fog_minimap_system,
                     )
                         .after(render_system)
                         .in_set(RenderSystems::Render),
@@ -225,6 +244,11 @@ impl Plugin for FogOfWarRenderPlugin {
                     // For clarity and robustness with async, processing in the next frame's Prepare is safer.
                 ),
             )
+            // Overlay pipeline for each view's target format / 每个视图目标格式的覆盖管线
+            .add_systems(
+                Render,
+                overlay::prepare_fog_overlay_pipelines.in_set(RenderSystems::Prepare),
+            )
             // Prepare systems (Create/Update GPU buffers and bind groups) / 准备系统 (创建/更新 GPU 缓冲区和绑定组)
             .add_systems(
                 Render,
@@ -232,6 +256,8 @@ impl Plugin for FogOfWarRenderPlugin {
                     transfer::check_and_clear_textures_on_reset,
                     prepare::prepare_fog_uniforms,
                     prepare::prepare_vision_source_buffer,
+                    prepare::prepare_vision_occluder_buffer,
+                    prepare::prepare_vision_polygon_buffer,
                     prepare::prepare_gpu_chunk_buffer,
                     prepare::prepare_overlay_chunk_mapping_buffer,
                     prepare::prepare_fog_bind_groups,
@@ -253,6 +279,20 @@ impl Plugin for FogOfWarRenderPlugin {
                     .before(Core2dSystems::PostProcess),
             ),
         );
+        // Same for 3D cameras, which get the fog projected onto the ground plane
+        // 3D 相机同样如此，迷雾被投影到地面平面上
+        render_app.add_systems(
+            Core3d,
+            (
+                fog_compute_system
+                    .after(Core3dSystems::MainPass)
+                    .before(Core3dSystems::PostProcess),
+                fog_overlay_system
+                    .after(Core3dSystems::MainPass)
+                    .after(fog_compute_system)
+                    .before(Core3dSystems::PostProcess),
+            ),
+        );
     }
 
     /// Finalizes render pipeline initialization by creating GPU pipelines and render states.
@@ -265,6 +305,7 @@ impl Plugin for FogOfWarRenderPlugin {
     /// # Pipeline Initialization
     /// - **FogComputePipeline**: Creates compute shader pipeline for fog calculations
     /// - **FogOverlayPipeline**: Creates render pipeline for fog overlay composition
+    /// - **FogMinimapPipeline**: Creates render pipeline for minimap images
     ///
     /// # Why finish() is needed
     /// GPU pipelines require the render device to be fully initialized, which happens
@@ -287,6 +328,8 @@ impl Plugin for FogOfWarRenderPlugin {
 
         render_app
             .init_resource::<compute::FogComputePipeline>()
-            .init_resource::<overlay::FogOverlayPipeline>();
+            .init_resource::<overlay::FogOverlayPipeline>()
+            .init_resource::<SpecializedRenderPipelines<overlay::FogOverlayPipeline>>()
+            .init_resource::<minimap::FogMinimapPipeline>();
     }
 }
@@ -20,6 +20,8 @@
 //! - **Vertex Stage**: Uses Bevy's fullscreen vertex shader for screen-aligned triangle
 //! - **Fragment Stage**: Custom fog overlay shader handles per-pixel fog compositing
 //! - **Render Target**: Draws directly to the main view target
+//! - **Target Formats**: The pipeline is specialized per view target format, so
+//!   cameras rendering to windows, images and texture views are all covered
 //! - **Blend Mode**: Alpha blending for proper fog transparency effects
 //!
 //! # Texture Composition
@@ -33,8 +35,8 @@
 //! ## Coordinate Transformation
 //! Converts screen coordinates to chunk texture coordinates:
 //! ```glsl
-//! // Screen space to world space
-//! world_pos = screen_to_world(screen_coords, view_matrix)
+//! // Screen space to world space: intersect the pixel's view ray with the ground plane
+//! world_pos = ray_plane_hit(screen_to_world(screen_coords, view_matrix), ground_plane)
 //!
 //! // World space to chunk coordinates
 //! chunk_coords = floor(world_pos / chunk_size)
@@ -69,6 +71,9 @@
 //! - **5**: Fog settings uniform (RenderFogMapSettings) - Global fog configuration
 //! - **6**: Chunk mapping buffer (Storage) - Chunk coordinate to texture layer mapping
 //!
+//! Group 1 holds a per-view `ViewFogTeamUniform` selecting which team's fog and
+//! visibility layers the view samples (`layer * team_count + team`).
+//!
 //! ## Texture Format Requirements
 //! - **Visibility/Fog**: R8Unorm format (1 byte per pixel) for memory efficiency
 //! - **Snapshots**: RGBA8 format (4 bytes per pixel) for full color capture
@@ -107,7 +112,8 @@
 
 use super::RenderFogMapSettings;
 use super::extract::{
-    OverlayChunkData, RenderFogTexture, RenderSnapshotTexture, RenderVisibilityTexture,
+    ExtractedViewFogTeam, OverlayChunkData, RenderFogTexture, RenderSnapshotTexture,
+    RenderVisibilityTexture,
 };
 use super::prepare::{FogUniforms, OverlayChunkMappingBuffer};
 use crate::snapshot::SnapshotCamera;
@@ -123,8 +129,9 @@ use bevy_render::{
     render_resource::*,
     renderer::{RenderContext, RenderDevice},
     texture::{FallbackImage, GpuImage},
-    view::{ViewTarget, ViewUniform, ViewUniformOffset, ViewUniforms},
+    view::{ExtractedView, ViewTarget, ViewUniform, ViewUniformOffset, ViewUniforms},
 };
+use bytemuck::{Pod, Zeroable};
 
 /// Path to the WGSL fog overlay shader that implements final fog compositing.
 /// 实现最终雾效合成的WGSL雾效覆盖着色器的路径
@@ -140,6 +147,29 @@ use bevy_render::{
 /// - **Performance Optimization**: Efficient per-pixel operations with GPU optimization
 const SHADER_ASSET_PATH: &str = "shaders/fog_overlay.wgsl";
 
+/// Per-view uniform selecting the `FogTeam` whose layers a view samples.
+/// 每个视图的 uniform，用于选择视图采样的 `FogTeam` 层
+///
+/// ```wgsl
+/// struct ViewFogTeam {
+///     team: u32,
+///     _padding1: u32,
+///     _padding2: u32,
+///     _padding3: u32,
+/// }
+/// @group(1) @binding(0) var<uniform> view_fog: ViewFogTeam;
+/// ```
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable, ShaderType)]
+#[repr(C)]
+pub struct ViewFogTeamUniform {
+    /// Team index, already clamped to `team_count - 1`.
+    /// 队伍索引，已限制在 `team_count - 1` 以内
+    pub team: u32,
+    pub _padding1: u32,
+    pub _padding2: u32,
+    pub _padding3: u32,
+}
+
 /// GPU render pipeline resource for fog overlay shader operations.
 /// 雾效覆盖着色器操作的GPU渲染管线资源
 ///
@@ -163,6 +193,7 @@ const SHADER_ASSET_PATH: &str = "shaders/fog_overlay.wgsl";
 /// @group(0) @binding(4) var snapshot_texture: texture_2d_array<f32>;
 /// @group(0) @binding(5) var<uniform> settings: RenderFogMapSettings;
 /// @group(0) @binding(6) var<storage, read> chunks: array<OverlayChunkData>;
+/// @group(1) @binding(0) var<uniform> view_fog: ViewFogTeam;
 /// ```
 ///
 /// # Sampler Configuration
@@ -192,6 +223,10 @@ pub struct FogOverlayPipeline {
     /// are bound to GPU shader inputs. Used to create bind groups for overlay rendering.
     layout: BindGroupLayoutDescriptor,
 
+    /// Bind group layout for the per-view `ViewFogTeamUniform` (group 1).
+    /// 每个视图 `ViewFogTeamUniform` 的绑定组布局（group 1）
+    view_team_layout: BindGroupLayoutDescriptor,
+
     /// Shared texture sampler for all fog texture array operations.
     /// 用于所有雾效纹理数组操作的共享纹理采样器
     ///
@@ -199,12 +234,50 @@ pub struct FogOverlayPipeline {
     /// texture sampling. Reused across all texture array bindings for efficiency.
     sampler: Sampler,
 
-    /// Cached render pipeline identifier for efficient pipeline retrieval.
-    /// 用于高效管线检索的缓存渲染管线标识符
-    ///
-    /// Used with Bevy's pipeline cache to retrieve the compiled render pipeline.
-    /// The pipeline may not be immediately available if shader compilation is pending.
-    pipeline_id: CachedRenderPipelineId,
+    /// Overlay pipeline descriptor, specialized per view target format.
+    /// 覆盖管线描述符，按视图目标格式特化
+    descriptor: RenderPipelineDescriptor,
+}
+
+/// Specializes the overlay pipeline for the texture format of a view target.
+/// 针对视图目标的纹理格式特化覆盖管线
+impl SpecializedRenderPipeline for FogOverlayPipeline {
+    type Key = TextureFormat;
+
+    fn specialize(&self, format: Self::Key) -> RenderPipelineDescriptor {
+        let mut descriptor = self.descriptor.clone();
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.targets = vec![Some(ColorTargetState {
+                format,                                  // Main texture format of the view / 视图主纹理的格式
+                blend: Some(BlendState::ALPHA_BLENDING), // Standard alpha blending for transparency
+                write_mask: ColorWrites::ALL,            // Write to all RGBA channels
+            })];
+        }
+        descriptor
+    }
+}
+
+/// Overlay pipeline specialized for a view's target format.
+/// 针对视图目标格式特化的覆盖管线
+#[derive(Component)]
+pub struct ViewFogOverlayPipeline(CachedRenderPipelineId);
+
+/// Specializes the overlay pipeline for every view that gets the fog overlay.
+/// 为每个绘制雾效覆盖的视图特化覆盖管线
+pub(super) fn prepare_fog_overlay_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<FogOverlayPipeline>>,
+    overlay_pipeline: Res<FogOverlayPipeline>,
+    views: Query<(Entity, &ExtractedView), Without<SnapshotCamera>>,
+) {
+    for (entity, view) in &views {
+        let pipeline_id =
+            pipelines.specialize(&pipeline_cache, &overlay_pipeline, view.target_format);
+        commands
+            .entity(entity)
+            .insert(ViewFogOverlayPipeline(pipeline_id));
+    }
 }
 
 /// Initializes the fog overlay pipeline from world resources during application startup.
@@ -231,24 +304,24 @@ impl FromWorld for FogOverlayPipeline {
     /// Creates several GPU resources:
     /// - **Bind Group Layout**: Defines shader resource binding schema
     /// - **Texture Sampler**: Linear filtering sampler for texture arrays
-    /// - **Pipeline Descriptor**: Specifies render pipeline configuration
     /// - **Shader Assets**: Loads WGSL overlay shader and fullscreen vertex shader
     ///
     /// # Pipeline Configuration
-    /// Sets up render pipeline with:
+    /// Render pipelines are specialized per view target format (see
+    /// `prepare_fog_overlay_pipelines`) with:
     /// - **Vertex Stage**: Bevy's fullscreen vertex shader for screen-aligned triangle
     /// - **Fragment Stage**: Custom fog overlay shader for per-pixel compositing
     /// - **Blend State**: Alpha blending for proper fog transparency
-    /// - **Color Target**: Standard HDR-capable color format with full write mask
+    /// - **Color Target**: The view's target format with full write mask
     ///
     /// # Return Value
     /// Returns a configured FogOverlayPipeline with:
     /// - **layout**: Bind group layout for resource binding
     /// - **sampler**: Configured texture sampler for filtering
-    /// - **pipeline_id**: Cached pipeline ID for runtime retrieval
+    /// - **descriptor**: Pipeline descriptor specialized per view target format
     ///
     /// # Time Complexity
-    /// O(1) for resource access and pipeline queuing, but shader compilation
+    /// O(1) for resource access, but shader compilation of each specialized pipeline
     /// happens asynchronously and may take additional time.
     ///
     /// # Performance Considerations
@@ -275,6 +348,14 @@ impl FromWorld for FogOverlayPipeline {
             ),
         );
 
+        let view_team_layout = BindGroupLayoutDescriptor::new(
+            "fog_overlay_view_team_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                uniform_buffer::<ViewFogTeamUniform>(false), // 0: Team rendered by this view
+            ),
+        );
+
         // Create linear filtering sampler for smooth fog texture transitions
         // 创建用于平滑雾效纹理过渡的线性过滤采样器
         let sampler = render_device.create_sampler(&SamplerDescriptor {
@@ -296,55 +377,58 @@ impl FromWorld for FogOverlayPipeline {
         // 从 FullscreenShader 资源获取全屏顶点着色器句柄
         let fullscreen_shader = world.resource::<FullscreenShader>().shader().clone();
 
-        // Queue render pipeline for compilation with complete configuration
-        // 排队渲染管线以进行完整配置的编译
-        let pipeline_id =
-            world
-                .resource_mut::<PipelineCache>()
-                .queue_render_pipeline(RenderPipelineDescriptor {
-                    label: Some("fog_overlay_pipeline_init".into()), // Pipeline identifier for debugging
-                    layout: vec![layout.clone()], // Use the bind group layout created above
-                    vertex: VertexState {
-                        shader: fullscreen_shader, // Bevy's built-in fullscreen vertex shader
-                        shader_defs: vec![],       // No shader preprocessor definitions
-                        entry_point: None,         // Use default entry point from shader
-                        buffers: vec![],           // No vertex buffers (fullscreen triangle)
-                    },
-                    fragment: Some(FragmentState {
-                        shader,              // Custom fog overlay fragment shader
-                        shader_defs: vec![], // No shader preprocessor definitions
-                        entry_point: None,   // Use default entry point from shader
-                        targets: vec![Some(ColorTargetState {
-                            format: TextureFormat::Rgba8UnormSrgb, // Standard RGBA format for overlay
-                            blend: Some(BlendState::ALPHA_BLENDING), // Standard alpha blending for transparency
-                            write_mask: ColorWrites::ALL,            // Write to all RGBA channels
-                        })],
-                    }),
-                    primitive: PrimitiveState::default(), // Default primitive settings (triangle list)
-                    depth_stencil: None,                  // No depth testing for fullscreen overlay
-                    multisample: MultisampleState::default(), // Default multisampling settings
-                    immediate_size: 0,                    // No immediate mode data
-                    zero_initialize_workgroup_memory: false,
-                });
+        // Color targets are filled in per view target format by `specialize`
+        // 颜色目标由 `specialize` 按视图目标格式填入
+        let descriptor = RenderPipelineDescriptor {
+            label: Some("fog_overlay_pipeline".into()), // Pipeline identifier for debugging
+            layout: vec![layout.clone(), view_team_layout.clone()], // Shared + per-view layouts
+            vertex: VertexState {
+                shader: fullscreen_shader, // Bevy's built-in fullscreen vertex shader
+                shader_defs: vec![],       // No shader preprocessor definitions
+                entry_point: None,         // Use default entry point from shader
+                buffers: vec![],           // No vertex buffers (fullscreen triangle)
+            },
+            fragment: Some(FragmentState {
+                shader,              // Custom fog overlay fragment shader
+                shader_defs: vec![], // No shader preprocessor definitions
+                entry_point: None,   // Use default entry point from shader
+                targets: vec![],
+            }),
+            primitive: PrimitiveState::default(), // Default primitive settings (triangle list)
+            depth_stencil: None,                  // No depth testing for fullscreen overlay
+            multisample: MultisampleState::default(), // Default multisampling settings
+            immediate_size: 0,                    // No immediate mode data
+            zero_initialize_workgroup_memory: false,
+        };
 
         // Return configured pipeline with all components
         // 返回包含所有组件的配置管线
         FogOverlayPipeline {
-            layout,      // Bind group layout descriptor for resource binding
-            sampler,     // Texture sampler for filtering
-            pipeline_id, // Cached pipeline ID for runtime retrieval
+            layout,           // Bind group layout descriptor for resource binding
+            view_team_layout, // Per-view team bind group layout
+            sampler,          // Texture sampler for filtering
+            descriptor,       // Pipeline descriptor for specialization
         }
     }
 }
 
+/// Views that get the fog overlay, with their specialized pipeline and team.
+/// 绘制雾效覆盖的视图，及其特化管线和队伍
+type FogOverlayViewsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static ViewTarget,
+        &'static ViewUniformOffset,
+        &'static ViewFogOverlayPipeline,
+        Option<&'static ExtractedViewFogTeam>,
+    ),
+    Without<SnapshotCamera>,
+>;
+
 #[derive(SystemParam)]
 pub struct FogOverlaySystemParams<'w, 's> {
-    views: Query<
-        'w,
-        's,
-        (Entity, &'static ViewTarget, &'static ViewUniformOffset),
-        Without<SnapshotCamera>,
-    >,
+    views: FogOverlayViewsQuery<'w, 's>,
     overlay_pipeline: Res<'w, FogOverlayPipeline>,
     pipeline_cache: Res<'w, PipelineCache>,
     fog_uniforms: Res<'w, FogUniforms>,
@@ -369,7 +453,7 @@ pub struct FogOverlaySystemParams<'w, 's> {
 /// 2. **Buffer Validation**: Verify all uniform and storage buffers are prepared
 /// 3. **Texture Access**: Get texture views for fog, visibility, and snapshot arrays
 /// 4. **Bind Group Creation**: Create shared bind group with all resources
-/// 5. **Render Pass**: Execute fullscreen triangle rendering for each view
+/// 5. **Render Pass**: Bind the view's team uniform and draw a fullscreen triangle
 ///
 /// # Performance Characteristics
 /// - **Resolution Dependent**: O(screen_width × screen_height) fragment operations per view
@@ -383,15 +467,6 @@ pub struct FogOverlaySystemParams<'w, 's> {
 /// - **Missing Buffers**: Skips rendering when GPU buffers not ready
 /// - **Missing Textures**: Uses fallback textures for graceful degradation
 pub fn fog_overlay_system(mut render_context: RenderContext, params: FogOverlaySystemParams) {
-    // Retrieve compiled render pipeline from cache
-    let Some(pipeline) = params
-        .pipeline_cache
-        .get_render_pipeline(params.overlay_pipeline.pipeline_id)
-    else {
-        // Pipeline not compiled yet, skip this frame gracefully
-        return;
-    };
-
     // Validate that all required GPU buffers are prepared and ready
     let (Some(uniform_buf), Some(mapping_buf), Some(view_uniform_binding)) = (
         params.fog_uniforms.buffer.as_ref(),
@@ -441,8 +516,39 @@ pub fn fog_overlay_system(mut render_context: RenderContext, params: FogOverlayS
         )),
     );
 
+    let view_team_layout = params
+        .pipeline_cache
+        .get_bind_group_layout(&params.overlay_pipeline.view_team_layout);
+
     // Iterate over all non-snapshot views and render fog overlay
-    for (_view_entity, view_target, view_uniform_offset) in &params.views {
+    for (view_target, view_uniform_offset, view_pipeline, view_team) in &params.views {
+        // Retrieve the pipeline specialized for the view's target format
+        // 获取针对视图目标格式特化的管线
+        let Some(pipeline) = params.pipeline_cache.get_render_pipeline(view_pipeline.0) else {
+            // Pipeline not compiled yet, skip this view gracefully
+            continue;
+        };
+
+        // Select which team's fog layers this view samples
+        // 选择此视图采样的队伍雾效层
+        let view_team_uniform = ViewFogTeamUniform {
+            team: view_team.map_or(0, |team| team.0),
+            ..Default::default()
+        };
+        let view_team_buffer =
+            render_context
+                .render_device()
+                .create_buffer_with_data(&BufferInitDescriptor {
+                    label: Some("fog_overlay_view_team_buffer"),
+                    contents: bytemuck::bytes_of(&view_team_uniform),
+                    usage: BufferUsages::UNIFORM,
+                });
+        let view_team_bind_group = render_context.render_device().create_bind_group(
+            "fog_overlay_view_team_bind_group",
+            &view_team_layout,
+            &BindGroupEntries::single(view_team_buffer.as_entire_binding()),
+        );
+
         let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
             label: Some("fog_overlay_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
@@ -462,6 +568,7 @@ pub fn fog_overlay_system(mut render_context: RenderContext, params: FogOverlayS
 
         render_pass.set_render_pipeline(pipeline);
         render_pass.set_bind_group(0, &bind_group, &[view_uniform_offset.offset]);
+        render_pass.set_bind_group(1, &view_team_bind_group, &[]);
         render_pass.draw(0..3, 0..1);
     }
 }
@@ -80,8 +80,8 @@
 //! - **Resource Recovery**: System handles temporary resource unavailability
 
 use super::extract::{
-    ExtractedGpuChunkData, ExtractedVisionSources, RenderFogMapSettings, RenderFogTexture,
-    RenderVisibilityTexture,
+    ExtractedGpuChunkData, ExtractedVisionOccluders, ExtractedVisionSources, RenderFogMapSettings,
+    RenderFogTexture, RenderVisibilityTexture, RenderVisionBlockerTexture,
 };
 use crate::render::compute::FogComputePipeline;
 use bevy_ecs::prelude::*;
@@ -104,7 +104,7 @@ use bevy_render::texture::{FallbackImage, GpuImage};
 ///
 /// # Buffer Characteristics
 /// - **Type**: Uniform buffer for fast, cached GPU access
-/// - **Size**: Fixed size structure (80 bytes for RenderFogMapSettings)
+/// - **Size**: Fixed size structure (96 bytes for RenderFogMapSettings)
 /// - **Usage**: Read-only access from compute and overlay shaders
 /// - **Update Frequency**: Recreated each frame if settings change
 ///
@@ -116,7 +116,7 @@ use bevy_render::texture::{FallbackImage, GpuImage};
 ///
 /// # Performance Characteristics
 /// - **GPU Cache**: Uniform buffers are cached by GPU for fast access
-/// - **Memory**: Small size (80 bytes) has minimal memory impact
+/// - **Memory**: Small size (96 bytes) has minimal memory impact
 /// - **Bandwidth**: Efficient for frequently accessed global configuration
 /// - **Update Cost**: Minimal, only recreated when settings change
 #[derive(Resource, Default)]
@@ -151,7 +151,7 @@ pub struct FogUniforms {
 /// # Performance Considerations
 /// - **Dynamic Sizing**: Buffer size adapts to vision source count
 /// - **GPU Access**: Efficient sequential access pattern in compute shaders
-/// - **Memory**: 48 bytes per vision source (aligned for GPU)
+/// - **Memory**: 112 bytes per vision source (aligned for GPU)
 /// - **Bandwidth**: Limited by number of active vision sources
 #[derive(Resource, Default)]
 pub struct VisionSourceBuffer {
@@ -170,6 +170,42 @@ pub struct VisionSourceBuffer {
     pub capacity: usize,
 }
 
+/// GPU storage buffer resource containing occluder segments for line-of-sight tests.
+/// 包含用于视线测试的遮挡线段的GPU存储缓冲区资源
+///
+/// # Buffer Characteristics
+/// - **Type**: Storage buffer of `OccluderSegmentData` (24 bytes per segment)
+/// - **Usage**: Read-only access from compute shaders
+/// - **Update Frequency**: Recreated each frame to follow moving occluders
+#[derive(Resource, Default)]
+pub struct VisionOccluderBuffer {
+    /// Optional GPU storage buffer containing occluder segment data.
+    /// 包含遮挡线段数据的可选GPU存储缓冲区
+    pub buffer: Option<Buffer>,
+
+    /// Number of segments stored in the buffer.
+    /// 存储在缓冲区中的线段数量
+    pub capacity: usize,
+}
+
+/// GPU storage buffer resource containing world-space vertices of polygon vision sources.
+/// 包含多边形视野源世界空间顶点的GPU存储缓冲区资源
+///
+/// # Buffer Characteristics
+/// - **Type**: Storage buffer of `Vec2` (8 bytes per vertex)
+/// - **Usage**: Read-only access from compute shaders
+/// - **Update Frequency**: Recreated each frame together with the vision source buffer
+#[derive(Resource, Default)]
+pub struct VisionPolygonBuffer {
+    /// Optional GPU storage buffer containing polygon vertices.
+    /// 包含多边形顶点的可选GPU存储缓冲区
+    pub buffer: Option<Buffer>,
+
+    /// Number of vertices stored in the buffer.
+    /// 存储在缓冲区中的顶点数量
+    pub capacity: usize,
+}
+
 /// GPU storage buffer resource containing chunk computation data for compute shader processing.
 /// 包含区块计算数据供计算着色器处理的GPU存储缓冲区资源
 ///
@@ -316,7 +352,7 @@ pub struct FogBindGroups {
 ///
 /// # GPU Buffer Properties
 /// - **Type**: Uniform buffer for fast, cached access
-/// - **Size**: 80 bytes (size of RenderFogMapSettings structure)
+/// - **Size**: 96 bytes (size of RenderFogMapSettings structure)
 /// - **Usage**: UNIFORM (shader binding) + COPY_DST (CPU updates)
 /// - **Access**: Read-only from both compute and overlay shaders
 ///
@@ -380,7 +416,7 @@ pub fn prepare_fog_uniforms(
 ///
 /// # GPU Buffer Properties
 /// - **Type**: Storage buffer for large array data
-/// - **Size**: Variable (48 bytes × number of vision sources)
+/// - **Size**: Variable (112 bytes × number of vision sources)
 /// - **Usage**: STORAGE (shader array access) + COPY_DST (CPU updates)
 /// - **Access**: Read-only from compute shaders with indexed access
 ///
@@ -389,10 +425,10 @@ pub fn prepare_fog_uniforms(
 /// - **Position**: World coordinates of vision source
 /// - **Properties**: Radius, shape type, direction, intensity
 /// - **Optimization**: Precomputed trigonometric values for GPU efficiency
-/// - **Alignment**: GPU-compatible memory layout (48 bytes per source)
+/// - **Alignment**: GPU-compatible memory layout (112 bytes per source)
 ///
 /// # Performance Characteristics
-/// - **Memory**: 48 bytes per active vision source
+/// - **Memory**: 112 bytes per active vision source
 /// - **Upload**: Direct memory copy without staging buffers
 /// - **GPU Access**: Efficient indexed array access in compute shaders
 /// - **Bandwidth**: Scales linearly with number of active vision sources
@@ -427,6 +463,56 @@ pub fn prepare_vision_source_buffer(
     buffer_res.buffer = Some(buffer);
 }
 
+/// Prepares GPU storage buffer containing occluder segments for the compute shader.
+/// 为计算着色器准备包含遮挡线段的GPU存储缓冲区
+///
+/// The buffer is bound to compute shaders at binding 5:
+/// ```wgsl
+/// @group(0) @binding(5) var<storage, read> occluder_segments: array<OccluderSegmentData>;
+/// ```
+///
+/// # Time Complexity: O(n) where n = number of occluder segments
+pub fn prepare_vision_occluder_buffer(
+    extracted_occluders: Res<ExtractedVisionOccluders>,
+    mut buffer_res: ResMut<VisionOccluderBuffer>,
+    render_device: Res<RenderDevice>,
+) {
+    buffer_res.capacity = extracted_occluders.segments.len();
+
+    let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("vision_occluder_storage_buffer"), // Debug label for GPU debugging
+        contents: bytemuck::cast_slice(&extracted_occluders.segments), // Array of OccluderSegmentData
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST, // Storage array + CPU updates
+    });
+
+    buffer_res.buffer = Some(buffer);
+}
+
+/// Prepares GPU storage buffer containing polygon vision vertices for the compute shader.
+/// 为计算着色器准备包含多边形视野顶点的GPU存储缓冲区
+///
+/// The buffer is bound to compute shaders at binding 7:
+/// ```wgsl
+/// @group(0) @binding(7) var<storage, read> polygon_vertices: array<vec2<f32>>;
+/// ```
+///
+/// # Time Complexity: O(n) where n = number of polygon vertices
+pub fn prepare_vision_polygon_buffer(
+    extracted_sources: Res<ExtractedVisionSources>,
+    mut buffer_res: ResMut<VisionPolygonBuffer>,
+    render_device: Res<RenderDevice>,
+) {
+    buffer_res.capacity = extracted_sources.polygon_vertices.len();
+
+    let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("vision_polygon_storage_buffer"), // Debug label for GPU debugging
+        contents: bytemuck::cast_slice(&extracted_sources.polygon_vertices), // Array of world-space Vec2
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST, // Storage array + CPU updates
+    });
+
+    buffer_res.buffer = Some(buffer);
+}
+
 /// Prepares GPU storage buffer containing chunk computation data for compute shader processing.
 /// 为计算着色器处理准备包含区块计算数据的GPU存储缓冲区
 ///
@@ -608,13 +694,16 @@ pub fn prepare_overlay_chunk_mapping_buffer(
 /// 4. **Resource Storage**: Store completed bind groups for shader usage
 ///
 /// # Compute Bind Group Resources
-/// The compute bind group contains 5 resources bound sequentially:
+/// The compute bind group contains 8 resources bound sequentially:
 /// ```wgsl
 /// @group(0) @binding(0) var fog_texture: texture_storage_2d_array<r8unorm, write>;
 /// @group(0) @binding(1) var visibility_texture: texture_storage_2d_array<r8unorm, read_write>;
 /// @group(0) @binding(2) var<storage, read> vision_sources: array<VisionSourceData>;
 /// @group(0) @binding(3) var<storage, read> chunks: array<ChunkComputeData>;
 /// @group(0) @binding(4) var<uniform> settings: RenderFogMapSettings;
+/// @group(0) @binding(5) var<storage, read> occluder_segments: array<OccluderSegmentData>;
+/// @group(0) @binding(6) var vision_blocker_texture: texture_storage_2d_array<r8unorm, read>;
+/// @group(0) @binding(7) var<storage, read> polygon_vertices: array<vec2<f32>>;
 /// ```
 ///
 /// # Fallback Texture Strategy
@@ -673,8 +762,11 @@ pub fn prepare_fog_bind_groups(
     fog_uniforms: Res<FogUniforms>,
     vision_source_buffer: Res<VisionSourceBuffer>,
     gpu_chunk_buffer: Res<GpuChunkInfoBuffer>,
+    occluder_buffer: Res<VisionOccluderBuffer>,
+    polygon_buffer: Res<VisionPolygonBuffer>,
     fog_texture: Res<RenderFogTexture>,
     visibility_texture: Res<RenderVisibilityTexture>,
+    blocker_texture: Res<RenderVisionBlockerTexture>,
     images: Res<RenderAssets<GpuImage>>,
     fallback_image: Res<FallbackImage>, // For default textures / 用于默认纹理
     fog_compute_pipeline: Res<FogComputePipeline>, // For view uniform binding / 用于视图统一绑定
@@ -692,15 +784,28 @@ pub fn prepare_fog_bind_groups(
         .map(|img| &img.texture_view)
         .unwrap_or(&fallback_image.d1.texture_view); // Use fallback if not available
 
+    let blocker_texture_view = images
+        .get(&blocker_texture.0) // Try to get real vision blocker texture array
+        .map(|img| &img.texture_view)
+        .unwrap_or(&fallback_image.d1.texture_view); // Use fallback if not available
+
     // --- Compute Bind Group Creation ---
     // --- 计算绑定组创建 ---
 
     // Validate all required GPU buffers are prepared before bind group creation
     // 在绑定组创建之前验证所有必需的GPU缓冲区都已准备就绪
-    if let (Some(uniform_buf), Some(source_buf), Some(chunk_buf)) = (
+    if let (
+        Some(uniform_buf),
+        Some(source_buf),
+        Some(chunk_buf),
+        Some(occluder_buf),
+        Some(polygon_buf),
+    ) = (
         fog_uniforms.buffer.as_ref(),         // Fog settings uniform buffer
         vision_source_buffer.buffer.as_ref(), // Vision source storage buffer
         gpu_chunk_buffer.buffer.as_ref(),     // Chunk computation storage buffer
+        occluder_buffer.buffer.as_ref(),      // Occluder segment storage buffer
+        polygon_buffer.buffer.as_ref(),       // Polygon vertex storage buffer
     ) {
         let compute_layout =
             pipeline_cache.get_bind_group_layout(&fog_compute_pipeline.compute_layout);
@@ -711,11 +816,14 @@ pub fn prepare_fog_bind_groups(
             "fog_compute_bind_group", // Debug label for GPU debugging
             &compute_layout,          // Use the resolved BindGroupLayout
             &BindGroupEntries::sequential((
-                fog_texture_view,                // 0: Fog texture array (write access)
-                visibility_texture_view,         // 1: Visibility texture array (read/write)
-                source_buf.as_entire_binding(),  // 2: Vision source storage buffer
-                chunk_buf.as_entire_binding(),   // 3: Chunk computation storage buffer
-                uniform_buf.as_entire_binding(), // 4: Fog settings uniform buffer
+                fog_texture_view,                 // 0: Fog texture array (write access)
+                visibility_texture_view,          // 1: Visibility texture array (read/write)
+                source_buf.as_entire_binding(),   // 2: Vision source storage buffer
+                chunk_buf.as_entire_binding(),    // 3: Chunk computation storage buffer
+                uniform_buf.as_entire_binding(),  // 4: Fog settings uniform buffer
+                occluder_buf.as_entire_binding(), // 5: Occluder segment storage buffer
+                blocker_texture_view,             // 6: Vision blocker texture array (read)
+                polygon_buf.as_entire_binding(),  // 7: Polygon vertex storage buffer
             )),
         );
 
@@ -70,6 +70,7 @@ use crate::prelude::*;
 use crate::render::RenderFogMapSettings;
 use crate::render::extract::{RenderFogTexture, RenderSnapshotTexture, RenderVisibilityTexture};
 use crate::settings::MAX_LAYERS;
+use crate::stats::{FogLayerReadback, FogReadbackRequests};
 use async_channel::{Receiver, Sender};
 use bevy_image::TextureFormatPixelInfo;
 use bevy_math::IVec2;
@@ -150,6 +151,7 @@ use bevy_render::texture::GpuImage;
 /// - **Resolution Match**: Image dimensions must match chunk texture resolution
 ///
 /// # Time Complexity: O(n) where n = number of pending upload requests
+#[allow(clippy::too_many_arguments)]
 pub fn process_cpu_to_gpu_copies(
     render_queue: Res<RenderQueue>,
     cpu_upload_requests: Res<CpuToGpuCopyRequests>,
@@ -158,6 +160,7 @@ pub fn process_cpu_to_gpu_copies(
     gpu_images: Res<RenderAssets<GpuImage>>,
     mut cpu_to_gpu_requests: ResMut<CpuToGpuRequests>,
     render_device: Res<RenderDevice>,
+    render_fog_settings: Res<RenderFogMapSettings>,
 ) {
     // Early return if no upload requests are pending
     // 如果没有待处理的上传请求，则提前返回
@@ -198,14 +201,17 @@ pub fn process_cpu_to_gpu_copies(
                     origin: Origin3d {
                         x: 0, // Copy to origin of layer
                         y: 0,
-                        z: request.fog_layer_index, // Target array layer
+                        z: request.fog_layer_index * render_fog_settings.team_count, // First team layer of the slot
                     },
                     aspect: TextureAspect::All, // Copy all texture aspects
                 },
                 Extent3d {
                     width: upload_fog_image.texture_descriptor.size.width, // Match source image dimensions
                     height: upload_fog_image.texture_descriptor.size.height,
-                    depth_or_array_layers: 1, // Copy single layer
+                    depth_or_array_layers: upload_fog_image
+                        .texture_descriptor
+                        .size
+                        .depth_or_array_layers, // One layer per team
                 },
             );
         }
@@ -328,8 +334,11 @@ pub fn initiate_gpu_to_cpu_copies_and_request_map(
         let bytes_per_row_fog = (texture_width as u64)
             .checked_mul(fog_format_size as u64)
             .expect("Fog bytes per row calculation would overflow");
+        // Fog data covers every team layer of the slot / 雾效数据覆盖该槽位的所有队伍层
+        let team_count = render_fog_settings.team_count;
         let fog_buffer_size = bytes_per_row_fog
             .checked_mul(texture_height as u64)
+            .and_then(|v| v.checked_mul(team_count as u64))
             .expect("Fog buffer size calculation would overflow");
 
         let snapshot_format_size = snapshot_format.pixel_size().unwrap_or(0) as u32;
@@ -391,7 +400,7 @@ pub fn initiate_gpu_to_cpu_copies_and_request_map(
                 origin: Origin3d {
                     x: 0,
                     y: 0,
-                    z: request.fog_layer_index,
+                    z: request.fog_layer_index * team_count,
                 },
                 aspect: TextureAspect::All,
             },
@@ -409,7 +418,7 @@ pub fn initiate_gpu_to_cpu_copies_and_request_map(
             Extent3d {
                 width: texture_width,
                 height: texture_height,
-                depth_or_array_layers: 1, // Copying a single layer
+                depth_or_array_layers: team_count, // One layer per team
             },
         );
 
@@ -730,6 +739,164 @@ pub(crate) fn check_cpu_to_gpu_request(
     }
 }
 
+/// Fog layer readbacks in flight for explored-fraction measurements.
+/// 用于已探索比例测量的进行中的雾效层回读
+///
+/// Unlike [`GpuToCpuActiveCopies`], these readbacks leave the chunk on the GPU and
+/// only copy its fog layers, not its snapshot.
+#[derive(Resource, Default)]
+pub(crate) struct ActiveFogReadbacks {
+    /// Receiver for each chunk's fog data, empty if mapping failed.
+    readbacks: HashMap<IVec2, Receiver<Vec<u8>>>,
+}
+
+/// Copies the fog layers requested through `FogReadbackRequests` to staging buffers
+/// and maps them for reading.
+/// 将 `FogReadbackRequests` 请求的雾效层复制到暂存缓冲区并映射以供读取
+///
+/// # Time Complexity: O(n) where n = number of new readback requests
+pub(crate) fn initiate_fog_readbacks(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut readback_requests: ResMut<FogReadbackRequests>,
+    fog_texture_array_handle: Res<RenderFogTexture>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_fog_settings: Res<RenderFogMapSettings>,
+    mut active_readbacks: ResMut<ActiveFogReadbacks>,
+) {
+    if readback_requests.requests.is_empty() {
+        return;
+    }
+    let Some(fog_gpu_image) = gpu_images.get(&fog_texture_array_handle.0) else {
+        return;
+    };
+
+    let texture_width = render_fog_settings.texture_resolution_per_chunk.x;
+    let texture_height = render_fog_settings.texture_resolution_per_chunk.y;
+    let team_count = render_fog_settings.team_count;
+    let fog_format_size = fog_gpu_image
+        .texture_descriptor
+        .format
+        .pixel_size()
+        .unwrap_or(0) as u64;
+    if fog_format_size == 0 {
+        error!("Fog format has no pixel size, cannot read fog layers back");
+        readback_requests.requests.clear();
+        return;
+    }
+    let bytes_per_row = (texture_width as u64)
+        .checked_mul(fog_format_size)
+        .expect("Fog bytes per row calculation would overflow");
+    let buffer_size = bytes_per_row
+        .checked_mul(texture_height as u64)
+        .and_then(|v| v.checked_mul(team_count as u64))
+        .expect("Fog buffer size calculation would overflow");
+
+    let mut command_encoder =
+        render_device.create_command_encoder(&CommandEncoderDescriptor::default());
+    let mut staging_buffers = Vec::new();
+    for request in readback_requests.requests.drain(..) {
+        if active_readbacks.readbacks.contains_key(&request.coords)
+            || staging_buffers
+                .iter()
+                .any(|(coords, _)| *coords == request.coords)
+        {
+            continue;
+        }
+
+        let staging_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some(&format!("fog_readback_buffer_{:?}", request.coords)),
+            size: buffer_size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        command_encoder.copy_texture_to_buffer(
+            TexelCopyTextureInfo {
+                texture: &fog_gpu_image.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: request.fog_layer_index * team_count,
+                },
+                aspect: TextureAspect::All,
+            },
+            TexelCopyBufferInfo {
+                buffer: &staging_buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(
+                        u32::try_from(bytes_per_row).expect("Fog bytes per row too large for u32"),
+                    ),
+                    rows_per_image: Some(texture_height),
+                },
+            },
+            Extent3d {
+                width: texture_width,
+                height: texture_height,
+                depth_or_array_layers: team_count, // One layer per team
+            },
+        );
+        staging_buffers.push((request.coords, staging_buffer));
+    }
+    if staging_buffers.is_empty() {
+        return;
+    }
+    render_queue.submit(std::iter::once(command_encoder.finish()));
+
+    for (coords, staging_buffer) in staging_buffers {
+        let (tx, rx) = async_channel::bounded(1);
+        let mapped_buffer = staging_buffer.clone();
+        staging_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |res| {
+                let result = match res {
+                    Ok(()) => {
+                        let data = mapped_buffer.slice(..).get_mapped_range();
+                        let result = Vec::from(&*data);
+                        drop(data);
+                        mapped_buffer.unmap();
+                        result
+                    }
+                    Err(e) => {
+                        warn!("Failed to map fog readback buffer: {}", e);
+                        Vec::new()
+                    }
+                };
+                if let Err(e) = tx.try_send(result) {
+                    warn!("Failed to send readback result: {}", e);
+                }
+            });
+        active_readbacks.readbacks.insert(coords, rx);
+    }
+}
+
+/// Forwards completed fog layer readbacks to the main world as `FogLayerReadback` messages.
+/// 将完成的雾效层回读以 `FogLayerReadback` 消息转发到主世界
+///
+/// # Time Complexity: O(n) where n = number of readbacks in flight
+pub(crate) fn check_fog_readbacks(
+    mut main_world: ResMut<MainWorld>,
+    mut active_readbacks: ResMut<ActiveFogReadbacks>,
+) {
+    active_readbacks.readbacks.retain(|coords, rx| {
+        let Ok(fog_data) = rx.try_recv() else {
+            return true;
+        };
+        if !fog_data.is_empty() {
+            if let Some(mut msgs) =
+                main_world.get_resource_mut::<bevy_ecs::message::Messages<FogLayerReadback>>()
+            {
+                msgs.write(FogLayerReadback {
+                    coords: *coords,
+                    fog_data,
+                });
+            }
+        }
+        false
+    });
+}
+
 /// 检查并清空纹理（在渲染世界中重置时）
 /// Check and clear textures (when resetting in render world)
 /// Performs complete fog system reset by clearing all GPU texture arrays to default state.
@@ -791,7 +958,10 @@ pub fn check_and_clear_textures_on_reset(
 
     let texture_width = render_settings.texture_resolution_per_chunk.x;
     let texture_height = render_settings.texture_resolution_per_chunk.y;
-    let num_layers = MAX_LAYERS;
+    // Fog and visibility hold one layer per team per slot; snapshots are shared
+    // 雾效和可见性纹理每个槽位每个队伍一层；快照为共享
+    let fog_layers = MAX_LAYERS * render_settings.team_count;
+    let snapshot_layers = MAX_LAYERS;
 
     // Get GPU images with error handling
     let Some(fog_gpu_image) = gpu_images.get(&fog_texture.0) else {
@@ -883,7 +1053,7 @@ pub fn check_and_clear_textures_on_reset(
 
     // Clear fog texture (set to 0 = unexplored) - reuse fog_buffer for all layers
     // 清除雾效纹理（设置为0=未探索）- 对所有层重用fog_buffer
-    for layer in 0..num_layers {
+    for layer in 0..fog_layers {
         command_encoder.copy_buffer_to_texture(
             TexelCopyBufferInfo {
                 buffer: &fog_buffer,
@@ -913,7 +1083,7 @@ pub fn check_and_clear_textures_on_reset(
 
     // Clear visibility texture (set to 0 = not visible) - reuse vis_buffer for all layers
     // 清除可见性纹理（设置为0=不可见）- 对所有层重用vis_buffer
-    for layer in 0..num_layers {
+    for layer in 0..fog_layers {
         command_encoder.copy_buffer_to_texture(
             TexelCopyBufferInfo {
                 buffer: &vis_buffer,
@@ -943,7 +1113,7 @@ pub fn check_and_clear_textures_on_reset(
 
     // Clear snapshot texture (set to 0) - reuse snap_buffer for all layers
     // 清除快照纹理（设置为0）- 对所有层重用snap_buffer
-    for layer in 0..num_layers {
+    for layer in 0..snapshot_layers {
         command_encoder.copy_buffer_to_texture(
             TexelCopyBufferInfo {
                 buffer: &snap_buffer,
@@ -0,0 +1,276 @@
+//! Timed reveal areas for scans, flares and pings.
+//! 用于扫描、照明弹和信号标记的定时揭示区域
+//!
+//! Writing a [`RevealArea`] message clears fog around a point for a while without
+//! spawning a vision entity. The plugin keeps every live reveal in [`ActiveReveals`],
+//! ages it each frame and drops it once its duration is over. Live reveals are fed
+//! into the chunk visibility pass, [`FogQuery`] and the compute shader's vision
+//! source buffer, exactly like a `VisionSource` whose intensity follows the fade.
+
+use crate::prelude::*;
+use bevy_math::Vec2;
+use bevy_time::Time;
+
+/// Message that reveals an area for a limited time.
+/// 在限定时间内揭示一块区域的消息
+///
+/// `source` supplies shape, range, direction, transition ratio and falloff; its
+/// `enabled`, `reveals` and `explores` flags and `eye_height` are honoured too.
+/// `duration` is the whole lifetime in seconds and includes the fades.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn scan(mut reveals: MessageWriter<RevealArea>) {
+///     // Show a 300-unit circle for 5 seconds, fading out over the last second
+///     reveals.write(
+///         RevealArea::new(Vec2::new(400.0, 0.0), VisionSource::circle(300.0), 5.0)
+///             .with_fade(0.25, 1.0),
+///     );
+///     // A ping that shows the spot live but leaves it unexplored afterwards
+///     reveals.write(RevealArea::circle(Vec2::ZERO, 80.0, 2.0).with_explores(false));
+/// }
+/// ```
+#[derive(Message, Debug, Clone)]
+pub struct RevealArea {
+    /// Position of the area's center in fog coordinates, see `FogMapSettings::world_to_fog`.
+    /// 区域中心的雾效坐标
+    pub position: Vec2,
+
+    /// Vision shape and parameters of the area.
+    /// 区域的视野形状与参数
+    pub source: VisionSource,
+
+    /// Team the area is revealed for (default: team 0). Allies sharing vision see it too.
+    /// 揭示区域所属的队伍（默认队伍 0），共享视野的盟友同样可见
+    pub team: FogTeam,
+
+    /// Total lifetime in seconds, fades included.
+    /// 总持续时间（秒），包含淡入淡出
+    pub duration: f32,
+
+    /// Seconds to fade in from nothing to full intensity.
+    /// 从无到完全强度的淡入时间（秒）
+    pub fade_in: f32,
+
+    /// Seconds to fade out at the end of the lifetime.
+    /// 生命周期结束前的淡出时间（秒）
+    pub fade_out: f32,
+
+    /// [`Detection`] level of the area, so scans can expose [`Stealth`] entities (default: 0).
+    /// 区域的侦测等级，使扫描可以暴露隐身实体（默认 0）
+    pub detection: u8,
+}
+
+impl RevealArea {
+    /// Creates a reveal of `source` at `position` lasting `duration` seconds.
+    /// 创建在 `position` 处持续 `duration` 秒的揭示区域
+    pub fn new(position: Vec2, source: VisionSource, duration: f32) -> Self {
+        Self {
+            position,
+            source,
+            team: FogTeam::default(),
+            duration,
+            fade_in: 0.0,
+            fade_out: 0.0,
+            detection: 0,
+        }
+    }
+
+    /// Creates a circular reveal of `radius` at `position` lasting `duration` seconds.
+    /// 创建在 `position` 处半径为 `radius`、持续 `duration` 秒的圆形揭示区域
+    pub fn circle(position: Vec2, radius: f32, duration: f32) -> Self {
+        Self::new(position, VisionSource::circle(radius), duration)
+    }
+
+    /// Sets the team the area is revealed for.
+    pub fn with_team(mut self, team: FogTeam) -> Self {
+        self.team = team;
+        self
+    }
+
+    /// Sets the fade-in and fade-out times in seconds.
+    pub fn with_fade(mut self, fade_in: f32, fade_out: f32) -> Self {
+        self.fade_in = fade_in;
+        self.fade_out = fade_out;
+        self
+    }
+
+    /// Sets the detection level the area applies to stealthed entities.
+    pub fn with_detection(mut self, level: u8) -> Self {
+        self.detection = level;
+        self
+    }
+
+    /// Sets whether the area marks what it reveals as explored (`source.explores`).
+    pub fn with_explores(mut self, explores: bool) -> Self {
+        self.source.explores = explores;
+        self
+    }
+
+    /// Fade factor in `0.0..=1.0` after `elapsed` seconds.
+    /// 经过 `elapsed` 秒后的淡入淡出系数
+    pub fn strength(&self, elapsed: f32) -> f32 {
+        let remaining = self.duration - elapsed;
+        if elapsed < 0.0 || remaining <= 0.0 {
+            return 0.0;
+        }
+        let fade_in = if self.fade_in > 0.0 {
+            elapsed / self.fade_in
+        } else {
+            1.0
+        };
+        let fade_out = if self.fade_out > 0.0 {
+            remaining / self.fade_out
+        } else {
+            1.0
+        };
+        fade_in.min(fade_out).clamp(0.0, 1.0)
+    }
+}
+
+/// A reveal area that is still running.
+/// 仍在生效的揭示区域
+#[derive(Debug, Clone)]
+pub struct ActiveReveal {
+    area: RevealArea,
+    elapsed: f32,
+    /// `area.source` with its intensity scaled by the current fade.
+    source: VisionSource,
+}
+
+impl ActiveReveal {
+    fn new(area: RevealArea) -> Self {
+        let source = area.source.clone();
+        let mut reveal = Self {
+            area,
+            elapsed: 0.0,
+            source,
+        };
+        reveal.refresh();
+        reveal
+    }
+
+    fn refresh(&mut self) {
+        self.source.intensity = self.area.source.intensity * self.area.strength(self.elapsed);
+    }
+
+    /// The message this reveal was started from.
+    pub fn area(&self) -> &RevealArea {
+        &self.area
+    }
+
+    /// Seconds since the reveal started.
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Seconds until the reveal ends.
+    pub fn remaining(&self) -> f32 {
+        (self.area.duration - self.elapsed).max(0.0)
+    }
+
+    /// Current fade factor in `0.0..=1.0`.
+    pub fn strength(&self) -> f32 {
+        self.area.strength(self.elapsed)
+    }
+
+    /// Vision source as fed to the shader, with the fade applied to its intensity.
+    pub(crate) fn source(&self) -> &VisionSource {
+        &self.source
+    }
+
+    /// Returns true if the reveal currently clears fog.
+    pub(crate) fn is_active(&self) -> bool {
+        self.source.enabled && self.source.intensity > 0.001
+    }
+}
+
+/// Reveal areas started by [`RevealArea`] messages that have not expired yet.
+/// 由 [`RevealArea`] 消息启动且尚未过期的揭示区域
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ActiveReveals {
+    reveals: Vec<ActiveReveal>,
+}
+
+impl ActiveReveals {
+    /// Iterates over the running reveals, oldest first.
+    /// 按启动顺序遍历正在生效的揭示区域
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveReveal> {
+        self.reveals.iter()
+    }
+
+    /// Number of running reveals.
+    pub fn len(&self) -> usize {
+        self.reveals.len()
+    }
+
+    /// Returns true if no reveal is running.
+    pub fn is_empty(&self) -> bool {
+        self.reveals.is_empty()
+    }
+
+    /// Ends every running reveal immediately.
+    /// 立即结束所有正在生效的揭示区域
+    pub fn clear(&mut self) {
+        self.reveals.clear();
+    }
+
+    /// Reveals that currently clear fog for a team the settings know about.
+    pub(crate) fn active<'a>(
+        &'a self,
+        settings: &'a FogMapSettings,
+    ) -> impl Iterator<Item = &'a ActiveReveal> {
+        self.reveals
+            .iter()
+            .filter(|reveal| reveal.is_active() && settings.contains_team(reveal.area.team))
+    }
+
+    /// Ages running reveals by `delta` seconds and drops the expired ones.
+    fn tick(&mut self, delta: f32) {
+        self.reveals.retain_mut(|reveal| {
+            reveal.elapsed += delta;
+            reveal.refresh();
+            reveal.elapsed < reveal.area.duration
+        });
+    }
+}
+
+/// Ages running reveals and starts the ones requested this frame.
+/// 推进正在生效的揭示区域并启动本帧请求的新区域
+pub(crate) fn update_reveal_areas(
+    time: Res<Time>,
+    mut messages: MessageReader<RevealArea>,
+    mut reveals: ResMut<ActiveReveals>,
+) {
+    reveals.tick(time.delta_secs());
+    for area in messages.read() {
+        if area.duration > 0.0 {
+            reveals.reveals.push(ActiveReveal::new(area.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reveal_fades_and_expires() {
+        let area = RevealArea::circle(Vec2::ZERO, 50.0, 4.0).with_fade(1.0, 2.0);
+        assert_eq!(area.strength(0.0), 0.0);
+        assert!((area.strength(0.5) - 0.5).abs() < 1e-5);
+        assert_eq!(area.strength(1.5), 1.0);
+        assert!((area.strength(3.0) - 0.5).abs() < 1e-5);
+        assert_eq!(area.strength(4.0), 0.0);
+
+        let mut reveals = ActiveReveals::default();
+        reveals.reveals.push(ActiveReveal::new(area));
+        reveals.tick(1.5);
+        assert_eq!(reveals.len(), 1);
+        assert!(reveals.iter().all(ActiveReveal::is_active));
+        reveals.tick(2.5);
+        assert!(reveals.is_empty());
+    }
+}
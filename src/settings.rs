@@ -1,13 +1,62 @@
+use crate::components::FogTeam;
 use bevy_color::Color;
 use bevy_color::palettes::basic;
 use bevy_ecs::prelude::Resource;
-use bevy_math::{IVec2, UVec2, Vec2};
+use bevy_math::{Affine2, IVec2, Rect, UVec2, Vec2, Vec3};
 use bevy_render::render_resource::TextureFormat;
 
 /// The maximum number of layers allowed in the fog of war texture array.
 /// 允许在雾效纹理数组中的最大层数。
 pub const MAX_LAYERS: u32 = 64;
 
+/// The maximum number of fog teams; fog arrays hold `MAX_LAYERS * team_count` layers.
+/// 雾效队伍的最大数量；雾效纹理数组包含 `MAX_LAYERS * team_count` 层。
+pub const MAX_TEAMS: u32 = 4;
+
+/// World plane the fog lies on.
+/// 雾效所在的世界平面
+///
+/// Fog coordinates (chunks, `RevealArea` positions, `world_bounds`, blocker grids) are
+/// 2D. On the XZ plane fog `x` is world `x` and fog `y` is world `-z`, so a camera
+/// looking down `-Y` with `-Z` up sees the fog the same way a 2D camera does.
+/// 雾效坐标是二维的；在 XZ 平面上，雾效 `x` 对应世界 `x`，雾效 `y` 对应世界 `-z`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FogGroundPlane {
+    /// 2D games: fog covers world XY, height is `z` (default).
+    #[default]
+    XY,
+    /// 3D games with a Y-up ground: fog covers world XZ, height is `y`.
+    XZ,
+}
+
+impl FogGroundPlane {
+    /// Projects a world position onto the plane, in fog coordinates.
+    /// 将世界位置投影到平面上，返回雾效坐标
+    pub fn to_fog(self, world_pos: Vec3) -> Vec2 {
+        match self {
+            Self::XY => world_pos.truncate(),
+            Self::XZ => Vec2::new(world_pos.x, -world_pos.z),
+        }
+    }
+
+    /// Returns the world position of a fog coordinate at `height` above the plane.
+    /// 返回雾效坐标在平面上方 `height` 处的世界位置
+    pub fn to_world(self, fog_pos: Vec2, height: f32) -> Vec3 {
+        match self {
+            Self::XY => fog_pos.extend(height),
+            Self::XZ => Vec3::new(fog_pos.x, height, -fog_pos.y),
+        }
+    }
+
+    /// Height of a world position above the plane.
+    pub fn height(self, world_pos: Vec3) -> f32 {
+        match self {
+            Self::XY => world_pos.z,
+            Self::XZ => world_pos.y,
+        }
+    }
+}
+
 /// Global configuration resource controlling fog of war behavior and appearance.
 #[derive(Resource, Clone, Debug)]
 pub struct FogMapSettings {
@@ -34,6 +83,39 @@ pub struct FogMapSettings {
 
     /// GPU texture format for exploration snapshots (default: RGBA8UnormSrgb).
     pub snapshot_texture_format: TextureFormat,
+
+    /// Number of teams with separate explored/visibility state (default: 1, max: `MAX_TEAMS`).
+    /// Insert the settings before adding `FogOfWarPlugin`; changing it at runtime is not supported.
+    pub team_count: u32,
+
+    /// Explored value lost per second by areas out of sight (default: 0.0, no decay).
+    /// At `0.1`, an area reverts to unexplored 10 seconds after it was last seen.
+    /// 视野外区域每秒损失的探索值（默认 0.0，不衰减）
+    pub explored_decay_rate: f32,
+
+    /// Extents of the playable map in world units (default: `None`, unbounded).
+    /// Chunks outside are never allocated, vision stops at the edge, and
+    /// `FogStats` / persistence treat the map as finite.
+    /// 可玩地图的世界范围（默认 `None`，无边界）
+    pub world_bounds: Option<Rect>,
+
+    /// Color drawn outside `world_bounds` (default: black).
+    /// `world_bounds` 之外区域的颜色（默认黑色）
+    pub fog_color_outside: Color,
+
+    /// World plane the fog is projected onto (default: XY).
+    /// Perspective cameras see the fog where their view rays hit this plane.
+    /// 雾效投影到的世界平面（默认 XY）
+    pub ground_plane: FogGroundPlane,
+
+    /// Maps ground plane coordinates to fog coordinates (default: identity).
+    /// For isometric maps this is the inverse of the grid-to-screen transform, so
+    /// chunks, vision ranges, `world_bounds` and `RevealArea` positions are in grid space.
+    /// Only positions are transformed: `VisionSource` ranges, polygon vertices and
+    /// directions are already fog units, while `VisionOccluder` outlines are local
+    /// world shapes and go through the transform with their entity.
+    /// 将地面平面坐标映射为雾效坐标（默认恒等变换）；等距地图可设为网格到屏幕变换的逆
+    pub fog_transform: Affine2,
 }
 
 impl Default for FogMapSettings {
@@ -50,6 +132,12 @@ impl Default for FogMapSettings {
             fog_texture_format: TextureFormat::R8Unorm,
             // 快照需要颜色和透明度 / Snapshots need color and alpha
             snapshot_texture_format: TextureFormat::Rgba8UnormSrgb,
+            team_count: 1,
+            explored_decay_rate: 0.0,
+            world_bounds: None,
+            fog_color_outside: Color::BLACK,
+            ground_plane: FogGroundPlane::XY,
+            fog_transform: Affine2::IDENTITY,
         }
     }
 }
@@ -63,10 +151,138 @@ impl FogMapSettings {
         )
     }
 
+    /// Projects a 3D world position onto the ground plane and applies `fog_transform`.
+    /// 将三维世界位置投影到地面平面上并应用 `fog_transform`，返回雾效坐标
+    pub fn world_to_fog(&self, world_pos: Vec3) -> Vec2 {
+        self.fog_transform
+            .transform_point2(self.ground_plane.to_fog(world_pos))
+    }
+
+    /// Returns the world position of a fog coordinate at `height` above the ground plane.
+    /// 返回雾效坐标在地面平面上方 `height` 处的世界位置
+    pub fn fog_to_world(&self, fog_pos: Vec2, height: f32) -> Vec3 {
+        let plane_pos = self.fog_transform.inverse().transform_point2(fog_pos);
+        self.ground_plane.to_world(plane_pos, height)
+    }
+
     /// Converts world coordinates to chunk coordinates using floor division.
     pub fn world_to_chunk_coords(&self, world_pos: Vec2) -> IVec2 {
         let chunk_x = (world_pos.x / self.chunk_size.x as f32).floor() as i32;
         let chunk_y = (world_pos.y / self.chunk_size.y as f32).floor() as i32;
         IVec2::new(chunk_x, chunk_y)
     }
+
+    /// Returns `team_count` clamped to `1..=MAX_TEAMS`.
+    pub fn effective_team_count(&self) -> u32 {
+        self.team_count.clamp(1, MAX_TEAMS)
+    }
+
+    /// Returns true if explored areas decay back to unexplored.
+    pub fn explored_decay_enabled(&self) -> bool {
+        self.explored_decay_rate > 0.0
+    }
+
+    /// Returns true if `team` has its own fog layers under the current settings.
+    pub fn contains_team(&self, team: FogTeam) -> bool {
+        u32::from(team.0) < self.effective_team_count()
+    }
+
+    /// Sets the transform from ground plane to fog coordinates.
+    pub fn with_fog_transform(mut self, fog_transform: Affine2) -> Self {
+        self.fog_transform = fog_transform;
+        self
+    }
+
+    /// Sets the playable map extents.
+    pub fn with_world_bounds(mut self, bounds: Rect) -> Self {
+        self.world_bounds = Some(bounds);
+        self
+    }
+
+    /// Returns true if `world_pos` lies inside `world_bounds` (always true when unbounded).
+    /// 如果 `world_pos` 位于 `world_bounds` 内则返回 true（无边界时总是 true）
+    pub fn is_in_world(&self, world_pos: Vec2) -> bool {
+        self.world_bounds
+            .is_none_or(|bounds| bounds.contains(world_pos))
+    }
+
+    /// Returns true if the chunk overlaps `world_bounds` and may be allocated.
+    /// 如果区块与 `world_bounds` 重叠（可以被分配）则返回 true
+    pub fn is_chunk_in_world(&self, chunk_coord: IVec2) -> bool {
+        self.world_bounds.is_none_or(|bounds| {
+            let min = self.chunk_coord_to_world(chunk_coord);
+            let max = min + self.chunk_size.as_vec2();
+            min.x < bounds.max.x
+                && min.y < bounds.max.y
+                && max.x > bounds.min.x
+                && max.y > bounds.min.y
+        })
+    }
+
+    /// Clamps an inclusive chunk range to the chunks overlapping `world_bounds`.
+    /// The range is empty (`min > max`) when it lies entirely outside the map.
+    /// 将包含式区块范围限制在与 `world_bounds` 重叠的区块内
+    pub fn clamp_chunk_range(&self, min_chunk: IVec2, max_chunk: IVec2) -> (IVec2, IVec2) {
+        let Some(bounds) = self.world_bounds else {
+            return (min_chunk, max_chunk);
+        };
+        let chunk_size = self.chunk_size.as_vec2();
+        let first = (bounds.min / chunk_size).floor().as_ivec2();
+        // A chunk merely touching the max edge doesn't overlap the map
+        // 仅接触最大边的区块不与地图重叠
+        let last = (bounds.max / chunk_size).ceil().as_ivec2() - IVec2::ONE;
+        (min_chunk.max(first), max_chunk.min(last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fog_transform_round_trip() {
+        let settings = FogMapSettings {
+            ground_plane: FogGroundPlane::XZ,
+            ..Default::default()
+        }
+        .with_fog_transform(Affine2::from_scale_angle_translation(
+            Vec2::new(2.0, 0.5),
+            0.3,
+            Vec2::new(5.0, -3.0),
+        ));
+
+        let world = Vec3::new(10.0, 7.0, -4.0);
+        let fog = settings.world_to_fog(world);
+        assert!(
+            fog.abs_diff_eq(
+                settings
+                    .fog_transform
+                    .transform_point2(Vec2::new(10.0, 4.0)),
+                1e-4
+            )
+        );
+        assert!(settings.fog_to_world(fog, 7.0).abs_diff_eq(world, 1e-4));
+
+        let fog = Vec2::new(-12.5, 40.0);
+        assert!(
+            settings
+                .world_to_fog(settings.fog_to_world(fog, 3.0))
+                .abs_diff_eq(fog, 1e-4)
+        );
+    }
+
+    #[test]
+    fn test_isometric_transform_maps_screen_to_grid() {
+        // 2:1 isometric tiles, one grid unit is (32, 16) along x and (-32, 16) along y
+        let grid_to_screen =
+            Affine2::from_cols(Vec2::new(32.0, 16.0), Vec2::new(-32.0, 16.0), Vec2::ZERO);
+        let settings = FogMapSettings::default().with_fog_transform(grid_to_screen.inverse());
+
+        let screen = grid_to_screen.transform_point2(Vec2::new(3.0, 1.0));
+        assert!(
+            settings
+                .world_to_fog(screen.extend(0.0))
+                .abs_diff_eq(Vec2::new(3.0, 1.0), 1e-4)
+        );
+    }
 }
@@ -8,7 +8,7 @@ use bevy_camera::{
     ScalingMode,
 };
 use bevy_color::Color;
-use bevy_image::Image;
+use bevy_image::{Image, TextureFormatPixelInfo};
 use bevy_math::{IVec2, Rect};
 use bevy_render::Render;
 use bevy_render::RenderApp;
@@ -16,9 +16,10 @@ use bevy_render::extract_component::ExtractComponent;
 use bevy_render::extract_resource::{ExtractResource, ExtractResourcePlugin};
 use bevy_render::render_asset::RenderAssets;
 use bevy_render::render_resource::{
-    Extent3d, Origin3d, TexelCopyTextureInfo, TextureAspect, TextureDimension, TextureUsages,
+    Extent3d, Origin3d, TexelCopyBufferLayout, TexelCopyTextureInfo, TextureAspect,
+    TextureDimension, TextureUsages,
 };
-use bevy_render::renderer::RenderContext;
+use bevy_render::renderer::{RenderContext, RenderQueue};
 use bevy_render::texture::GpuImage;
 use bevy_transform::components::{GlobalTransform, Transform};
 
@@ -69,7 +70,7 @@ impl Plugin for SnapshotPlugin {
     ///
     /// # System Setup
     /// - **Startup Systems**: `setup_snapshot_camera` - Creates snapshot camera and temp texture
-    /// - **Update Systems**: `prepare_snapshot_camera`, `ensure_snapshot_render_layer`, `handle_request_chunk_snapshot_events`
+    /// - **Update Systems**: `prepare_snapshot_camera`, `ensure_snapshot_render_layer`, `handle_request_chunk_snapshot_events`, `handle_clean_chunk_snapshot_events`
     /// - **PostUpdate Systems**: `prepare_snapshot_camera` - Configures camera for next frame
     /// - **Last Systems**: `check_snapshot_image_ready` - Manages camera activation state
     ///
@@ -82,7 +83,9 @@ impl Plugin for SnapshotPlugin {
     /// # Time Complexity: O(1) for setup, O(n) for entity render layer updates per frame
     fn build(&self, app: &mut App) {
         app.add_plugins(ExtractResourcePlugin::<SnapshotCameraState>::default());
+        app.add_plugins(ExtractResourcePlugin::<SnapshotClearQueue>::default());
         app.init_resource::<SnapshotCameraState>();
+        app.init_resource::<SnapshotClearQueue>();
         app.add_message::<RequestCleanChunkSnapshot>();
         app.add_systems(Startup, setup_snapshot_camera)
             .add_systems(PostUpdate, prepare_snapshot_camera)
@@ -95,6 +98,7 @@ impl Plugin for SnapshotPlugin {
             Update,
             (
                 handle_request_chunk_snapshot_events,
+                handle_clean_chunk_snapshot_events,
                 handle_force_snapshot_capturables,
             )
                 .after(FogSystems::UpdateChunkState) // Run after chunk states are updated / 在区块状态更新后运行
@@ -105,7 +109,10 @@ impl Plugin for SnapshotPlugin {
             return;
         };
 
-        render_app.add_systems(Render, snapshot_copy_system);
+        render_app.add_systems(
+            Render,
+            (snapshot_clear_system, snapshot_copy_system).chain(),
+        );
     }
 }
 
@@ -128,9 +135,20 @@ pub struct MainWorldSnapshotRequest {
 
 /// Event to request clean a snapshot for a specific chunk.
 /// 请求为特定区块清理快照的事件。
+///
+/// Clears the chunk's snapshot layer on the GPU and drops any snapshot still
+/// queued for it. Sent for chunks whose explored fog decays away while resident
+/// on the GPU.
 #[derive(Message, Debug, Clone, Copy)]
 pub struct RequestCleanChunkSnapshot(pub IVec2);
 
+/// Snapshot layers to clear in the render world this frame.
+/// 本帧需要在渲染世界中清除的快照层
+#[derive(Resource, ExtractResource, Clone, Default, Debug)]
+pub(crate) struct SnapshotClearQueue {
+    pub layers: Vec<u32>,
+}
+
 /// Component trigger to force a snapshot capture of specific Capturable entities.
 /// 强制对特定可捕获实体进行快照捕获的组件触发器
 ///
@@ -444,6 +462,68 @@ pub struct SnapshotCameraState {
     pub need_clear_layer_index: Option<u32>,
 }
 
+/// System that zeroes the snapshot layers queued in [`SnapshotClearQueue`].
+/// 将 [`SnapshotClearQueue`] 中排队的快照层清零的系统。
+///
+/// Runs before `snapshot_copy_system`. The queue is drained, so a layer is cleared
+/// once even if the main world does not extract a new queue next frame.
+fn snapshot_clear_system(
+    clear_queue: Option<ResMut<SnapshotClearQueue>>,
+    render_queue: Res<RenderQueue>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_snapshot_texture: Option<Res<RenderSnapshotTexture>>,
+) {
+    let Some(mut clear_queue) = clear_queue else {
+        return;
+    };
+    if clear_queue.layers.is_empty() {
+        return;
+    }
+    let layers = std::mem::take(&mut clear_queue.layers);
+
+    let Some(render_snapshot_texture) = render_snapshot_texture else {
+        return;
+    };
+    let Some(snapshot_images) = gpu_images.get(&render_snapshot_texture.0) else {
+        return;
+    };
+
+    let size = snapshot_images.texture_descriptor.size;
+    let bytes_per_row = size.width
+        * snapshot_images
+            .texture_descriptor
+            .format
+            .pixel_size()
+            .unwrap_or(4) as u32;
+    let clear_data = vec![0u8; (bytes_per_row * size.height) as usize];
+    for layer_index in layers {
+        render_queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &snapshot_images.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer_index,
+                },
+                aspect: TextureAspect::All,
+            },
+            &clear_data,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: None,
+            },
+            Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        trace!("Cleared snapshot layer {}.", layer_index);
+    }
+}
+
 /// System that copies completed snapshot textures to the final texture array.
 /// 将完成的快照纹理复制到最终纹理数组的系统。
 ///
@@ -671,6 +751,51 @@ fn handle_request_chunk_snapshot_events(
     }
 }
 
+/// System to handle `RequestCleanChunkSnapshot` and queue snapshot layer clears.
+/// 处理 `RequestCleanChunkSnapshot` 事件并对快照层清除进行排队的系统。
+///
+/// Pending snapshot requests for the chunk are dropped so a stale capture does not
+/// land on the layer after it was cleared. Chunks without a snapshot layer have
+/// nothing on the GPU to clear and are skipped.
+pub(crate) fn handle_clean_chunk_snapshot_events(
+    mut events: MessageReader<RequestCleanChunkSnapshot>,
+    chunk_manager: Res<ChunkEntityManager>,
+    chunk_query: Query<&FogChunk>,
+    mut snapshot_requests: ResMut<MainWorldSnapshotRequestQueue>,
+    mut clear_queue: ResMut<SnapshotClearQueue>,
+) {
+    // Last frame's clears were extracted already
+    // 上一帧的清除已被提取
+    if !clear_queue.layers.is_empty() {
+        clear_queue.layers.clear();
+    }
+
+    for event in events.read() {
+        let chunk_coords = event.0;
+        let Some(chunk) = chunk_manager
+            .map
+            .get(&chunk_coords)
+            .and_then(|entity| chunk_query.get(*entity).ok())
+        else {
+            continue;
+        };
+        let Some(snapshot_layer_index) = chunk.snapshot_layer_index else {
+            continue;
+        };
+
+        snapshot_requests
+            .requests
+            .retain(|req| req.chunk_coords != chunk_coords);
+        if !clear_queue.layers.contains(&snapshot_layer_index) {
+            trace!(
+                "Clearing snapshot layer {} of chunk {:?}.",
+                snapshot_layer_index, chunk_coords
+            );
+            clear_queue.layers.push(snapshot_layer_index);
+        }
+    }
+}
+
 /// System to handle `ForceSnapshotCapturables` events that force snapshots for all on-screen Capturable entities.
 /// 处理 `ForceSnapshotCapturables` 事件的系统，强制对屏幕上所有Capturable实体进行快照。
 ///
@@ -724,7 +849,7 @@ fn handle_force_snapshot_capturables(
         // Remove the trigger component now that we're processing it
         commands.entity(entity).remove::<ForceSnapshotCapturables>();
 
-        let entity_pos = entity_transform.translation().truncate();
+        let entity_pos = settings.world_to_fog(entity_transform.translation());
 
         // Convert entity position to chunk coordinates
         let chunk_coords = settings.world_to_chunk_coords(entity_pos);
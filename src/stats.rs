@@ -0,0 +1,913 @@
+//! Chunk exploration messages and map-completion statistics.
+//! 区块探索消息与地图完成度统计
+//!
+//! `update_chunk_component_state` writes [`ChunkExplored`], [`ChunkBecameVisible`] and
+//! [`ChunkLeftVision`] whenever a chunk's [`ChunkVisibility`] changes. [`FogStats`]
+//! tracks chunk counts and the explored area per team inside [`FogStats::map_bounds`]
+//! (or `FogMapSettings::world_bounds`), measured texel by texel from the fog data of
+//! CPU-resident chunks and from periodic readbacks of GPU-resident ones.
+//!
+//! [`RequestExploredFraction`] measures any region on demand: CPU-resident chunks are
+//! measured right away, GPU-resident fog layers are read back asynchronously, and the
+//! result arrives as an [`ExploredFractionReady`] message a few frames later.
+
+use crate::prelude::*;
+use bevy_asset::Assets;
+use bevy_image::Image;
+use bevy_math::{IVec2, Rect, Vec2};
+use bevy_render::extract_resource::ExtractResource;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+/// Fog texel value from which a texel counts as explored, the overlay's transition midpoint.
+pub(crate) const EXPLORED_TEXEL_THRESHOLD: u8 = 128;
+
+/// Frames to wait for a fog layer readback before requesting it again.
+const READBACK_RETRY_FRAMES: u32 = 30;
+
+/// Frames after which `FogStats` reads a GPU-resident chunk back again.
+const STATS_REFRESH_FRAMES: u32 = 60;
+
+/// Readbacks `FogStats` requests at most per frame.
+const STATS_READBACKS_PER_FRAME: usize = 4;
+
+/// Frames after which an explored-fraction request completes with the chunks
+/// measured so far, counting the rest as unexplored.
+const EXPLORED_FRACTION_TIMEOUT_FRAMES: u32 = 120;
+
+/// Message sent when a chunk becomes explored for the first time (or again after decay).
+/// 当区块首次（或衰减后再次）被探索时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkExplored {
+    /// Coordinates of the chunk.
+    pub coords: IVec2,
+}
+
+/// Message sent when a chunk enters vision.
+/// 当区块进入视野时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkBecameVisible {
+    /// Coordinates of the chunk.
+    pub coords: IVec2,
+}
+
+/// Message sent when a chunk leaves vision.
+/// 当区块离开视野时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkLeftVision {
+    /// Coordinates of the chunk.
+    pub coords: IVec2,
+}
+
+/// Exploration statistics for achievements and map-completion UI.
+/// 用于成就与地图完成度界面的探索统计
+///
+/// Explored area is counted per fog texel. Chunks whose fog data is on the CPU are
+/// measured directly; chunks resident on the GPU are read back a few at a time,
+/// each at most every 60 frames, and count as unexplored until their first
+/// readback arrives.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn setup(mut stats: ResMut<FogStats>) {
+///     stats.map_bounds = Some(Rect::new(-2048.0, -2048.0, 2048.0, 2048.0));
+/// }
+///
+/// fn show_progress(stats: Res<FogStats>) {
+///     if let Some(percentage) = stats.explored_percentage(FogTeam(0)) {
+///         info!("{percentage:.0}% of the map explored");
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FogStats {
+    /// World rect the statistics cover, `FogMapSettings::world_bounds` when `None`.
+    /// Without either, every chunk is counted and there is no fraction.
+    /// 统计覆盖的世界矩形，为 `None` 时使用 `FogMapSettings::world_bounds`；
+    /// 两者都没有时统计所有区块且不计算比例
+    pub map_bounds: Option<Rect>,
+
+    explored_chunks: usize,
+    visible_chunks: usize,
+    explored_area: [f32; MAX_TEAMS as usize],
+
+    /// Explored area per team of CPU-resident chunks, measured from their fog data.
+    measured: HashMap<IVec2, Vec<f32>>,
+    /// Explored area per team of GPU-resident chunks, measured from their latest readback.
+    read_back: HashMap<IVec2, ReadBackArea>,
+    /// Bounds the measurements were taken with, `map_bounds` or the world bounds.
+    measured_bounds: Option<Rect>,
+}
+
+impl FogStats {
+    /// Sets the world rect the statistics cover.
+    pub fn with_map_bounds(mut self, bounds: Rect) -> Self {
+        self.map_bounds = Some(bounds);
+        self
+    }
+
+    /// Number of chunks explored by any team within the bounds.
+    /// 边界内被任意队伍探索的区块数量
+    pub fn explored_chunks(&self) -> usize {
+        self.explored_chunks
+    }
+
+    /// Number of chunks in vision of any team within the bounds.
+    /// 边界内处于任意队伍视野中的区块数量
+    pub fn visible_chunks(&self) -> usize {
+        self.visible_chunks
+    }
+
+    /// Explored world area of `team` within the bounds, in square world units.
+    /// `team` 在边界内已探索的世界面积（世界单位的平方）
+    pub fn explored_area(&self, team: FogTeam) -> f32 {
+        self.explored_area
+            .get(usize::from(team.0))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Explored fraction of the bounds for `team` in `0.0..=1.0`, `None` without bounds.
+    /// `team` 已探索的边界面积比例，没有边界时为 `None`
+    pub fn explored_fraction(&self, team: FogTeam) -> Option<f32> {
+        let area = rect_area(self.measured_bounds?);
+        (area > 0.0).then(|| (self.explored_area(team) / area).clamp(0.0, 1.0))
+    }
+
+    /// Explored percentage of the bounds for `team` in `0.0..=100.0`, `None` without bounds.
+    pub fn explored_percentage(&self, team: FogTeam) -> Option<f32> {
+        self.explored_fraction(team)
+            .map(|fraction| fraction * 100.0)
+    }
+}
+
+/// Explored area per team of a GPU-resident chunk, from its latest readback.
+#[derive(Debug, Clone)]
+struct ReadBackArea {
+    area: Vec<f32>,
+    /// Frames since the readback.
+    age: u32,
+}
+
+fn rect_area(rect: Rect) -> f32 {
+    if rect.is_empty() {
+        0.0
+    } else {
+        rect.width() * rect.height()
+    }
+}
+
+/// World rect covered by a chunk.
+fn chunk_rect(settings: &FogMapSettings, coords: IVec2) -> Rect {
+    let min = settings.chunk_coord_to_world(coords);
+    Rect::from_corners(min, min + settings.chunk_size.as_vec2())
+}
+
+/// Area of a chunk inside `bounds` (the whole chunk without bounds).
+fn chunk_area_in(settings: &FogMapSettings, coords: IVec2, bounds: Option<Rect>) -> f32 {
+    let rect = chunk_rect(settings, coords);
+    rect_area(bounds.map_or(rect, |bounds| rect.intersect(bounds)))
+}
+
+/// Explored area per team layer of a chunk's fog data, counting texels whose center
+/// lies inside `bounds`.
+/// 按队伍层统计区块雾效数据中的已探索面积，仅计入中心位于 `bounds` 内的纹素
+pub(crate) fn measure_explored_area(
+    settings: &FogMapSettings,
+    coords: IVec2,
+    fog_data: &[u8],
+    bounds: Option<Rect>,
+) -> Vec<f32> {
+    let resolution = settings.texture_resolution_per_chunk;
+    let texels = (resolution.x * resolution.y) as usize;
+    let team_count = settings.effective_team_count() as usize;
+    if texels == 0 || fog_data.len() < texels * team_count {
+        return vec![0.0; team_count];
+    }
+    let bytes_per_texel = fog_data.len() / (texels * team_count);
+
+    let origin = settings.chunk_coord_to_world(coords);
+    let texel_size = settings.chunk_size.as_vec2() / resolution.as_vec2();
+    let texel_area = texel_size.x * texel_size.y;
+    let inside = |index: usize| {
+        bounds.is_none_or(|bounds| {
+            let texel = Vec2::new(
+                (index % resolution.x as usize) as f32,
+                (index / resolution.x as usize) as f32,
+            );
+            bounds.contains(origin + (texel + 0.5) * texel_size)
+        })
+    };
+
+    fog_data
+        .chunks_exact(texels * bytes_per_texel)
+        .take(team_count)
+        .map(|layer| {
+            let explored = layer
+                .chunks_exact(bytes_per_texel)
+                .enumerate()
+                .filter(|(index, texel)| texel[0] >= EXPLORED_TEXEL_THRESHOLD && inside(*index))
+                .count();
+            explored as f32 * texel_area
+        })
+        .collect()
+}
+
+/// Recomputes [`FogStats`] from the chunk cache, CPU-resident fog data and readbacks.
+/// 根据区块缓存、CPU 驻留的雾效数据与回读结果重新计算 [`FogStats`]
+///
+/// GPU-resident explored chunks without a recent readback get one requested, at
+/// most [`STATS_READBACKS_PER_FRAME`] per frame.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_fog_stats(
+    settings: Res<FogMapSettings>,
+    cache: Res<ChunkStateCache>,
+    mut stats: ResMut<FogStats>,
+    mut readbacks: MessageReader<FogLayerReadback>,
+    mut readback_requests: ResMut<FogReadbackRequests>,
+    mut in_flight: ResMut<FogReadbacksInFlight>,
+    chunks: Query<(&FogChunk, &FogChunkImage)>,
+    images: Res<Assets<Image>>,
+) {
+    let stats = &mut *stats;
+    let bounds = stats.map_bounds.or(settings.world_bounds);
+    if stats.measured_bounds != bounds {
+        stats.measured.clear();
+        stats.read_back.clear();
+        stats.measured_bounds = bounds;
+    }
+
+    for read_back in stats.read_back.values_mut() {
+        read_back.age += 1;
+    }
+    for readback in readbacks.read() {
+        let area = measure_explored_area(&settings, readback.coords, &readback.fog_data, bounds);
+        stats
+            .read_back
+            .insert(readback.coords, ReadBackArea { area, age: 0 });
+    }
+
+    // Measure chunks once per stay on the CPU; their data only changes on the GPU
+    // 每次驻留 CPU 时只测量一次区块；其数据只会在 GPU 上改变
+    let mut on_cpu = HashSet::new();
+    let mut requested = 0;
+    for (chunk, chunk_image) in &chunks {
+        if !cache.explored_chunks.contains(&chunk.coords) {
+            continue;
+        }
+        match chunk.state.memory_location {
+            ChunkMemoryLocation::Cpu => {}
+            ChunkMemoryLocation::Gpu => {
+                // Read stale GPU-resident chunks back, a few per frame
+                // 回读过期的 GPU 驻留区块，每帧少量
+                let stale = stats
+                    .read_back
+                    .get(&chunk.coords)
+                    .is_none_or(|read_back| read_back.age >= STATS_REFRESH_FRAMES);
+                let fog_layer_index = chunk
+                    .fog_layer_index
+                    .filter(|_| stale && requested < STATS_READBACKS_PER_FRAME);
+                if fog_layer_index.is_some_and(|fog_layer_index| {
+                    in_flight.request(&mut readback_requests, chunk.coords, fog_layer_index)
+                }) {
+                    requested += 1;
+                }
+                continue;
+            }
+            ChunkMemoryLocation::PendingCopyToCpu | ChunkMemoryLocation::PendingCopyToGpu => {
+                continue;
+            }
+        }
+        on_cpu.insert(chunk.coords);
+        if stats.measured.contains_key(&chunk.coords) {
+            continue;
+        }
+        let Some(data) = images
+            .get(&chunk_image.fog_image_handle)
+            .and_then(|image| image.data.as_deref())
+        else {
+            continue;
+        };
+        let area = measure_explored_area(&settings, chunk.coords, data, bounds);
+        stats.measured.insert(chunk.coords, area);
+    }
+
+    // Chunks leaving the CPU keep their measurement until a readback replaces it
+    // 离开 CPU 的区块保留其测量值，直到被回读结果替换
+    let left_cpu: Vec<_> = stats
+        .measured
+        .keys()
+        .filter(|coords| !on_cpu.contains(*coords))
+        .copied()
+        .collect();
+    for coords in left_cpu {
+        if let Some(area) = stats.measured.remove(&coords) {
+            stats.read_back.insert(
+                coords,
+                ReadBackArea {
+                    area,
+                    age: STATS_REFRESH_FRAMES,
+                },
+            );
+        }
+    }
+    stats
+        .read_back
+        .retain(|coords, _| !on_cpu.contains(coords) && cache.explored_chunks.contains(coords));
+
+    let in_bounds = |coords: &&IVec2| {
+        bounds.is_none_or(|bounds| !chunk_rect(&settings, **coords).intersect(bounds).is_empty())
+    };
+    stats.explored_chunks = cache.explored_chunks.iter().filter(in_bounds).count();
+    stats.visible_chunks = cache.visible_chunks.iter().filter(in_bounds).count();
+
+    stats.explored_area = [0.0; MAX_TEAMS as usize];
+    for (team, explored) in &cache.team_explored_chunks {
+        let Some(total) = stats.explored_area.get_mut(usize::from(team.0)) else {
+            continue;
+        };
+        // Chunks not measured yet count as unexplored
+        // 尚未测量的区块按未探索计算
+        *total = explored
+            .iter()
+            .filter_map(|coords| {
+                stats
+                    .measured
+                    .get(coords)
+                    .or_else(|| stats.read_back.get(coords).map(|read_back| &read_back.area))
+                    .and_then(|area| area.get(usize::from(team.0)).copied())
+            })
+            .sum();
+    }
+}
+
+/// Message requesting the explored fraction of a world region for a team.
+/// 请求某队伍在世界区域内的已探索比例的消息
+///
+/// The answer is an [`ExploredFractionReady`] carrying the same `id`. It is written
+/// the same frame when every overlapping chunk is unexplored or on the CPU, and a few
+/// frames later when GPU-resident fog layers have to be read back. A request still
+/// waiting after 120 frames completes anyway, counting the chunks it could not
+/// measure as unexplored.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn request(mut requests: MessageWriter<RequestExploredFraction>) {
+///     let map = Rect::new(-2048.0, -2048.0, 2048.0, 2048.0);
+///     requests.write(RequestExploredFraction::new(map, FogTeam(0)).with_id(7));
+/// }
+///
+/// fn receive(mut ready: MessageReader<ExploredFractionReady>) {
+///     for result in ready.read().filter(|result| result.id == 7) {
+///         info!("{:.1}% explored", result.fraction * 100.0);
+///     }
+/// }
+/// ```
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct RequestExploredFraction {
+    /// Caller-chosen id echoed in the result.
+    /// 由调用方选择、在结果中原样返回的 ID
+    pub id: u64,
+
+    /// World region to measure. Parts outside `FogMapSettings::world_bounds` are ignored.
+    /// 要测量的世界区域，`FogMapSettings::world_bounds` 之外的部分会被忽略
+    pub region: Rect,
+
+    /// Team whose explored layer is measured.
+    /// 被测量的探索层所属的队伍
+    pub team: FogTeam,
+}
+
+impl RequestExploredFraction {
+    /// Requests the explored fraction of `region` for `team`.
+    pub fn new(region: Rect, team: FogTeam) -> Self {
+        Self {
+            id: 0,
+            region,
+            team,
+        }
+    }
+
+    /// Sets the id echoed in the result.
+    pub fn with_id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+}
+
+/// Result of a [`RequestExploredFraction`].
+/// [`RequestExploredFraction`] 的结果
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct ExploredFractionReady {
+    /// Id of the request.
+    pub id: u64,
+
+    /// Region that was measured, clipped to `FogMapSettings::world_bounds`.
+    pub region: Rect,
+
+    /// Team that was measured.
+    pub team: FogTeam,
+
+    /// Explored fraction of the region in `0.0..=1.0`.
+    /// 区域的已探索比例
+    pub fraction: f32,
+
+    /// Explored fraction of each overlapping chunk's part inside the region.
+    /// 每个重叠区块在区域内部分的已探索比例
+    pub chunks: HashMap<IVec2, f32>,
+}
+
+/// Request to read a chunk's fog layers back from the GPU texture array.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FogReadbackRequest {
+    pub coords: IVec2,
+    pub fog_layer_index: u32,
+}
+
+/// Fog layer readbacks requested this frame, drained by the render world.
+/// 本帧请求的雾效层回读，由渲染世界处理
+#[derive(Resource, Debug, Clone, Default, ExtractResource)]
+pub(crate) struct FogReadbackRequests {
+    pub requests: Vec<FogReadbackRequest>,
+}
+
+/// Fog layer readbacks in flight, shared by [`FogStats`] and explored-fraction requests.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct FogReadbacksInFlight {
+    /// Frames since each in-flight readback was requested.
+    frames: HashMap<IVec2, u32>,
+}
+
+impl FogReadbacksInFlight {
+    /// Requests a readback of a chunk's fog layers unless one is already in flight.
+    /// Returns true if a readback was requested.
+    fn request(
+        &mut self,
+        requests: &mut FogReadbackRequests,
+        coords: IVec2,
+        fog_layer_index: u32,
+    ) -> bool {
+        let Entry::Vacant(entry) = self.frames.entry(coords) else {
+            return false;
+        };
+        entry.insert(0);
+        requests.requests.push(FogReadbackRequest {
+            coords,
+            fog_layer_index,
+        });
+        true
+    }
+}
+
+/// Clears last frame's readback requests and tracks the readbacks in flight.
+/// 清除上一帧的回读请求并跟踪进行中的回读
+///
+/// Readbacks that never arrive, e.g. because the chunk left the GPU, may be
+/// requested again after [`READBACK_RETRY_FRAMES`].
+pub(crate) fn update_fog_readbacks(
+    mut readbacks: MessageReader<FogLayerReadback>,
+    mut readback_requests: ResMut<FogReadbackRequests>,
+    mut in_flight: ResMut<FogReadbacksInFlight>,
+) {
+    // Last frame's requests have been extracted by now
+    // 上一帧的请求此时已被提取
+    if !readback_requests.requests.is_empty() {
+        readback_requests.requests.clear();
+    }
+
+    for readback in readbacks.read() {
+        in_flight.frames.remove(&readback.coords);
+    }
+    in_flight.frames.retain(|_, frames| {
+        *frames += 1;
+        *frames < READBACK_RETRY_FRAMES
+    });
+}
+
+/// Every team layer of a chunk's fog data, read back from the GPU.
+#[derive(Message, Debug, Clone)]
+pub(crate) struct FogLayerReadback {
+    pub coords: IVec2,
+    pub fog_data: Vec<u8>,
+}
+
+/// A [`RequestExploredFraction`] waiting for chunk data.
+#[derive(Debug)]
+struct PendingFraction {
+    request: RequestExploredFraction,
+    /// Explored area of each measured chunk inside the region.
+    explored: HashMap<IVec2, f32>,
+    /// Chunks not measured yet.
+    unresolved: HashSet<IVec2>,
+    /// Frames the request has been waiting.
+    frames: u32,
+}
+
+/// Explored-fraction requests in progress.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct PendingExploredFractions {
+    pending: Vec<PendingFraction>,
+}
+
+impl PendingFraction {
+    fn new(settings: &FogMapSettings, mut request: RequestExploredFraction) -> Self {
+        if let Some(bounds) = settings.world_bounds {
+            request.region = request.region.intersect(bounds);
+        }
+        let min = settings.world_to_chunk_coords(request.region.min);
+        let max = settings.world_to_chunk_coords(request.region.max);
+        let unresolved = (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter(|coords| chunk_area_in(settings, *coords, Some(request.region)) > 0.0)
+            .collect();
+        Self {
+            request,
+            explored: HashMap::new(),
+            unresolved,
+            frames: 0,
+        }
+    }
+
+    fn resolve(&mut self, coords: IVec2, explored_area: f32) {
+        if self.unresolved.remove(&coords) {
+            self.explored.insert(coords, explored_area);
+        }
+    }
+
+    fn measure(&mut self, settings: &FogMapSettings, coords: IVec2, fog_data: &[u8]) {
+        let area = measure_explored_area(settings, coords, fog_data, Some(self.request.region));
+        let team_area = area
+            .get(usize::from(self.request.team.0))
+            .copied()
+            .unwrap_or(0.0);
+        self.resolve(coords, team_area);
+    }
+
+    fn finish(self, settings: &FogMapSettings) -> ExploredFractionReady {
+        let region = self.request.region;
+        let fraction = |explored: f32, area: f32| {
+            if area > 0.0 {
+                (explored / area).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        };
+        let chunks = self
+            .explored
+            .iter()
+            .map(|(coords, explored)| {
+                let area = chunk_area_in(settings, *coords, Some(region));
+                (*coords, fraction(*explored, area))
+            })
+            .collect();
+        ExploredFractionReady {
+            id: self.request.id,
+            region,
+            team: self.request.team,
+            fraction: fraction(self.explored.values().sum(), rect_area(region)),
+            chunks,
+        }
+    }
+}
+
+/// Measures requested regions and requests GPU readbacks for GPU-resident chunks.
+/// 测量请求的区域，并为驻留 GPU 的区块请求回读
+///
+/// Chunks being uploaded are measured from their CPU data, chunks being downloaded
+/// are retried next frame, and requests still waiting after
+/// [`EXPLORED_FRACTION_TIMEOUT_FRAMES`] complete without them.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_explored_fraction_requests(
+    settings: Res<FogMapSettings>,
+    cache: Res<ChunkStateCache>,
+    chunk_manager: Res<ChunkEntityManager>,
+    chunks: Query<(&FogChunk, &FogChunkImage)>,
+    images: Res<Assets<Image>>,
+    mut requests: MessageReader<RequestExploredFraction>,
+    mut readbacks: MessageReader<FogLayerReadback>,
+    mut readback_requests: ResMut<FogReadbackRequests>,
+    mut in_flight: ResMut<FogReadbacksInFlight>,
+    mut fractions: ResMut<PendingExploredFractions>,
+    mut ready_writer: MessageWriter<ExploredFractionReady>,
+) {
+    for readback in readbacks.read() {
+        for pending in &mut fractions.pending {
+            if pending.unresolved.contains(&readback.coords) {
+                pending.measure(&settings, readback.coords, &readback.fog_data);
+            }
+        }
+    }
+
+    for request in requests.read() {
+        fractions
+            .pending
+            .push(PendingFraction::new(&settings, *request));
+    }
+
+    for pending in &mut fractions.pending {
+        let team = pending.request.team;
+        for coords in pending.unresolved.clone() {
+            if !cache.is_explored_by(team, coords) {
+                pending.resolve(coords, 0.0);
+                continue;
+            }
+            let Some((chunk, chunk_image)) = chunk_manager
+                .map
+                .get(&coords)
+                .and_then(|entity| chunks.get(*entity).ok())
+            else {
+                // Explored but not spawned yet: count it whole rather than wait
+                // 已探索但尚未生成实体：直接按完整区块计算而不等待
+                let area = chunk_area_in(&settings, coords, Some(pending.request.region));
+                pending.resolve(coords, area);
+                continue;
+            };
+
+            match chunk.state.memory_location {
+                // Uploads still hold the fog data on the CPU
+                // 上传中的区块仍在 CPU 上保留雾效数据
+                ChunkMemoryLocation::Cpu | ChunkMemoryLocation::PendingCopyToGpu => {
+                    let data = images
+                        .get(&chunk_image.fog_image_handle)
+                        .and_then(|image| image.data.as_deref());
+                    match data {
+                        Some(data) => pending.measure(&settings, coords, data),
+                        None => pending.resolve(coords, 0.0),
+                    }
+                }
+                ChunkMemoryLocation::Gpu => {
+                    let Some(fog_layer_index) = chunk.fog_layer_index else {
+                        warn!(
+                            "Chunk {:?} is on the GPU without a fog layer, counting it as unexplored",
+                            coords
+                        );
+                        pending.resolve(coords, 0.0);
+                        continue;
+                    };
+                    in_flight.request(&mut readback_requests, coords, fog_layer_index);
+                }
+                ChunkMemoryLocation::PendingCopyToCpu => {}
+            }
+        }
+
+        pending.frames += 1;
+        if !pending.unresolved.is_empty() && pending.frames >= EXPLORED_FRACTION_TIMEOUT_FRAMES {
+            warn!(
+                "Explored fraction request {} timed out, counting {} chunks as unexplored",
+                pending.request.id,
+                pending.unresolved.len()
+            );
+            for coords in std::mem::take(&mut pending.unresolved) {
+                pending.explored.insert(coords, 0.0);
+            }
+        }
+    }
+
+    let (done, waiting) = std::mem::take(&mut fractions.pending)
+        .into_iter()
+        .partition::<Vec<_>, _>(|pending| pending.unresolved.is_empty());
+    fractions.pending = waiting;
+    for pending in done {
+        ready_writer.write(pending.finish(&settings));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_math::UVec2;
+
+    #[test]
+    fn test_explored_area_is_counted_per_texel_within_bounds() {
+        let settings = FogMapSettings {
+            chunk_size: UVec2::splat(100),
+            texture_resolution_per_chunk: UVec2::splat(4),
+            team_count: 2,
+            ..Default::default()
+        };
+        // Team 0 explored the bottom row, team 1 the left column
+        let mut fog = vec![0u8; 32];
+        fog[0..4].fill(255);
+        for row in 0..4 {
+            fog[16 + row * 4] = 200;
+        }
+        fog[1] = 127;
+
+        let area = measure_explored_area(&settings, IVec2::new(1, 0), &fog, None);
+        assert_eq!(area, vec![3.0 * 625.0, 4.0 * 625.0]);
+
+        // Only the chunk's left half (x < 150) is on the map
+        let bounds = Rect::new(0.0, 0.0, 150.0, 100.0);
+        let area = measure_explored_area(&settings, IVec2::new(1, 0), &fog, Some(bounds));
+        assert_eq!(area, vec![625.0, 4.0 * 625.0]);
+        assert_eq!(
+            chunk_area_in(&settings, IVec2::new(1, 0), Some(bounds)),
+            5000.0
+        );
+    }
+
+    #[test]
+    fn test_region_fraction_combines_partial_chunks() {
+        let settings = FogMapSettings {
+            chunk_size: UVec2::splat(100),
+            texture_resolution_per_chunk: UVec2::splat(4),
+            ..Default::default()
+        };
+        // Covers the right half of chunk (0, 0) and the left half of chunk (1, 0)
+        let region = Rect::new(50.0, 0.0, 150.0, 100.0);
+        let mut pending = PendingFraction::new(
+            &settings,
+            RequestExploredFraction::new(region, FogTeam(0)).with_id(3),
+        );
+        assert_eq!(pending.unresolved.len(), 2);
+
+        pending.measure(&settings, IVec2::ZERO, &[255u8; 16]);
+        pending.resolve(IVec2::new(1, 0), 0.0);
+        let result = pending.finish(&settings);
+        assert_eq!(result.id, 3);
+        assert_eq!(result.fraction, 0.5);
+        assert_eq!(result.chunks[&IVec2::ZERO], 1.0);
+        assert_eq!(result.chunks[&IVec2::new(1, 0)], 0.0);
+    }
+
+    #[test]
+    fn test_region_is_clipped_to_world_bounds() {
+        let settings = FogMapSettings {
+            chunk_size: UVec2::splat(100),
+            texture_resolution_per_chunk: UVec2::splat(4),
+            ..Default::default()
+        }
+        .with_world_bounds(Rect::new(0.0, 0.0, 100.0, 100.0));
+        assert!(settings.is_chunk_in_world(IVec2::ZERO));
+        assert!(!settings.is_chunk_in_world(IVec2::new(1, 0)));
+        assert_eq!(
+            settings.clamp_chunk_range(IVec2::splat(-3), IVec2::splat(3)),
+            (IVec2::ZERO, IVec2::ZERO)
+        );
+
+        let region = Rect::new(50.0, 0.0, 150.0, 100.0);
+        let mut pending =
+            PendingFraction::new(&settings, RequestExploredFraction::new(region, FogTeam(0)));
+        assert_eq!(pending.unresolved.len(), 1);
+
+        pending.measure(&settings, IVec2::ZERO, &[255u8; 16]);
+        let result = pending.finish(&settings);
+        assert_eq!(result.region, Rect::new(50.0, 0.0, 100.0, 100.0));
+        assert_eq!(result.fraction, 1.0);
+    }
+
+    #[test]
+    fn test_gpu_chunks_are_read_back_for_stats() {
+        let settings = FogMapSettings {
+            chunk_size: UVec2::splat(100),
+            texture_resolution_per_chunk: UVec2::splat(4),
+            ..Default::default()
+        };
+        let mut app = App::new();
+        app.insert_resource(settings.clone())
+            .init_resource::<ChunkStateCache>()
+            .init_resource::<FogStats>()
+            .init_resource::<FogReadbackRequests>()
+            .init_resource::<FogReadbacksInFlight>()
+            .init_resource::<Assets<Image>>()
+            .add_message::<FogLayerReadback>()
+            .add_systems(Update, (update_fog_readbacks, update_fog_stats).chain());
+
+        let chunk_image = FogChunkImage::from_setting_raw(
+            &mut app.world_mut().resource_mut::<Assets<Image>>(),
+            &settings,
+        );
+        let mut chunk = FogChunk::new(IVec2::ZERO, settings.chunk_size, 1.0);
+        chunk.fog_layer_index = Some(2);
+        chunk.state.memory_location = ChunkMemoryLocation::Gpu;
+        app.world_mut().spawn((chunk, chunk_image));
+        app.world_mut()
+            .resource_mut::<ChunkStateCache>()
+            .mark_explored(FogTeam(0), IVec2::ZERO);
+
+        // Unmeasured GPU chunks count as unexplored and get read back
+        app.update();
+        let requests = &app.world().resource::<FogReadbackRequests>().requests;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].fog_layer_index, 2);
+        let stats = app.world().resource::<FogStats>();
+        assert_eq!(stats.explored_chunks(), 1);
+        assert_eq!(stats.explored_area(FogTeam(0)), 0.0);
+
+        // Half of the chunk turns out explored
+        let mut fog_data = vec![0u8; 16];
+        fog_data[..8].fill(255);
+        app.world_mut().write_message(FogLayerReadback {
+            coords: IVec2::ZERO,
+            fog_data,
+        });
+        app.update();
+        assert!(
+            app.world()
+                .resource::<FogReadbackRequests>()
+                .requests
+                .is_empty()
+        );
+        assert_eq!(
+            app.world().resource::<FogStats>().explored_area(FogTeam(0)),
+            5000.0
+        );
+
+        // Refreshed once the measurement is stale
+        for _ in 1..STATS_REFRESH_FRAMES {
+            app.update();
+            assert!(
+                app.world()
+                    .resource::<FogReadbackRequests>()
+                    .requests
+                    .is_empty()
+            );
+        }
+        app.update();
+        assert_eq!(
+            app.world().resource::<FogReadbackRequests>().requests.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_fraction_requests_complete_for_chunks_without_data() {
+        let settings = FogMapSettings {
+            chunk_size: UVec2::splat(100),
+            texture_resolution_per_chunk: UVec2::splat(4),
+            ..Default::default()
+        };
+        let mut app = App::new();
+        app.insert_resource(settings.clone())
+            .init_resource::<ChunkStateCache>()
+            .init_resource::<ChunkEntityManager>()
+            .init_resource::<FogReadbackRequests>()
+            .init_resource::<FogReadbacksInFlight>()
+            .init_resource::<PendingExploredFractions>()
+            .init_resource::<Assets<Image>>()
+            .add_message::<RequestExploredFraction>()
+            .add_message::<FogLayerReadback>()
+            .add_message::<ExploredFractionReady>()
+            .add_systems(
+                Update,
+                (update_fog_readbacks, process_explored_fraction_requests).chain(),
+            );
+
+        // Chunk (0, 0) is on the GPU without a fog layer, chunk (1, 0) is being downloaded
+        for (x, memory_location) in [
+            (0, ChunkMemoryLocation::Gpu),
+            (1, ChunkMemoryLocation::PendingCopyToCpu),
+        ] {
+            let coords = IVec2::new(x, 0);
+            let chunk_image = FogChunkImage::from_setting_raw(
+                &mut app.world_mut().resource_mut::<Assets<Image>>(),
+                &settings,
+            );
+            let mut chunk = FogChunk::new(coords, settings.chunk_size, 1.0);
+            chunk.state.memory_location = memory_location;
+            let entity = app.world_mut().spawn((chunk, chunk_image)).id();
+            app.world_mut()
+                .resource_mut::<ChunkEntityManager>()
+                .map
+                .insert(coords, entity);
+            app.world_mut()
+                .resource_mut::<ChunkStateCache>()
+                .mark_explored(FogTeam(0), coords);
+        }
+
+        let ready = |app: &mut App| {
+            app.world_mut()
+                .resource_mut::<Messages<ExploredFractionReady>>()
+                .drain()
+                .collect::<Vec<_>>()
+        };
+        app.world_mut().write_message(
+            RequestExploredFraction::new(Rect::new(0.0, 0.0, 100.0, 100.0), FogTeam(0)).with_id(1),
+        );
+        app.update();
+        let results = ready(&mut app);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, 1);
+        assert_eq!(results[0].fraction, 0.0);
+
+        // The download never settles, so the request times out
+        app.world_mut().write_message(
+            RequestExploredFraction::new(Rect::new(100.0, 0.0, 200.0, 100.0), FogTeam(0))
+                .with_id(2),
+        );
+        for _ in 1..EXPLORED_FRACTION_TIMEOUT_FRAMES {
+            app.update();
+            assert!(ready(&mut app).is_empty());
+        }
+        app.update();
+        let results = ready(&mut app);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, 2);
+        assert_eq!(results[0].fraction, 0.0);
+        assert_eq!(results[0].chunks[&IVec2::new(1, 0)], 0.0);
+    }
+}
@@ -0,0 +1,222 @@
+//! Stealth and detection layers.
+//! 隐身与侦测层级
+//!
+//! A [`Stealth`] entity standing in visible terrain is only seen by a team if one of
+//! the team's vision sources (or an ally's, through shared vision) with a sufficient
+//! [`Detection`] level covers its position. Coverage uses the same shapes, falloff
+//! and occlusion as the fog shader; the fog texture itself is unaffected.
+//!
+//! Detection is evaluated every frame for every team and exposed through
+//! [`Stealth::is_detected_by`], [`EntityDetected`] / [`EntityUndetected`] messages,
+//! and [`FogHideable`] / [`FogGhost`], which treat undetected entities as out of vision.
+
+use crate::prelude::*;
+use bevy_transform::components::GlobalTransform;
+
+/// Hides an entity from vision sources whose [`Detection`] level is below `level`.
+/// 使实体对侦测等级低于 `level` 的视野源隐身
+///
+/// Level `0` is seen by every source. Teams sharing vision with the entity's own
+/// [`FogTeam`] always detect it.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn spawn(mut commands: Commands) {
+///     // Cloaked enemy: hidden unless a level 2+ detector covers it
+///     commands.spawn((Transform::default(), Stealth::new(2), FogHideable::default(), FogTeam(1)));
+///     // Observer tower that sees cloaked units
+///     commands.spawn((Transform::default(), VisionSource::circle(250.0), Detection::new(2)));
+/// }
+/// ```
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Component, Default)]
+pub struct Stealth {
+    /// Minimum detection level needed to see the entity.
+    /// 看到该实体所需的最低侦测等级
+    pub level: u8,
+
+    /// Bit per team that detected the entity on the last update.
+    detected: u32,
+}
+
+impl Stealth {
+    /// Creates a stealth component of the given level.
+    /// 创建指定等级的隐身组件
+    pub fn new(level: u8) -> Self {
+        Self { level, detected: 0 }
+    }
+
+    /// Returns true if `team` detected the entity on the last update.
+    /// 如果上次更新时 `team` 侦测到了该实体则返回 true
+    pub fn is_detected_by(&self, team: FogTeam) -> bool {
+        u32::from(team.0) < MAX_TEAMS && self.detected & (1 << team.0) != 0
+    }
+}
+
+/// Detection level of a vision source entity (`0` without this component).
+/// 视野源实体的侦测等级（没有该组件时为 `0`）
+///
+/// A source detects [`Stealth`] entities whose level does not exceed its own.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct Detection {
+    /// Highest stealth level this source sees through.
+    /// 该视野源能够看穿的最高隐身等级
+    pub level: u8,
+}
+
+impl Detection {
+    /// Creates a detection component of the given level.
+    pub fn new(level: u8) -> Self {
+        Self { level }
+    }
+}
+
+/// Message sent when a team starts detecting a [`Stealth`] entity.
+/// 当队伍开始侦测到隐身实体时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityDetected {
+    /// The stealthed entity.
+    pub entity: Entity,
+    /// Team that detected it.
+    pub team: FogTeam,
+}
+
+/// Message sent when a team stops detecting a [`Stealth`] entity.
+/// 当队伍不再侦测到隐身实体时发送的消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityUndetected {
+    /// The stealthed entity.
+    pub entity: Entity,
+    /// Team that lost it.
+    pub team: FogTeam,
+}
+
+/// Re-evaluates which teams detect each [`Stealth`] entity and reports changes.
+/// 重新计算每个隐身实体被哪些队伍侦测到，并报告变化
+///
+/// Teams sharing vision with the entity's own team always detect it and get no
+/// messages. With fog disabled every entity counts as detected.
+pub(crate) fn update_stealth_detection(
+    fog: FogQuery,
+    mut stealthed: Query<(Entity, &GlobalTransform, &mut Stealth, Option<&FogTeam>)>,
+    mut detected_writer: MessageWriter<EntityDetected>,
+    mut undetected_writer: MessageWriter<EntityUndetected>,
+) {
+    if stealthed.is_empty() {
+        return;
+    }
+
+    let sampler = fog.sampler();
+    let settings = fog.settings();
+    let teams = || (0..settings.effective_team_count()).map(|index| FogTeam(index as u8));
+
+    for (entity, transform, mut stealth, own_team) in stealthed.iter_mut() {
+        let position = settings.world_to_fog(transform.translation());
+        let own_team = own_team.copied();
+
+        let detected = teams()
+            .filter(|&team| sampler.sees_entity(team, position, own_team, Some(&*stealth)))
+            .fold(0, |mask, team| mask | (1 << team.0));
+        if detected == stealth.detected {
+            continue;
+        }
+
+        let previous = stealth.detected;
+        stealth.detected = detected;
+        for team in teams().filter(|&team| !sampler.is_friendly(team, own_team)) {
+            let bit = 1 << team.0;
+            if detected & bit != 0 && previous & bit == 0 {
+                detected_writer.write(EntityDetected { entity, team });
+            } else if detected & bit == 0 && previous & bit != 0 {
+                undetected_writer.write(EntityUndetected { entity, team });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::message::Messages;
+
+    fn drain<M: Message>(app: &mut App) -> Vec<M> {
+        app.world_mut()
+            .resource_mut::<Messages<M>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn test_detection_messages_fire_once_per_transition() {
+        let mut alliances = FogAlliances::default();
+        alliances.set_allied(FogTeam(1), FogTeam(2), true);
+        let mut app = App::new();
+        app.insert_resource(FogMapSettings {
+            team_count: 3,
+            ..Default::default()
+        })
+        .insert_resource(alliances)
+        .init_resource::<ChunkStateCache>()
+        .init_resource::<ActiveReveals>()
+        .add_message::<EntityDetected>()
+        .add_message::<EntityUndetected>()
+        .add_systems(Update, update_stealth_detection);
+
+        let world = app.world_mut();
+        // Team 0 sees the origin, but only detects stealth around its tower
+        world.spawn((GlobalTransform::IDENTITY, VisionSource::circle(100.0)));
+        world.spawn((
+            GlobalTransform::from_xyz(500.0, 0.0, 0.0),
+            VisionSource::circle(100.0),
+            Detection::new(1),
+        ));
+        let cloaked = world
+            .spawn((
+                GlobalTransform::from_xyz(10.0, 0.0, 0.0),
+                Stealth::new(1),
+                FogTeam(1),
+            ))
+            .id();
+
+        // Visible but undetected; the own and allied teams detect it silently
+        app.update();
+        assert!(drain::<EntityDetected>(&mut app).is_empty());
+        assert!(drain::<EntityUndetected>(&mut app).is_empty());
+        let stealth = app.world().get::<Stealth>(cloaked).unwrap();
+        assert!(!stealth.is_detected_by(FogTeam(0)));
+        assert!(stealth.is_detected_by(FogTeam(1)) && stealth.is_detected_by(FogTeam(2)));
+
+        // Walking up to the tower
+        *app.world_mut().get_mut::<GlobalTransform>(cloaked).unwrap() =
+            GlobalTransform::from_xyz(500.0, 0.0, 0.0);
+        app.update();
+        assert_eq!(
+            drain::<EntityDetected>(&mut app),
+            vec![EntityDetected {
+                entity: cloaked,
+                team: FogTeam(0)
+            }]
+        );
+        app.update();
+        assert!(drain::<EntityDetected>(&mut app).is_empty());
+        assert!(drain::<EntityUndetected>(&mut app).is_empty());
+
+        // And back out of its range
+        *app.world_mut().get_mut::<GlobalTransform>(cloaked).unwrap() =
+            GlobalTransform::from_xyz(10.0, 0.0, 0.0);
+        app.update();
+        assert_eq!(
+            drain::<EntityUndetected>(&mut app),
+            vec![EntityUndetected {
+                entity: cloaked,
+                team: FogTeam(0)
+            }]
+        );
+        assert!(drain::<EntityDetected>(&mut app).is_empty());
+        app.update();
+        assert!(drain::<EntityUndetected>(&mut app).is_empty());
+    }
+}
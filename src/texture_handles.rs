@@ -34,3 +34,11 @@ pub struct SnapshotTempTexture {
     /// Handle to temporary 2D texture used as intermediate render target for snapshots.
     pub handle: Handle<Image>,
 }
+
+/// GPU texture array handle for tile-grid vision blocker data.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct VisionBlockerTextureArray {
+    /// Handle to 3D texture array where each layer stores one chunk's blocker cells.
+    pub handle: Handle<Image>,
+}
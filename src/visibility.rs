@@ -0,0 +1,816 @@
+//! CPU-side per-point visibility matching the fog compute shader.
+//! 与雾效计算着色器一致的 CPU 端逐点可见性
+//!
+//! The compute shader writes visibility per texel and never reads it back. Gameplay
+//! code that needs an answer for a single world position (hiding enemy units, fog
+//! queries) uses the functions here instead. They follow `fog_compute.wgsl` step by
+//! step: the same shape falloffs, occlusion rules, intensity and blending, so a
+//! point is reported visible exactly where the overlay shows it uncovered.
+
+use crate::line_of_sight::{OccluderSegment, collect_occluder_segments, is_ray_blocked};
+use crate::prelude::*;
+use bevy_ecs::system::SystemParam;
+use bevy_math::{Rect, Vec2};
+use bevy_transform::components::GlobalTransform;
+
+/// Visibility above which a point counts as seen, same as
+/// `EXPLORATION_VISIBILITY_THRESHOLD` in the shader.
+pub(crate) const VISIBILITY_THRESHOLD: f32 = 0.05;
+
+/// An enabled vision source sampled at its world position.
+/// 在世界位置上采样的启用视野源
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SourceSample<'a> {
+    pub position: Vec2,
+    pub team: FogTeam,
+    pub source: &'a VisionSource,
+    /// [`Detection`] level of the source, `0` without the component.
+    pub detection: u8,
+}
+
+/// Unoccluded visibility of `point` from a single source, before intensity.
+/// 单个视野源对 `point` 的未遮挡可见性（未应用强度）
+pub(crate) fn shape_visibility(source: &VisionSource, source_pos: Vec2, point: Vec2) -> f32 {
+    let radius = source.outer_radius();
+    let transition_ratio = source.transition_ratio.max(0.01);
+    let inner_radius = radius * (1.0 - transition_ratio);
+    let offset = point - source_pos;
+    let dist = offset.length();
+
+    let falloff = &source.falloff;
+
+    // Fade from `inner_radius` out to `radius`, shared by round shapes
+    // 从 `inner_radius` 到 `radius` 的衰减，圆形类形状共用
+    let radial_falloff = |dist: f32| {
+        if dist <= inner_radius {
+            1.0
+        } else if dist >= radius {
+            0.0
+        } else {
+            falloff.apply((dist - inner_radius) / (radius - inner_radius))
+        }
+    };
+
+    // Fade across a band of `band` width outside a polygon edge
+    // 在多边形边缘外宽度为 `band` 的过渡带内衰减
+    let edge_falloff = |edge_dist: f32, band: f32| {
+        if edge_dist <= 0.0 {
+            1.0
+        } else if edge_dist <= band {
+            falloff.apply(edge_dist / band)
+        } else {
+            0.0
+        }
+    };
+
+    match &source.shape {
+        VisionShape::Circle => radial_falloff(dist),
+        VisionShape::Cone => {
+            if dist > radius {
+                return 0.0;
+            }
+            let forward = Vec2::new(source.direction.cos(), source.direction.sin());
+            let dot = offset.normalize_or_zero().dot(forward);
+            let half_angle_cos = (source.angle * 0.5).cos();
+            if dot < half_angle_cos {
+                return 0.0;
+            }
+            // The side edges fade over the outer 20% of the half-angle cosine range
+            // 侧边在半角余弦范围的外侧 20% 内衰减
+            let angle_t = (dot - half_angle_cos) / (1.0 - half_angle_cos);
+            let angle_visibility = if angle_t >= 0.2 {
+                1.0
+            } else {
+                falloff.apply(1.0 - angle_t / 0.2)
+            };
+            radial_falloff(dist) * angle_visibility
+        }
+        VisionShape::Square => {
+            // Rotate into the square's local frame / 旋转到正方形的局部坐标系
+            let local = Vec2::from_angle(-source.direction).rotate(offset);
+            let outside = (local.abs() - Vec2::splat(radius)).max(Vec2::ZERO);
+            edge_falloff(outside.length(), radius * transition_ratio)
+        }
+        VisionShape::Annulus {
+            inner_radius: min_range,
+        } => {
+            // Fade in over the same band width past the blind inner disc
+            // 在内侧盲区外以相同的过渡带宽度淡入
+            let band = radius * transition_ratio;
+            let inner_fade = if dist <= *min_range {
+                0.0
+            } else if dist >= min_range + band {
+                1.0
+            } else {
+                falloff.apply(1.0 - (dist - min_range) / band)
+            };
+            radial_falloff(dist) * inner_fade
+        }
+        VisionShape::Capsule { length } => {
+            let end = source_pos + Vec2::from_angle(source.direction) * *length;
+            radial_falloff(point_segment_distance(point, source_pos, end))
+        }
+        VisionShape::Polygon { vertices } => {
+            if vertices.len() < 3 {
+                return 0.0;
+            }
+            let local = Vec2::from_angle(-source.direction).rotate(offset);
+            if polygon_contains(vertices, local) {
+                return 1.0;
+            }
+            let edge_dist = polygon_edges(vertices)
+                .map(|(a, b)| point_segment_distance(local, a, b))
+                .fold(f32::INFINITY, f32::min);
+            edge_falloff(edge_dist, radius * transition_ratio)
+        }
+    }
+}
+
+/// Efficiently tests if a circle intersects with an axis-aligned rectangle.
+/// 高效测试圆形是否与轴对齐矩形相交。
+///
+/// This function implements the standard circle-rectangle intersection algorithm
+/// by finding the closest point on the rectangle to the circle center and
+/// comparing the distance to the circle radius.
+///
+/// # Algorithm
+/// 1. Clamp circle center to rectangle bounds (finds closest point)
+/// 2. Calculate squared distance from circle center to closest point
+/// 3. Compare with squared radius (avoids expensive sqrt operation)
+///
+/// # Parameters
+/// - `circle_center`: Center position of the vision source
+/// - `range_sq`: Squared radius of the vision source (for performance)
+/// - `rect_min`: Bottom-left corner of the chunk rectangle
+/// - `rect_max`: Top-right corner of the chunk rectangle
+///
+/// # Performance
+/// - **Complexity**: O(1) - Constant time geometric calculation
+/// - **Optimizations**: Uses squared distance to avoid sqrt
+/// - **Precision**: Works with f32 precision, suitable for game coordinates
+///
+/// # Returns
+/// `true` if the circle intersects or overlaps the rectangle, `false` otherwise
+///
+/// # Mathematical Foundation
+/// Based on the principle that the closest point on a rectangle to any external
+/// point can be found by clamping coordinates to the rectangle's bounds.
+pub(crate) fn circle_intersects_rect(
+    circle_center: Vec2,
+    range_sq: f32,
+    rect_min: Vec2,
+    rect_max: Vec2,
+) -> bool {
+    // Clamp the circle center to the rectangle's bounds
+    // 将圆心限制在矩形边界内
+    let closest_x = circle_center.x.clamp(rect_min.x, rect_max.x);
+    let closest_y = circle_center.y.clamp(rect_min.y, rect_max.y);
+
+    // Calculate the distance from the circle center to the closest point
+    // 计算圆心到最近点的距离
+    let dx = circle_center.x - closest_x;
+    let dy = circle_center.y - closest_y;
+
+    // If the distance is less than or equal to the radius, they intersect
+    // 如果距离小于等于半径，则相交
+    (dx * dx + dy * dy) <= range_sq
+}
+
+/// Distance from the source beyond which its shape gives no visibility.
+/// 超出该距离后视野源的形状不再提供可见性
+pub(crate) fn shape_reach(source: &VisionSource) -> f32 {
+    let transition_ratio = source.transition_ratio.max(0.01);
+    match &source.shape {
+        VisionShape::Circle | VisionShape::Cone | VisionShape::Annulus { .. } => source.range,
+        VisionShape::Capsule { length } => length.max(0.0) + source.range,
+        // Rotated corners plus the outer transition band / 旋转后的角点加外侧过渡带
+        VisionShape::Square => source.range * (std::f32::consts::SQRT_2 + transition_ratio),
+        VisionShape::Polygon { .. } => source.outer_radius() * (1.0 + transition_ratio),
+    }
+}
+
+/// Returns true if the source's shape can give visibility anywhere in `rect_min..rect_max`.
+/// 如果视野源的形状可能照亮矩形内任意位置则返回 true
+///
+/// Ignores occlusion. Boundary contact counts as intersecting, so the test never
+/// culls a rectangle the shader would light.
+pub(crate) fn shape_intersects_rect(
+    source: &VisionSource,
+    source_pos: Vec2,
+    rect_min: Vec2,
+    rect_max: Vec2,
+) -> bool {
+    let range_sq = source.range * source.range;
+    let transition_ratio = source.transition_ratio.max(0.01);
+    match &source.shape {
+        VisionShape::Circle => circle_intersects_rect(source_pos, range_sq, rect_min, rect_max),
+        VisionShape::Annulus { inner_radius } => {
+            // Skip rectangles entirely inside the blind inner disc
+            // 跳过完全位于内侧盲区内的矩形
+            let farthest = rect_corners(rect_min, rect_max)
+                .into_iter()
+                .map(|corner| corner.distance(source_pos))
+                .fold(0.0, f32::max);
+            farthest > *inner_radius
+                && circle_intersects_rect(source_pos, range_sq, rect_min, rect_max)
+        }
+        VisionShape::Capsule { length } => {
+            let end = source_pos + Vec2::from_angle(source.direction) * *length;
+            polygon_distance(&[source_pos, end], &rect_corners(rect_min, rect_max)) <= source.range
+        }
+        VisionShape::Cone => {
+            if !circle_intersects_rect(source_pos, range_sq, rect_min, rect_max) {
+                return false;
+            }
+            // Split the sector into two halves that are each convex
+            // 将扇形拆分为两个各自为凸的半扇形
+            let half_angle = (source.angle * 0.5).clamp(0.0, std::f32::consts::PI);
+            let rect = rect_corners(rect_min, rect_max);
+            [
+                (source.direction - half_angle, source.direction),
+                (source.direction, source.direction + half_angle),
+            ]
+            .into_iter()
+            .any(|(start, end)| {
+                let (start, end) = (Vec2::from_angle(start), Vec2::from_angle(end));
+                let wedge = clip_half_plane(&rect, |p| start.perp_dot(p - source_pos));
+                let wedge = clip_half_plane(&wedge, |p| (p - source_pos).perp_dot(end));
+                !wedge.is_empty() && polygon_distance(&[source_pos], &wedge) <= source.range
+            })
+        }
+        VisionShape::Square => {
+            let rotation = Vec2::from_angle(source.direction);
+            let square = [
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(1.0, -1.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(-1.0, 1.0),
+            ]
+            .map(|corner| source_pos + rotation.rotate(corner * source.range));
+            let transition = source.range * transition_ratio;
+            polygon_distance(&square, &rect_corners(rect_min, rect_max)) <= transition
+        }
+        VisionShape::Polygon { vertices } => {
+            if vertices.len() < 3 {
+                return false;
+            }
+            let rotation = Vec2::from_angle(source.direction);
+            let polygon: Vec<Vec2> = vertices
+                .iter()
+                .map(|v| source_pos + rotation.rotate(*v))
+                .collect();
+            let transition = source.outer_radius() * transition_ratio;
+            polygon_distance(&polygon, &rect_corners(rect_min, rect_max)) <= transition
+        }
+    }
+}
+
+/// Corners of an axis-aligned rectangle in counter-clockwise order.
+fn rect_corners(min: Vec2, max: Vec2) -> [Vec2; 4] {
+    [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+}
+
+/// Keeps the part of a convex polygon where `side(p) >= 0` (Sutherland-Hodgman).
+fn clip_half_plane(polygon: &[Vec2], side: impl Fn(Vec2) -> f32) -> Vec<Vec2> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let (side_a, side_b) = (side(a), side(b));
+        if side_a >= 0.0 {
+            clipped.push(a);
+        }
+        if (side_a >= 0.0) != (side_b >= 0.0) {
+            clipped.push(a + (b - a) * (side_a / (side_a - side_b)));
+        }
+    }
+    clipped
+}
+
+/// Edges of a closed polygon, the last vertex connecting back to the first.
+fn polygon_edges(polygon: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    (0..polygon.len()).map(|i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+}
+
+/// Returns true if `p` lies inside a simple polygon (crossing-number rule, as in the shader).
+fn polygon_contains(polygon: &[Vec2], p: Vec2) -> bool {
+    polygon.len() >= 3
+        && polygon_edges(polygon)
+            .filter(|(a, b)| {
+                (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            })
+            .count()
+            % 2
+            == 1
+}
+
+fn point_segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_squared();
+    let t = if len_sq > 0.0 {
+        ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
+
+fn segment_distance(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> f32 {
+    let crosses = (a1 - a0).perp_dot(b0 - a0) * (a1 - a0).perp_dot(b1 - a0) <= 0.0
+        && (b1 - b0).perp_dot(a0 - b0) * (b1 - b0).perp_dot(a1 - b0) <= 0.0;
+    if crosses {
+        return 0.0;
+    }
+    point_segment_distance(a0, b0, b1)
+        .min(point_segment_distance(a1, b0, b1))
+        .min(point_segment_distance(b0, a0, a1))
+        .min(point_segment_distance(b1, a0, a1))
+}
+
+/// Distance between two simple polygons (a single point is a one-vertex polygon).
+/// 两个简单多边形之间的距离（单个点视为只有一个顶点的多边形）
+fn polygon_distance(a: &[Vec2], b: &[Vec2]) -> f32 {
+    if a.iter().any(|&p| polygon_contains(b, p)) || b.iter().any(|&p| polygon_contains(a, p)) {
+        return 0.0;
+    }
+    polygon_edges(a)
+        .flat_map(|(a0, a1)| polygon_edges(b).map(move |(b0, b1)| segment_distance(a0, a1, b0, b1)))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Blended visibility of `point` for `viewer`, in `0.0..=1.0`.
+/// 对 `viewer` 而言 `point` 的混合可见性，范围 `0.0..=1.0`
+///
+/// Only sources whose team shares vision with `viewer` contribute, as in the shader,
+/// and only those with a detection level of at least `detection`.
+pub(crate) fn point_visibility(
+    point: Vec2,
+    viewer: FogTeam,
+    detection: u8,
+    sources: &[SourceSample],
+    segments: &[OccluderSegment],
+    grid: Option<&VisionBlockerGrid>,
+    alliances: &FogAlliances,
+) -> f32 {
+    let mut visibility = 0.0;
+    for sample in sources {
+        let source = sample.source;
+        if !source.reveals
+            || sample.detection < detection
+            || source.outer_radius() <= 0.001
+            || source.intensity <= 0.001
+            || !alliances.shares_vision(viewer, sample.team)
+        {
+            continue;
+        }
+
+        let mut single = shape_visibility(source, sample.position, point);
+        if single > 0.0 && is_ray_blocked(sample.position, point, source.eye_height, segments, grid)
+        {
+            single = 0.0;
+        }
+        single *= source.intensity.max(0.01);
+
+        visibility += single * (1.0 - visibility);
+        if visibility >= 0.999 {
+            return 1.0;
+        }
+    }
+    visibility
+}
+
+/// Vision sources and blockers gathered once for many visibility lookups.
+/// 为多次可见性查询一次性收集的视野源与阻挡物
+///
+/// Returned by [`FogQuery::sampler`]. Each lookup costs O(V + O) where V=vision
+/// sources, O=occluder edges, without gathering them again. Results match the
+/// [`FogQuery`] methods of the same name, including `enabled` and `world_bounds`.
+pub struct VisionSampler<'a> {
+    sources: Vec<SourceSample<'a>>,
+    segments: Vec<OccluderSegment>,
+    grid: Option<&'a VisionBlockerGrid>,
+    alliances: &'a FogAlliances,
+    /// With fog disabled everything is visible.
+    enabled: bool,
+    /// Vision stops at `FogMapSettings::world_bounds`.
+    world_bounds: Option<Rect>,
+}
+
+impl VisionSampler<'_> {
+    /// Blended visibility of `point` for `viewer`, in `0.0..=1.0`.
+    /// `viewer` 在 `point` 处的混合可见度，范围 `0.0..=1.0`
+    pub fn visibility(&self, viewer: FogTeam, point: Vec2) -> f32 {
+        self.detection_visibility(viewer, point, 0)
+    }
+
+    /// Blended visibility of `point` for `viewer` from sources detecting stealth `level`.
+    /// `viewer` 中能侦测隐身等级 `level` 的视野源在 `point` 处的混合可见度
+    pub fn detection_visibility(&self, viewer: FogTeam, point: Vec2, level: u8) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        if self
+            .world_bounds
+            .is_some_and(|bounds| !bounds.contains(point))
+        {
+            return 0.0;
+        }
+        point_visibility(
+            point,
+            viewer,
+            level,
+            &self.sources,
+            &self.segments,
+            self.grid,
+            self.alliances,
+        )
+    }
+
+    /// Returns true if `point` is currently visible to `viewer`.
+    /// 如果 `point` 当前对 `viewer` 可见则返回 true
+    pub fn is_visible(&self, viewer: FogTeam, point: Vec2) -> bool {
+        self.visibility(viewer, point) > VISIBILITY_THRESHOLD
+    }
+
+    /// Returns true if a [`Stealth`] entity of `level` at `point` is detected by `viewer`.
+    /// 如果位于 `point`、隐身等级为 `level` 的实体被 `viewer` 侦测到则返回 true
+    pub fn is_detected(&self, viewer: FogTeam, point: Vec2, level: u8) -> bool {
+        self.detection_visibility(viewer, point, level) > VISIBILITY_THRESHOLD
+    }
+
+    /// Returns true if `viewer` sees an entity of `own_team` standing at `point`.
+    /// 如果 `viewer` 能看到位于 `point`、属于 `own_team` 的实体则返回 true
+    ///
+    /// Teams sharing vision with `own_team` always see the entity. Otherwise `point`
+    /// must be visible to `viewer`'s sources that detect the entity's [`Stealth`]
+    /// level, evaluated against this frame's sources.
+    pub fn sees_entity(
+        &self,
+        viewer: FogTeam,
+        point: Vec2,
+        own_team: Option<FogTeam>,
+        stealth: Option<&Stealth>,
+    ) -> bool {
+        self.is_friendly(viewer, own_team)
+            || self.is_detected(viewer, point, stealth.map_or(0, |stealth| stealth.level))
+    }
+
+    /// Returns true if `viewer` shares the vision of `own_team`.
+    pub(crate) fn is_friendly(&self, viewer: FogTeam, own_team: Option<FogTeam>) -> bool {
+        own_team.is_some_and(|own| self.alliances.shares_vision(viewer, own))
+    }
+}
+
+/// System parameter answering "is this world point visible / explored?" on the CPU.
+/// 在 CPU 上回答“该世界坐标是否可见/已探索”的系统参数
+///
+/// Visibility uses the same shapes, transition ratios, intensity, occlusion and
+/// blending as `fog_compute.wgsl`, evaluated for this frame's vision sources and
+/// running [`RevealArea`]s.
+/// Explored state comes from [`ChunkStateCache`] and is therefore per chunk: a
+/// position counts as explored as soon as any part of its chunk was, even where
+/// the fog texture itself is still unexplored. Use [`RequestExploredFraction`]
+/// for texel-accurate exploration of an area.
+/// Points outside `FogMapSettings::world_bounds` are never visible or explored.
+/// Positions are fog coordinates; convert entity positions with `FogMapSettings::world_to_fog`
+/// when a ground plane other than XY or a `fog_transform` is used.
+///
+/// Methods without a team argument answer for the team rendered by the
+/// `FogOfWarCamera` (team `0` if the camera has no [`FogTeam`]). With several fog
+/// cameras, that is the first one; pass a team explicitly for the others.
+///
+/// Every call gathers the vision sources and occluders again, so the cost is
+/// O(V + O) per lookup plus O(V + O) to gather, where V=vision sources,
+/// O=occluder edges. To test many positions in one system, gather them once with
+/// [`FogQuery::sampler`] and query the returned [`VisionSampler`]:
+///
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// # #[derive(Component)]
+/// # struct Enemy;
+/// fn count_spotted(fog: FogQuery, enemies: Query<&GlobalTransform, With<Enemy>>) {
+///     let viewer = fog.viewer_team();
+///     let sampler = fog.sampler();
+///     let spotted = enemies
+///         .iter()
+///         .filter(|transform| sampler.is_visible(viewer, transform.translation().truncate()))
+///         .count();
+///     info!("{spotted} enemies spotted");
+/// }
+/// ```
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// #[derive(Component)]
+/// struct Enemy;
+///
+/// fn report_spotted(fog: FogQuery, enemies: Query<&GlobalTransform, With<Enemy>>) {
+///     for transform in &enemies {
+///         if fog.is_visible(transform.translation().truncate()) {
+///             info!("Enemy spotted");
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct FogQuery<'w, 's> {
+    settings: Res<'w, FogMapSettings>,
+    cache: Res<'w, ChunkStateCache>,
+    alliances: Res<'w, FogAlliances>,
+    blocker_grid: Option<Res<'w, VisionBlockerGrid>>,
+    fog_cameras: Query<'w, 's, Option<&'static FogTeam>, With<FogOfWarCamera>>,
+    vision_sources: Query<
+        'w,
+        's,
+        (
+            &'static GlobalTransform,
+            &'static VisionSource,
+            Option<&'static FogTeam>,
+            Option<&'static Detection>,
+        ),
+    >,
+    reveals: Res<'w, ActiveReveals>,
+    occluders: Query<'w, 's, (&'static GlobalTransform, &'static VisionOccluder)>,
+}
+
+impl FogQuery<'_, '_> {
+    /// Team rendered by the (first) `FogOfWarCamera`.
+    /// （第一个）`FogOfWarCamera` 所渲染的队伍
+    pub fn viewer_team(&self) -> FogTeam {
+        self.fog_cameras
+            .iter()
+            .next()
+            .flatten()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Current visibility at `pos` for the viewing team, in `0.0..=1.0`.
+    /// 观察队伍在 `pos` 处的当前可见度
+    pub fn visibility_at(&self, pos: Vec2) -> f32 {
+        self.visibility_at_for(self.viewer_team(), pos)
+    }
+
+    /// Current visibility at `pos` for `team`, in `0.0..=1.0`.
+    /// `team` 在 `pos` 处的当前可见度
+    pub fn visibility_at_for(&self, team: FogTeam, pos: Vec2) -> f32 {
+        self.sampler().visibility(team, pos)
+    }
+
+    /// Returns true if `pos` is currently visible to the viewing team.
+    /// 如果 `pos` 当前对观察队伍可见则返回 true
+    pub fn is_visible(&self, pos: Vec2) -> bool {
+        self.is_visible_for(self.viewer_team(), pos)
+    }
+
+    /// Returns true if `pos` is currently visible to `team`.
+    /// 如果 `pos` 当前对 `team` 可见则返回 true
+    pub fn is_visible_for(&self, team: FogTeam, pos: Vec2) -> bool {
+        self.sampler().is_visible(team, pos)
+    }
+
+    /// Returns true if a [`Stealth`] entity of `level` at `pos` is detected by `team`,
+    /// i.e. visible to its sources with a [`Detection`] level of at least `level`.
+    /// 如果位于 `pos`、隐身等级为 `level` 的实体被 `team` 侦测到则返回 true
+    pub fn is_detected_for(&self, team: FogTeam, pos: Vec2, level: u8) -> bool {
+        self.sampler().is_detected(team, pos, level)
+    }
+
+    /// Returns true if the chunk containing `pos` was explored by the viewing team
+    /// or an ally sharing explored history.
+    /// 如果 `pos` 所在区块已被观察队伍或共享探索的盟友探索则返回 true
+    pub fn is_explored(&self, pos: Vec2) -> bool {
+        self.is_explored_for(self.viewer_team(), pos)
+    }
+
+    /// Returns true if the chunk containing `pos` was explored by `team` or an ally
+    /// sharing explored history.
+    /// 如果 `pos` 所在区块已被 `team` 或共享探索的盟友探索则返回 true
+    ///
+    /// This is a chunk-level test; the explored texels live on the GPU and are not
+    /// read back for single lookups.
+    pub fn is_explored_for(&self, team: FogTeam, pos: Vec2) -> bool {
+        !self.settings.enabled
+            || (self.settings.is_in_world(pos)
+                && self.cache.is_explored_with_allies(
+                    team,
+                    self.settings.world_to_chunk_coords(pos),
+                    &self.alliances,
+                ))
+    }
+
+    /// Gathers this frame's enabled sources and blockers for repeated lookups.
+    /// 收集本帧启用的视野源与阻挡物，以供多次查询
+    pub fn sampler(&self) -> VisionSampler<'_> {
+        VisionSampler {
+            sources: self
+                .vision_sources
+                .iter()
+                .filter_map(|(transform, source, team, detection)| {
+                    let team = team.copied().unwrap_or_default();
+                    (source.enabled && self.settings.contains_team(team)).then(|| SourceSample {
+                        position: self.settings.world_to_fog(transform.translation()),
+                        team,
+                        source,
+                        detection: detection.map_or(0, |detection| detection.level),
+                    })
+                })
+                .chain(
+                    self.reveals
+                        .active(&self.settings)
+                        .map(|reveal| SourceSample {
+                            position: reveal.area().position,
+                            team: reveal.area().team,
+                            source: reveal.source(),
+                            detection: reveal.area().detection,
+                        }),
+                )
+                .collect(),
+            segments: collect_occluder_segments(&self.settings, self.occluders.iter()),
+            grid: self.blocker_grid.as_deref(),
+            alliances: &self.alliances,
+            enabled: self.settings.enabled,
+            world_bounds: self.settings.world_bounds,
+        }
+    }
+
+    pub(crate) fn settings(&self) -> &FogMapSettings {
+        &self.settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cone_only_intersects_chunks_it_faces() {
+        let source = VisionSource::cone(100.0, 0.0, std::f32::consts::FRAC_PI_2);
+        let chunk = |min: Vec2| (min, min + Vec2::splat(50.0));
+
+        let (min, max) = chunk(Vec2::new(40.0, -25.0));
+        assert!(shape_intersects_rect(&source, Vec2::ZERO, min, max));
+        // Behind the source: inside the circle but outside the sector
+        let (min, max) = chunk(Vec2::new(-90.0, -25.0));
+        assert!(!shape_intersects_rect(&source, Vec2::ZERO, min, max));
+        // Straight up: the 90° cone only reaches 45° from the x axis
+        let (min, max) = chunk(Vec2::new(-25.0, 60.0));
+        assert!(!shape_intersects_rect(&source, Vec2::ZERO, min, max));
+    }
+
+    #[test]
+    fn test_rotated_square_reaches_diagonal_chunks() {
+        let mut source = VisionSource::square(100.0);
+        let (min, max) = (Vec2::new(125.0, -10.0), Vec2::new(135.0, 10.0));
+        assert!(!shape_intersects_rect(&source, Vec2::ZERO, min, max));
+
+        // Rotated by 45°, a corner points along +x out to 100·√2
+        source.direction = std::f32::consts::FRAC_PI_4;
+        assert!(shape_intersects_rect(&source, Vec2::ZERO, min, max));
+        assert!(shape_reach(&source) >= 135.0);
+
+        // The transition band extends past the edge
+        let (min, max) = (Vec2::new(105.0, -10.0), Vec2::new(110.0, 10.0));
+        source.direction = 0.0;
+        assert!(shape_intersects_rect(&source, Vec2::ZERO, min, max));
+    }
+
+    #[test]
+    fn test_polygon_is_rotated_and_softened() {
+        // L-shaped sight area: a concave notch at the top right
+        let source = VisionSource::polygon(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            Vec2::new(100.0, 50.0),
+            Vec2::new(50.0, 50.0),
+            Vec2::new(50.0, 100.0),
+            Vec2::new(0.0, 100.0),
+        ]);
+        assert_eq!(
+            shape_visibility(&source, Vec2::ZERO, Vec2::new(25.0, 75.0)),
+            1.0
+        );
+        assert_eq!(
+            shape_visibility(&source, Vec2::ZERO, Vec2::new(90.0, 90.0)),
+            0.0
+        );
+        // Just outside an edge, within the transition band
+        let soft = shape_visibility(&source, Vec2::ZERO, Vec2::new(105.0, 25.0));
+        assert!(soft > 0.0 && soft < 1.0);
+
+        // A quarter turn maps +x onto +y
+        let rotated = source.clone().with_direction(std::f32::consts::FRAC_PI_2);
+        assert_eq!(
+            shape_visibility(&rotated, Vec2::ZERO, Vec2::new(-25.0, 75.0)),
+            1.0
+        );
+        let (min, max) = (Vec2::new(80.0, 80.0), Vec2::new(95.0, 95.0));
+        assert!(!shape_intersects_rect(&source, Vec2::ZERO, min, max));
+    }
+
+    #[test]
+    fn test_annulus_and_capsule_falloff() {
+        let ring = VisionSource::annulus(50.0, 100.0);
+        assert_eq!(
+            shape_visibility(&ring, Vec2::ZERO, Vec2::new(30.0, 0.0)),
+            0.0
+        );
+        assert_eq!(
+            shape_visibility(&ring, Vec2::ZERO, Vec2::new(75.0, 0.0)),
+            1.0
+        );
+        let inner_edge = shape_visibility(&ring, Vec2::ZERO, Vec2::new(60.0, 0.0));
+        assert!(inner_edge > 0.0 && inner_edge < 1.0);
+        // A chunk inside the blind disc is culled
+        let (min, max) = (Vec2::splat(-20.0), Vec2::splat(20.0));
+        assert!(!shape_intersects_rect(&ring, Vec2::ZERO, min, max));
+
+        let beam = VisionSource::capsule(10.0, 200.0, std::f32::consts::FRAC_PI_2);
+        assert_eq!(
+            shape_visibility(&beam, Vec2::ZERO, Vec2::new(0.0, 150.0)),
+            1.0
+        );
+        assert_eq!(
+            shape_visibility(&beam, Vec2::ZERO, Vec2::new(30.0, 150.0)),
+            0.0
+        );
+        assert!(shape_reach(&beam) >= 210.0);
+        let (min, max) = (Vec2::new(-5.0, 180.0), Vec2::new(5.0, 190.0));
+        assert!(shape_intersects_rect(&beam, Vec2::ZERO, min, max));
+    }
+
+    #[test]
+    fn test_falloff_curves_shape_the_transition_zone() {
+        // Transition zone of a 100-unit circle runs from 80 to 100
+        let at = |falloff: VisionFalloff, dist: f32| {
+            let source = VisionSource::circle(100.0).with_falloff(falloff);
+            shape_visibility(&source, Vec2::ZERO, Vec2::new(dist, 0.0))
+        };
+        assert!((at(VisionFalloff::Linear, 90.0) - 0.5).abs() < 1e-5);
+        assert!((at(VisionFalloff::Smoothstep, 85.0) - 0.84375).abs() < 1e-5);
+        assert!(at(VisionFalloff::Exponential { sharpness: 4.0 }, 90.0) < 0.2);
+        assert_eq!(at(VisionFalloff::Step, 99.0), 1.0);
+        let curve = VisionFalloff::Curve {
+            samples: vec![1.0, 0.9, 0.3, 0.0],
+        };
+        assert!((at(curve.clone(), 90.0) - 0.6).abs() < 1e-5);
+
+        // Nothing leaks past the outer edge, whatever the curve ends on
+        let flat = VisionFalloff::Curve { samples: vec![1.0] };
+        assert_eq!(at(flat, 101.0), 0.0);
+        assert_eq!(at(curve, 50.0), 1.0);
+    }
+
+    #[test]
+    fn test_explore_only_sources_add_no_visibility() {
+        let alliances = FogAlliances::default();
+        let visibility = |source: &VisionSource| {
+            let sample = SourceSample {
+                position: Vec2::ZERO,
+                team: FogTeam(0),
+                source,
+                detection: 0,
+            };
+            point_visibility(Vec2::ZERO, FogTeam(0), 0, &[sample], &[], None, &alliances)
+        };
+        assert_eq!(visibility(&VisionSource::circle(50.0)), 1.0);
+        assert_eq!(
+            visibility(&VisionSource::circle(50.0).with_reveals(false)),
+            0.0
+        );
+        assert_eq!(
+            visibility(&VisionSource::circle(50.0).with_explores(false)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_stealth_needs_sufficient_detection() {
+        let alliances = FogAlliances::default();
+        let source = VisionSource::circle(100.0);
+        let samples = [
+            SourceSample {
+                position: Vec2::ZERO,
+                team: FogTeam(0),
+                source: &source,
+                detection: 0,
+            },
+            SourceSample {
+                position: Vec2::new(300.0, 0.0),
+                team: FogTeam(0),
+                source: &source,
+                detection: 2,
+            },
+        ];
+        let visibility = |point: Vec2, level: u8| {
+            point_visibility(point, FogTeam(0), level, &samples, &[], None, &alliances)
+        };
+
+        assert_eq!(visibility(Vec2::ZERO, 0), 1.0);
+        assert_eq!(visibility(Vec2::ZERO, 1), 0.0);
+        assert_eq!(visibility(Vec2::new(300.0, 0.0), 2), 1.0);
+        assert_eq!(visibility(Vec2::new(300.0, 0.0), 3), 0.0);
+    }
+}
@@ -0,0 +1,296 @@
+//! Tile-grid vision blockers streamed per chunk into the fog compute pass.
+//! 按区块流式传输到雾效计算通道的网格视野阻挡数据
+//!
+//! Tilemap levels usually describe walls as cells rather than shapes. A
+//! [`VisionBlockerGrid`] stores one byte per cell, grouped by the same chunk
+//! coordinates used by [`FogMapSettings`], so blocker data can be loaded and
+//! unloaded chunk by chunk alongside fog data.
+//!
+//! # GPU Upload
+//! Each GPU-resident chunk's cells are written into the layer of
+//! [`VisionBlockerTextureArray`] matching that chunk's `fog_layer_index`. The
+//! compute shader ray-marches from every vision source to every texel through
+//! this texture and drops visibility once it crosses a cell taller than the
+//! source's eye height.
+
+use crate::prelude::*;
+use bevy_asset::{Assets, RenderAssetUsages};
+use bevy_image::Image;
+use bevy_math::{IVec2, UVec2, Vec2};
+use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use std::collections::HashMap;
+
+/// Per-chunk grid of vision blocking cells.
+/// 按区块划分的视野阻挡网格
+///
+/// Each cell holds a `u8` height: `0` is open ground, `255` is a wall that blocks
+/// every source, and values in between only block sources whose
+/// [`VisionSource::eye_height`] is lower than the cell. This gives the usual RTS
+/// high-ground rule: a unit on a cliff sees past low walls while units below
+/// cannot see up. Chunks that have no entry are treated as fully open.
+///
+/// # Example
+/// ```rust,no_run
+/// # use bevy::prelude::*;
+/// # use bevy_fog_of_war::prelude::*;
+/// fn setup(mut commands: Commands, settings: Res<FogMapSettings>) {
+///     let mut grid = VisionBlockerGrid::new(&settings, 16);
+///     grid.set_blocked(Vec2::new(40.0, 40.0), true);
+///     commands.insert_resource(grid);
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct VisionBlockerGrid {
+    /// Chunk size in world units, copied from `FogMapSettings` on creation.
+    chunk_size: UVec2,
+
+    /// Number of cells along each axis of a chunk.
+    cells_per_chunk: UVec2,
+
+    /// Row-major cell data per chunk (`cells_per_chunk.x * cells_per_chunk.y` bytes).
+    chunks: HashMap<IVec2, Vec<u8>>,
+}
+
+impl VisionBlockerGrid {
+    /// Creates an empty grid whose cells are `cell_size` world units wide.
+    /// `cell_size` should divide `settings.chunk_size` evenly.
+    pub fn new(settings: &FogMapSettings, cell_size: u32) -> Self {
+        let cell_size = cell_size.max(1);
+        Self {
+            chunk_size: settings.chunk_size,
+            cells_per_chunk: (settings.chunk_size / cell_size).max(UVec2::ONE),
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Number of cells along each axis of a chunk.
+    pub fn cells_per_chunk(&self) -> UVec2 {
+        self.cells_per_chunk
+    }
+
+    /// Size of a single cell in world units.
+    pub fn cell_size(&self) -> Vec2 {
+        self.chunk_size.as_vec2() / self.cells_per_chunk.as_vec2()
+    }
+
+    /// Converts a world position to its chunk coordinates and cell inside that chunk.
+    /// 将世界坐标转换为区块坐标及区块内的单元格坐标
+    pub fn world_to_cell(&self, world_pos: Vec2) -> (IVec2, UVec2) {
+        let global_cell = (world_pos / self.cell_size()).floor().as_ivec2();
+        let cells = self.cells_per_chunk.as_ivec2();
+        let chunk = global_cell.div_euclid(cells);
+        let local = global_cell.rem_euclid(cells).as_uvec2();
+        (chunk, local)
+    }
+
+    /// Returns the blocker value at a world position (`0` if open).
+    pub fn value_at(&self, world_pos: Vec2) -> u8 {
+        let (chunk, cell) = self.world_to_cell(world_pos);
+        self.cell(chunk, cell)
+    }
+
+    /// Returns true if the cell at the world position blocks vision at ground level.
+    pub fn is_blocked(&self, world_pos: Vec2) -> bool {
+        self.value_at(world_pos) != 0
+    }
+
+    /// Returns true if the cell at the world position blocks a viewer with the given eye height.
+    pub fn is_blocked_for(&self, world_pos: Vec2, eye_height: f32) -> bool {
+        Self::value_blocks(self.value_at(world_pos), eye_height)
+    }
+
+    /// Returns true if a cell of the given value blocks a viewer with the given eye height.
+    /// Matches the test performed by the fog compute shader.
+    pub fn value_blocks(value: u8, eye_height: f32) -> bool {
+        value == u8::MAX || (value != 0 && f32::from(value) > eye_height)
+    }
+
+    /// Marks the cell at a world position as a full-height wall or open ground.
+    pub fn set_blocked(&mut self, world_pos: Vec2, blocked: bool) {
+        self.set_value(world_pos, if blocked { u8::MAX } else { 0 });
+    }
+
+    /// Sets the blocker height of the cell at a world position.
+    pub fn set_value(&mut self, world_pos: Vec2, value: u8) {
+        let (chunk, cell) = self.world_to_cell(world_pos);
+        self.set_cell(chunk, cell, value);
+    }
+
+    /// Returns the blocker value of a cell inside a chunk (`0` if open or unloaded).
+    pub fn cell(&self, chunk: IVec2, cell: UVec2) -> u8 {
+        self.chunks
+            .get(&chunk)
+            .and_then(|data| data.get(self.cell_index(cell)))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Sets the blocker value of a cell inside a chunk.
+    pub fn set_cell(&mut self, chunk: IVec2, cell: UVec2, value: u8) {
+        if cell.x >= self.cells_per_chunk.x || cell.y >= self.cells_per_chunk.y {
+            return;
+        }
+        let index = self.cell_index(cell);
+        let len = self.chunk_len();
+        self.chunks.entry(chunk).or_insert_with(|| vec![0; len])[index] = value;
+    }
+
+    /// Returns the row-major cell data of a chunk, if loaded.
+    pub fn chunk_data(&self, chunk: IVec2) -> Option<&[u8]> {
+        self.chunks.get(&chunk).map(Vec::as_slice)
+    }
+
+    /// Replaces the cell data of a chunk, e.g. when streaming a level section in.
+    /// Data is truncated or zero-padded to `cells_per_chunk.x * cells_per_chunk.y` bytes.
+    pub fn insert_chunk(&mut self, chunk: IVec2, mut data: Vec<u8>) {
+        data.resize(self.chunk_len(), 0);
+        self.chunks.insert(chunk, data);
+    }
+
+    /// Unloads the cell data of a chunk, treating it as open afterwards.
+    pub fn remove_chunk(&mut self, chunk: IVec2) -> Option<Vec<u8>> {
+        self.chunks.remove(&chunk)
+    }
+
+    /// Removes all blocker data.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    fn chunk_len(&self) -> usize {
+        (self.cells_per_chunk.x * self.cells_per_chunk.y) as usize
+    }
+
+    fn cell_index(&self, cell: UVec2) -> usize {
+        (cell.y * self.cells_per_chunk.x + cell.x) as usize
+    }
+}
+
+/// Builds the blocker texture array for the given number of cells per chunk.
+/// 为给定的每区块单元格数量构建阻挡纹理数组
+pub(crate) fn create_vision_blocker_image(cells_per_chunk: UVec2) -> Image {
+    let size = Extent3d {
+        width: cells_per_chunk.x,
+        height: cells_per_chunk.y,
+        depth_or_array_layers: MAX_LAYERS,
+    };
+    let data = vec![0u8; (size.width * size.height * size.depth_or_array_layers) as usize];
+    let mut image = Image::new(
+        size,
+        TextureDimension::D2,
+        data,
+        TextureFormat::R8Unorm,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::STORAGE_BINDING // For compute shader read / 用于 compute shader 读取
+        | TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_DST; // For CPU->GPU upload / 用于 CPU->GPU 上传
+    image
+}
+
+/// Writes blocker cells of GPU-resident chunks into the blocker texture array.
+/// 将 GPU 常驻区块的阻挡单元格写入阻挡纹理数组
+///
+/// The texture is only rewritten when the grid changes or a chunk's fog layer
+/// assignment changes, so static levels cost nothing per frame.
+///
+/// # Performance Characteristics
+/// - **Idle Cost**: O(n) layer-map comparison where n = GPU-resident chunks
+/// - **Upload Cost**: Whole texture array re-uploaded on change (cells² × 64 bytes)
+pub(crate) fn sync_vision_blocker_texture(
+    grid: Option<Res<VisionBlockerGrid>>,
+    blocker_texture: Res<VisionBlockerTextureArray>,
+    mut images: ResMut<Assets<Image>>,
+    chunk_query: Query<&FogChunk>,
+    mut uploaded_layers: Local<HashMap<u32, IVec2>>,
+    mut had_grid: Local<bool>,
+) {
+    let Some(grid) = grid else {
+        // Grid removed: clear the texture once / 网格被移除：清空纹理一次
+        if *had_grid {
+            if let Some(mut image) = images.get_mut(&blocker_texture.handle) {
+                *image = create_vision_blocker_image(UVec2::ONE);
+            }
+            uploaded_layers.clear();
+            *had_grid = false;
+        }
+        return;
+    };
+
+    let layers: HashMap<u32, IVec2> = chunk_query
+        .iter()
+        .filter_map(|chunk| chunk.fog_layer_index.map(|layer| (layer, chunk.coords)))
+        .collect();
+
+    if *had_grid && !grid.is_changed() && layers == *uploaded_layers {
+        return;
+    }
+
+    let Some(mut image) = images.get_mut(&blocker_texture.handle) else {
+        return;
+    };
+
+    let cells = grid.cells_per_chunk();
+    if image.texture_descriptor.size.width != cells.x
+        || image.texture_descriptor.size.height != cells.y
+    {
+        *image = create_vision_blocker_image(cells);
+    }
+
+    let layer_len = (cells.x * cells.y) as usize;
+    let mut data = vec![0u8; layer_len * MAX_LAYERS as usize];
+    for (layer, coords) in layers.iter() {
+        if let Some(cells_data) = grid.chunk_data(*coords) {
+            let offset = *layer as usize * layer_len;
+            data[offset..offset + layer_len].copy_from_slice(cells_data);
+        }
+    }
+    image.data = Some(data);
+
+    *uploaded_layers = layers;
+    *had_grid = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_to_cell_handles_negative_coordinates() {
+        let grid = VisionBlockerGrid::new(&FogMapSettings::default(), 16);
+        assert_eq!(grid.cells_per_chunk(), UVec2::splat(16));
+        assert_eq!(
+            grid.world_to_cell(Vec2::new(17.0, 0.5)),
+            (IVec2::ZERO, UVec2::new(1, 0))
+        );
+        assert_eq!(
+            grid.world_to_cell(Vec2::new(-1.0, -257.0)),
+            (IVec2::new(-1, -2), UVec2::new(15, 15))
+        );
+    }
+
+    #[test]
+    fn test_set_and_clear_blocked_cells() {
+        let mut grid = VisionBlockerGrid::new(&FogMapSettings::default(), 32);
+        let pos = Vec2::new(-100.0, 300.0);
+        assert!(!grid.is_blocked(pos));
+        grid.set_blocked(pos, true);
+        assert!(grid.is_blocked(pos));
+        assert!(!grid.is_blocked(pos + Vec2::new(32.0, 0.0)));
+        grid.set_blocked(pos, false);
+        assert!(!grid.is_blocked(pos));
+    }
+
+    #[test]
+    fn test_high_ground_sees_over_low_cells() {
+        let mut grid = VisionBlockerGrid::new(&FogMapSettings::default(), 32);
+        let pos = Vec2::new(10.0, 10.0);
+        grid.set_value(pos, 5);
+        assert!(grid.is_blocked_for(pos, 0.0));
+        assert!(grid.is_blocked_for(pos, 4.9));
+        assert!(!grid.is_blocked_for(pos, 5.0));
+        grid.set_blocked(pos, true);
+        assert!(grid.is_blocked_for(pos, 1000.0));
+    }
+}